@@ -18,6 +18,8 @@ pub struct AuthConfig {
 
     pub TOKEN_KEY: Vec<u8>,
     pub TOKEN_DURATION_SEC: f64,
+    pub EMAIL_TOKEN_DURATION_SEC: f64,
+    pub PWD_RESET_TOKEN_DURATION_SEC: f64,
 }
 
 impl AuthConfig {
@@ -28,6 +30,12 @@ impl AuthConfig {
 
             TOKEN_KEY: get_env_b64u_as_u8s("SERVICE_TOKEN_KEY")?,
             TOKEN_DURATION_SEC: get_env_parse("SERVICE_TOKEN_DURATION_SEC")?,
+            EMAIL_TOKEN_DURATION_SEC: get_env_parse(
+                "SERVICE_EMAIL_TOKEN_DURATION_SEC",
+            )?,
+            PWD_RESET_TOKEN_DURATION_SEC: get_env_parse(
+                "SERVICE_PWD_RESET_TOKEN_DURATION_SEC",
+            )?,
         })
     }
 }
@@ -59,6 +59,16 @@ impl Display for Token {
     }
 }
 
+/// What a token is for, part of its signature so that a token of one scope
+/// (e.g., a link sent by email) never validates as another (e.g., the web
+/// token).
+#[derive(Debug, Clone, Copy, strum_macros::AsRefStr)]
+enum TokenScope {
+    Web,
+    EmailVerify,
+    PwdReset,
+}
+
 // endregion: --- Token Type
 
 // region:    --- Web Token Gen and Validation
@@ -66,6 +76,7 @@ impl Display for Token {
 pub fn generate_web_token(user: &str, token_salt: Uuid) -> Result<Token> {
     let config = &auth_config();
     generate_token(
+        TokenScope::Web,
         user,
         config.TOKEN_DURATION_SEC,
         token_salt,
@@ -75,16 +86,82 @@ pub fn generate_web_token(user: &str, token_salt: Uuid) -> Result<Token> {
 
 pub fn validate_web_token(origin_token: &Token, salt: Uuid) -> Result<()> {
     let config = &auth_config();
-    validate_token_sign_and_exp(origin_token, salt, &config.TOKEN_KEY)?;
+    validate_token_sign_and_exp(
+        TokenScope::Web,
+        origin_token,
+        salt,
+        &config.TOKEN_KEY,
+    )?;
 
     Ok(())
 }
 
 // endregion: --- Web Token Gen and Validation
 
+// region:    --- Email Token Gen and Validation
+
+/// Token of the link sent to `user` to verify their email.
+pub fn generate_email_token(user: &str, token_salt: Uuid) -> Result<Token> {
+    let config = &auth_config();
+    generate_token(
+        TokenScope::EmailVerify,
+        user,
+        config.EMAIL_TOKEN_DURATION_SEC,
+        token_salt,
+        &config.TOKEN_KEY,
+    )
+}
+
+pub fn validate_email_token(origin_token: &Token, salt: Uuid) -> Result<()> {
+    let config = &auth_config();
+    validate_token_sign_and_exp(
+        TokenScope::EmailVerify,
+        origin_token,
+        salt,
+        &config.TOKEN_KEY,
+    )?;
+
+    Ok(())
+}
+
+// endregion: --- Email Token Gen and Validation
+
+// region:    --- Pwd Reset Token Gen and Validation
+
+/// Token sent to `user` to reset their password. Single use as long as the
+/// reset rotates the `token_salt`.
+pub fn generate_pwd_reset_token(user: &str, token_salt: Uuid) -> Result<Token> {
+    let config = &auth_config();
+    generate_token(
+        TokenScope::PwdReset,
+        user,
+        config.PWD_RESET_TOKEN_DURATION_SEC,
+        token_salt,
+        &config.TOKEN_KEY,
+    )
+}
+
+pub fn validate_pwd_reset_token(
+    origin_token: &Token,
+    salt: Uuid,
+) -> Result<()> {
+    let config = &auth_config();
+    validate_token_sign_and_exp(
+        TokenScope::PwdReset,
+        origin_token,
+        salt,
+        &config.TOKEN_KEY,
+    )?;
+
+    Ok(())
+}
+
+// endregion: --- Pwd Reset Token Gen and Validation
+
 // region:    --- (private) Token Gen and Validation
 
 fn generate_token(
+    scope: TokenScope,
     ident: &str,
     duration_sec: f64,
     token_salt: Uuid,
@@ -95,7 +172,7 @@ fn generate_token(
     let exp = TimeRfc3339::now_utc_plus_sec_str(duration_sec);
 
     // -- Sign the two first components.
-    let sign_b64u = token_sign_into_b64u(&ident, &exp, token_salt, key)?;
+    let sign_b64u = token_sign_into_b64u(scope, &ident, &exp, token_salt, key)?;
 
     Ok(Token {
         ident,
@@ -105,12 +182,14 @@ fn generate_token(
 }
 
 fn validate_token_sign_and_exp(
+    scope: TokenScope,
     origin_token: &Token,
     token_salt: Uuid,
     key: &[u8],
 ) -> Result<()> {
     // -- Validate signature.
     let new_sign_b64u = token_sign_into_b64u(
+        scope,
         &origin_token.ident,
         &origin_token.exp,
         token_salt,
@@ -133,9 +212,10 @@ fn validate_token_sign_and_exp(
     Ok(())
 }
 
-/// Create token signature from token parts
+/// Create token signature from token scope, parts
 /// and salt.
 fn token_sign_into_b64u(
+    scope: TokenScope,
     ident: &str,
     exp: &str,
     token_salt: Uuid,
@@ -147,6 +227,7 @@ fn token_sign_into_b64u(
     let mut hasher = blake3::Hasher::new();
 
     // -- Add content.
+    hasher.update(scope.as_ref().as_bytes());
     hasher.update(content.as_bytes());
     hasher.update(token_salt.as_bytes());
     hasher.update(key);
@@ -215,8 +296,13 @@ mod tests {
             Uuid::parse_str("f05e8961-d6ad-4086-9e78-a6de065e5453").unwrap();
         let fx_duration_sec = 0.02; // 20ms
         let token_key = &auth_config().TOKEN_KEY;
-        let fx_token =
-            generate_token(fx_user, fx_duration_sec, fx_salt, token_key)?;
+        let fx_token = generate_token(
+            TokenScope::Web,
+            fx_user,
+            fx_duration_sec,
+            fx_salt,
+            token_key,
+        )?;
 
         // -- Exec
         thread::sleep(Duration::from_millis(10));
@@ -236,8 +322,13 @@ mod tests {
             Uuid::parse_str("f05e8961-d6ad-4086-9e78-a6de065e5453").unwrap();
         let fx_duration_sec = 0.01; // 10ms
         let token_key = &auth_config().TOKEN_KEY;
-        let fx_token =
-            generate_token(fx_user, fx_duration_sec, fx_salt, token_key)?;
+        let fx_token = generate_token(
+            TokenScope::Web,
+            fx_user,
+            fx_duration_sec,
+            fx_salt,
+            token_key,
+        )?;
 
         // -- Exec
         thread::sleep(Duration::from_millis(20));
@@ -251,5 +342,32 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_token_validate_email_token_err_scope() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_user = "user_one";
+        let fx_salt =
+            Uuid::parse_str("f05e8961-d6ad-4086-9e78-a6de065e5453").unwrap();
+        let fx_email_token = generate_email_token(fx_user, fx_salt)?;
+        let fx_web_token = generate_web_token(fx_user, fx_salt)?;
+
+        // -- Exec
+        validate_email_token(&fx_email_token, fx_salt)?;
+        let res_web = validate_web_token(&fx_email_token, fx_salt);
+        let res_email = validate_email_token(&fx_web_token, fx_salt);
+
+        // -- Check
+        assert!(
+            matches!(res_web, Err(token::Error::SignatureNotMatching)),
+            "Should have matched `Err(Error::SignatureNotMatching)` but was `{res_web:?}`"
+        );
+        assert!(
+            matches!(res_email, Err(token::Error::SignatureNotMatching)),
+            "Should have matched `Err(Error::SignatureNotMatching)` but was `{res_email:?}`"
+        );
+
+        Ok(())
+    }
 }
 // endregion: --- Tests
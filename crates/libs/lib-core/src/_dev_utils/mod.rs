@@ -54,6 +54,12 @@ pub async fn init_test() -> ModelManager {
     ModelManager::new().await.unwrap()
 }
 
+/// Ctx of the seeded `demo1` user, in its `demo1` organisation which owns
+/// the seed sellers.
+pub fn demo_ctx() -> Ctx {
+    Ctx::new_with_org("demo1", "demo1").unwrap()
+}
+
 // region:    --- User seed/clean
 
 pub async fn seed_users(
@@ -78,7 +84,9 @@ pub async fn seed_user(
 ) -> model::user::Result<String> {
     // let pwd_clear = "seed-user-pwd";
 
+    // Seeded users are past the email verification.
     let id = model::user::UserBmc::create(ctx, mm, user_for_seed).await?;
+    model::user::UserBmc::verify_email(ctx, mm, &id).await?;
 
     Ok(id)
 }
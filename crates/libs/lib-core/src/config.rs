@@ -1,5 +1,6 @@
+use crate::model::tax::{TaxRounding, TaxRoundingMode};
 use lib_utils::envs::{DefaultIfMissing, get_env, get_env_parse};
-use std::{sync::OnceLock, time::Duration};
+use std::{path::PathBuf, sync::OnceLock, time::Duration};
 
 pub fn core_config() -> &'static CoreConfig {
     static INSTANCE: OnceLock<CoreConfig> = OnceLock::new();
@@ -17,6 +18,17 @@ pub struct CoreConfig {
     pub DB_URL: String,
     pub DB_MAX_CONNECTIONS: u32,
     pub DB_CONNECTION_TIMEOUT: Duration,
+
+    // -- Tax
+    pub TAX_ROUNDING: TaxRounding,
+
+    // -- Unit
+    /// Cost deviation (in percent) from the recent median above which a unit
+    /// cost is flagged.
+    pub UNIT_COST_DEVIATION_PCT: u32,
+
+    // -- Mail
+    pub MAIL_OUTBOX_DIR: PathBuf,
 }
 
 impl CoreConfig {
@@ -27,11 +39,26 @@ impl CoreConfig {
             get_env_parse::<u64>("SERVICE_DB_CONNECTION_TIMEOUT_MS")
                 .if_missing(500)
                 .map(Duration::from_millis)?;
+        let tax_rounding = TaxRounding {
+            mode: get_env_parse::<TaxRoundingMode>("SERVICE_TAX_ROUNDING_MODE")
+                .default_if_missing()?,
+            scale: get_env_parse::<i64>("SERVICE_TAX_ROUNDING_SCALE")
+                .if_missing(2)?,
+        };
+        let unit_cost_deviation_pct =
+            get_env_parse::<u32>("SERVICE_UNIT_COST_DEVIATION_PCT")
+                .if_missing(20)?;
+        let mail_outbox_dir =
+            get_env_parse::<PathBuf>("SERVICE_MAIL_OUTBOX_DIR")
+                .if_missing(PathBuf::from("outbox"))?;
 
         Ok(CoreConfig {
             DB_URL: get_env("SERVICE_DB_URL")?,
             DB_MAX_CONNECTIONS: db_max_connections,
             DB_CONNECTION_TIMEOUT: db_connections_timeout,
+            TAX_ROUNDING: tax_rounding,
+            UNIT_COST_DEVIATION_PCT: unit_cost_deviation_pct,
+            MAIL_OUTBOX_DIR: mail_outbox_dir,
         })
     }
 }
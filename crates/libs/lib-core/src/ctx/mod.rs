@@ -11,6 +11,8 @@ pub use self::error::{Error, Result};
 #[derive(Clone, Debug)]
 pub struct Ctx {
     user_id: Arc<str>,
+    /// The active organisation, whose books the `*Bmc` functions work on.
+    org_id: Option<Arc<str>>,
 }
 
 // Constructors.
@@ -18,6 +20,7 @@ impl Ctx {
     pub fn root_ctx() -> Self {
         Ctx {
             user_id: Arc::from("root"),
+            org_id: None,
         }
     }
 
@@ -27,9 +30,18 @@ impl Ctx {
         } else {
             Ok(Self {
                 user_id: Arc::from(user_id),
+                org_id: None,
             })
         }
     }
+
+    pub fn new_with_org(user_id: &str, org_id: &str) -> Result<Self> {
+        let ctx = Self::new(user_id)?;
+        Ok(Self {
+            org_id: Some(Arc::from(org_id)),
+            ..ctx
+        })
+    }
 }
 
 // Property Accessors.
@@ -37,4 +49,9 @@ impl Ctx {
     pub fn user_id(&self) -> &str {
         self.user_id.as_ref()
     }
+
+    /// None for a ctx without an organisation, which sees no books.
+    pub fn org_id(&self) -> Option<&str> {
+        self.org_id.as_deref()
+    }
 }
@@ -1,5 +1,6 @@
 pub mod config;
 pub mod ctx;
+pub mod mail;
 pub mod model;
 
 //  TODO: uncomment in prod
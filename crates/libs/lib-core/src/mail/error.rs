@@ -0,0 +1,18 @@
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    MailSendFail(String),
+
+    // -- Externals
+    #[error(transparent)]
+    Io(
+        #[from]
+        #[serde_as(as = "DisplayFromStr")]
+        std::io::Error,
+    ),
+}
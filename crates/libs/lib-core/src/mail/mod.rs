@@ -0,0 +1,120 @@
+//! Mail
+//!
+//! The app sends its mails (e.g., the email verification link) through a
+//! `MailSender`, held by the `ModelManager`. The default one, the
+//! `OutboxMailSender`, writes them to a local outbox folder so that it works
+//! offline. Another one (e.g., SMTP) is plugged with
+//! `ModelManager::with_mailer`.
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use lib_utils::time::TimeRfc3339;
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+// endregion: --- Modules
+
+// region:    --- Mail Types
+
+#[derive(Clone, Debug)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    /// Plain text.
+    pub body: String,
+}
+
+/// NOTE: Called from the request handlers, so a sender that talks to a slow
+///       service should rather queue the mail than block.
+pub trait MailSender: Send + Sync {
+    fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+// endregion: --- Mail Types
+
+// region:    --- OutboxMailSender
+
+/// Writes each mail as an `.eml` file in the `dir` folder.
+pub struct OutboxMailSender {
+    dir: PathBuf,
+}
+
+impl OutboxMailSender {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl MailSender for OutboxMailSender {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let now = TimeRfc3339::now_utc();
+        let file = self.dir.join(format!(
+            "{}-{}.eml",
+            now.inner().unix_timestamp(),
+            Uuid::new_v4()
+        ));
+        let content = format!(
+            "To: {}\r\nSubject: {}\r\nDate: {}\r\n\r\n{}\r\n",
+            mail.to,
+            mail.subject,
+            now.format_time(),
+            mail.body
+        );
+
+        fs::write(file, content)?;
+
+        Ok(())
+    }
+}
+
+// endregion: --- OutboxMailSender
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_outbox_send_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_dir = std::env::temp_dir()
+            .join(format!("test_outbox_send_ok-{}", Uuid::new_v4()));
+        let mailer = OutboxMailSender::new(&fx_dir);
+        let fx_mail = Mail {
+            to: "demo1@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "Hello demo1".to_string(),
+        };
+
+        // -- Exec
+        mailer.send(&fx_mail)?;
+
+        // -- Check
+        let files = fs::read_dir(&fx_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        assert_eq!(files.len(), 1);
+        let content = fs::read_to_string(&files[0])?;
+        assert!(content.starts_with("To: demo1@example.com\r\n"));
+        assert!(content.contains("Subject: Hello\r\n"));
+        assert!(content.ends_with("\r\n\r\nHello demo1\r\n"));
+
+        // -- Clean
+        fs::remove_dir_all(&fx_dir)?;
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,25 @@
+use crate::model::acs::Privilege;
+use crate::model::store::dbx;
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    NoPermission {
+        privilege: Privilege,
+    },
+
+    /// Until verified, the user only holds the `*.read` privileges.
+    EmailNotVerified {
+        privilege: Privilege,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Dbx(#[from] dbx::Error),
+}
+
+// region:    --- Error Boilerplate
@@ -1,5 +1,325 @@
-//! TO BE IMPLEMENTED
-//!
 //! `acs` Access Control System based on PBAC (Privilege Based Access Control)
 //!
-//! (more to come)
+//! Privileges (e.g., `bill.finalize`) are grouped into roles (e.g., `Member`)
+//! in the db. A user holds the privileges of the roles assigned to them
+//! (e.g., `SysAdmin`) and of the role of their membership in an organisation.
+//! The ctx only carries the user and organisation ids, the privileges are
+//! resolved on each check by the `has_privilege(user_id, org_id, privilege)`
+//! sql function.
+//!
+//! The public functions of the data `*Bmc`s (sellers, bills, transactions,
+//! ...) start with `acs::require` of their privilege. The `pub(crate)` ones
+//! are called by those, so do not check again. `OrgBmc` checks the role of
+//! the member instead (`ensure_can_grant`, `ensure_owner_left`).
+//!
+//! Until the user verifies their email, only the `*.read` privileges of
+//! their roles are held.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use serde::Serialize;
+
+mod error;
+
+pub use error::{Error, Result};
+
+/// NOTE: Must match the `privilege` table.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+    strum_macros::EnumString,
+    strum_macros::IntoStaticStr,
+)]
+#[serde(into = "&'static str")]
+pub enum Privilege {
+    #[strum(serialize = "seller.read")]
+    SellerRead,
+    #[strum(serialize = "seller.create")]
+    SellerCreate,
+    #[strum(serialize = "seller.update")]
+    SellerUpdate,
+    #[strum(serialize = "seller.merge")]
+    SellerMerge,
+
+    #[strum(serialize = "bill.read")]
+    BillRead,
+    #[strum(serialize = "bill.create")]
+    BillCreate,
+    #[strum(serialize = "bill.update")]
+    BillUpdate,
+    #[strum(serialize = "bill.delete")]
+    BillDelete,
+    #[strum(serialize = "bill.finalize")]
+    BillFinalize,
+    #[strum(serialize = "bill.void")]
+    BillVoid,
+    #[strum(serialize = "bill.pay")]
+    BillPay,
+
+    #[strum(serialize = "payment.create")]
+    PaymentCreate,
+
+    #[strum(serialize = "transaction.read")]
+    TransactionRead,
+    #[strum(serialize = "transaction.create")]
+    TransactionCreate,
+    #[strum(serialize = "transaction.update")]
+    TransactionUpdate,
+    #[strum(serialize = "transaction.delete")]
+    TransactionDelete,
+
+    #[strum(serialize = "tag.read")]
+    TagRead,
+    #[strum(serialize = "tag.update")]
+    TagUpdate,
+    #[strum(serialize = "tag.merge")]
+    TagMerge,
+    #[strum(serialize = "tag.delete")]
+    TagDelete,
+
+    #[strum(serialize = "unit.read")]
+    UnitRead,
+    #[strum(serialize = "unit.update")]
+    UnitUpdate,
+
+    #[strum(serialize = "exchange_rate.read")]
+    ExchangeRateRead,
+    #[strum(serialize = "exchange_rate.set")]
+    ExchangeRateSet,
+
+    /// Manage the users, e.g. for the login and the registration.
+    #[strum(serialize = "user.admin")]
+    UserAdmin,
+}
+
+/// Fail with `NoPermission` unless the ctx holds `privilege`, or with
+/// `EmailNotVerified` when a role of the user grants it but the user has
+/// still to verify their email.
+pub async fn require(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    privilege: Privilege,
+) -> Result<()> {
+    let sqlx_query = sqlx::query_as::<_, (bool, bool)>(
+        "select has_privilege($1, $2, $3), role_has_privilege($1, $2, $3);",
+    )
+    .bind(ctx.user_id())
+    .bind(ctx.org_id())
+    .bind(privilege.to_string());
+
+    let (granted, granted_by_role) = mm.dbx().fetch_one(sqlx_query).await?;
+    if !granted && granted_by_role {
+        return Err(Error::EmailNotVerified { privilege });
+    }
+    if !granted {
+        return Err(Error::NoPermission { privilege });
+    }
+
+    Ok(())
+}
+
+/// Privileges of the ctx, e.g. for the ui to hide what it may not do.
+pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Privilege>> {
+    let sqlx_query = sqlx::query_as::<_, (String,)>(
+        "select p.name from privilege p
+        where has_privilege($1, $2, p.name)
+        order by p.serial_id;",
+    )
+    .bind(ctx.user_id())
+    .bind(ctx.org_id());
+
+    let privileges = mm
+        .dbx()
+        .fetch_all(sqlx_query)
+        .await?
+        .into_iter()
+        .filter_map(|(name,)| name.parse().ok())
+        .collect();
+
+    Ok(privileges)
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::bill::{self, BillBmc};
+    use crate::model::org::OrgBmc;
+    use crate::model::seller::{self, SellerBmc};
+    use crate::model::user::{self, UserBmc, UserForCreate};
+    use serde_json::json;
+    use serial_test::serial;
+    use strum::IntoEnumIterator;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_privileges_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+
+        // -- Exec
+        let root = list(&Ctx::root_ctx(), &mm).await?;
+        let owner = list(&ctx, &mm).await?;
+
+        // -- Check
+        assert_eq!(
+            root,
+            Privilege::iter().collect::<Vec<_>>(),
+            "the privilege table should match the enum"
+        );
+        for privilege in [
+            Privilege::UserAdmin,
+            Privilege::UnitUpdate,
+            Privilege::ExchangeRateSet,
+        ] {
+            assert!(!owner.contains(&privilege), "{privilege}");
+        }
+        assert_eq!(owner.len(), root.len() - 3);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_require_by_role_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_user_id = _dev_utils::seed_user(
+            &Ctx::root_ctx(),
+            &mm,
+            UserForCreate {
+                name: "member".to_string(),
+                email: "member@example.com".to_string(),
+                pwd_clear: "welcome".to_string(),
+            },
+        )
+        .await?;
+        OrgBmc::add_member(
+            &ctx,
+            &mm,
+            "demo1",
+            serde_json::from_value(json!({ "email": "member@example.com" }))?,
+        )
+        .await?;
+        let member_ctx = Ctx::new_with_org(&fx_user_id, "demo1")?;
+        let bill_id = BillBmc::create(
+            &member_ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" }))?,
+        )
+        .await?;
+
+        // -- Exec & Check member
+        assert!(matches!(
+            BillBmc::finalize(&member_ctx, &mm, &bill_id).await,
+            Err(bill::Error::Acs(super::Error::NoPermission {
+                privilege: Privilege::BillFinalize
+            }))
+        ));
+        BillBmc::finalize(&ctx, &mm, &bill_id).await?;
+
+        // -- Exec & Check viewer
+        OrgBmc::update_member(
+            &ctx,
+            &mm,
+            "demo1",
+            &fx_user_id,
+            serde_json::from_value(json!({ "role": "Viewer" }))?,
+        )
+        .await?;
+        SellerBmc::get_by_seller_id(&member_ctx, &mm, "0001").await?;
+        assert!(matches!(
+            SellerBmc::create(
+                &member_ctx,
+                &mm,
+                serde_json::from_value(json!([{ "name": "new seller" }]))?
+            )
+            .await,
+            Err(seller::Error::Acs(super::Error::NoPermission {
+                privilege: Privilege::SellerCreate
+            }))
+        ));
+
+        // -- Exec & Check user admin
+        assert!(matches!(
+            UserBmc::list(&ctx, &mm).await,
+            Err(user::Error::Acs(super::Error::NoPermission {
+                privilege: Privilege::UserAdmin
+            }))
+        ));
+        UserBmc::list(&Ctx::root_ctx(), &mm).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_require_unverified_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let fx_user_id = UserBmc::create(
+            &root_ctx,
+            &mm,
+            UserForCreate {
+                name: "unverified".to_string(),
+                email: "unverified@example.com".to_string(),
+                pwd_clear: "welcome".to_string(),
+            },
+        )
+        .await?;
+        let fx_org_id = UserBmc::first_by_user_id(&root_ctx, &mm, &fx_user_id)
+            .await?
+            .and_then(|user| user.org_id)
+            .ok_or("Should have an organisation")?;
+        let ctx = Ctx::new_with_org(&fx_user_id, &fx_org_id)?;
+        let fx_sellers = json!([{ "name": "unverified seller" }]);
+
+        // -- Exec & Check unverified
+        assert!(
+            list(&ctx, &mm)
+                .await?
+                .iter()
+                .all(|privilege| { privilege.to_string().ends_with(".read") })
+        );
+        SellerBmc::get_all(&ctx, &mm, None).await?;
+        assert!(matches!(
+            SellerBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(fx_sellers.clone())?
+            )
+            .await,
+            Err(seller::Error::Acs(super::Error::EmailNotVerified {
+                privilege: Privilege::SellerCreate
+            }))
+        ));
+        assert!(matches!(
+            require(&ctx, &mm, Privilege::UnitUpdate).await,
+            Err(super::Error::NoPermission {
+                privilege: Privilege::UnitUpdate
+            })
+        ));
+
+        // -- Exec & Check verified
+        UserBmc::verify_email(&root_ctx, &mm, &fx_user_id).await?;
+        SellerBmc::create(&ctx, &mm, serde_json::from_value(fx_sellers)?)
+            .await?;
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
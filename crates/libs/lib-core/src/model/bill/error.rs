@@ -1,4 +1,10 @@
+use crate::model::acs;
+use crate::model::bill::BillStatus;
 use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::{
+    exchange_rate, pagination, payment, seller, transaction, validation,
+};
+use lib_utils::money::Money;
 use serde::Serialize;
 use serde_with::serde_as;
 
@@ -13,10 +19,67 @@ pub enum Error {
 
     BillNotUnique,
 
+    BillNotEditable {
+        bill_id: String,
+        status: BillStatus,
+    },
+
+    BillStatusTransitionInvalid {
+        bill_id: String,
+        from: BillStatus,
+        to: BillStatus,
+    },
+
+    /// A bill is only paid once nothing is outstanding.
+    BillNotSettled {
+        bill_id: String,
+        outstanding: Money,
+    },
+
+    /// A bill with payments can not be voided, the payments would be left
+    /// crediting the seller.
+    BillHasPayments {
+        bill_id: String,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Pagination(#[from] pagination::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Seller(#[from] seller::Error),
+
+    #[error(transparent)]
+    ExchangeRate(#[from] exchange_rate::Error),
+
+    #[error(transparent)]
+    Transaction(Box<transaction::Error>),
+
+    #[error(transparent)]
+    Payment(Box<payment::Error>),
+
     #[error(transparent)]
     Dbx(dbx::Error),
 }
 
+impl From<transaction::Error> for Error {
+    fn from(value: transaction::Error) -> Self {
+        Self::Transaction(Box::new(value))
+    }
+}
+
+impl From<payment::Error> for Error {
+    fn from(value: payment::Error) -> Self {
+        Self::Payment(Box::new(value))
+    }
+}
+
 impl From<dbx::Error> for Error {
     fn from(value: dbx::Error) -> Self {
         match value.resolve_unique_violation() {
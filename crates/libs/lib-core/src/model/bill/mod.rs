@@ -1,19 +1,128 @@
+use crate::ctx::Ctx;
 use crate::model::ModelManager;
-use lib_utils::b58::b58_encode;
-use rand::RngCore as _;
+use crate::model::acs::{self, Privilege};
+use crate::model::exchange_rate::{self, ExchangeRateBmc};
+use crate::model::pagination::{Cursor, Page, SortOrder, normalize_limit};
+use crate::model::payment::{Payment, PaymentBmc};
+use crate::model::seller::SellerBmc;
+use crate::model::tax::{TaxRateTotal, TaxSummary};
+use crate::model::transaction::{Transaction, TransactionBmc};
+use crate::model::validation::{Validate, Validator};
+use bigdecimal::BigDecimal;
+use lib_utils::{
+    id::IdSpec,
+    money::{Currency, Money},
+    time::TimeRfc3339,
+};
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 mod error;
 
 pub use error::{Error, Result};
 use sqlx::prelude::FromRow;
 
+/// Lifecycle of a bill: `Draft -> Finalized -> Paid`, with `Void` reachable
+/// from `Draft` and `Finalized`. Only draft bills accept transaction changes.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum BillStatus {
+    #[default]
+    Draft,
+    Finalized,
+    Paid,
+    Void,
+}
+
+impl BillStatus {
+    pub fn can_transition_to(&self, to: BillStatus) -> bool {
+        use BillStatus::*;
+
+        matches!(
+            (self, to),
+            (Draft, Finalized)
+                | (Finalized, Paid)
+                | (Draft, Void)
+                | (Finalized, Void)
+        )
+    }
+
+    pub fn is_editable(&self) -> bool {
+        matches!(self, BillStatus::Draft)
+    }
+}
+
+impl TryFrom<String> for BillStatus {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
 #[derive(Debug, Serialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct Bill {
     pub bill_id: String,
     pub remark: Option<String>,
     pub seller_id: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub status: BillStatus,
+    pub ctime: TimeRfc3339,
+}
+
+/// A bill with its line items and totals.
+///
+/// The line items are in their own currency, the totals in `currency`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillDetails {
+    #[serde(flatten)]
+    pub bill: Bill,
+    pub transactions: Vec<Transaction>,
+    pub currency: Currency,
+    pub total_seller_cost: BigDecimal,
+    pub totals_by_payment_method: Vec<BillTotal>,
+    /// What the transactions allocated to each tag, counting toward its
+    /// ancestors too. The untagged transactions are left out.
+    pub totals_by_tag: Vec<BillTotal>,
+    /// GST included in `total_seller_cost`, per rate and overall.
+    pub tax_summary: TaxSummary,
+    pub payments: Vec<Payment>,
+    pub total_paid: BigDecimal,
+    /// `total_seller_cost - total_paid`.
+    pub outstanding: BigDecimal,
+}
+
+/// A bill with what is paid and still due on it, in `currency`.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BillBalance {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub bill: Bill,
+    #[sqlx(try_from = "String")]
+    pub currency: Currency,
+    pub total: BigDecimal,
+    pub paid: BigDecimal,
+    pub outstanding: BigDecimal,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BillTotal {
+    pub name: String,
+    pub total: BigDecimal,
 }
 
 /// Fields required for creating new bill
@@ -24,42 +133,119 @@ pub struct BillForCreate {
     seller_id: Option<String>,
 }
 
-pub struct BillBmc;
+impl Validate for BillForCreate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .max_len(path, "remark", self.remark.as_deref(), 256)
+            .max_len(path, "sellerId", self.seller_id.as_deref(), 30);
+    }
+}
 
-impl BillBmc {
-    fn generate_bill_id() -> String {
-        let mut key = [0u8; 64]; // 512 bits = 64 bytes
-        rand::rng().fill_bytes(&mut key);
-        b58_encode(key)
-            .chars()
-            .take(10)
-            .collect::<String>()
-            .to_uppercase()
+/// Fields that can be changed on an existing bill.
+/// An explicit `null` clears the value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillForUpdate {
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    remark: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    seller_id: Option<Option<String>>,
+}
+
+impl Validate for BillForUpdate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .max_len(
+                path,
+                "remark",
+                self.remark.as_ref().and_then(Option::as_deref),
+                256,
+            )
+            .max_len(
+                path,
+                "sellerId",
+                self.seller_id.as_ref().and_then(Option::as_deref),
+                30,
+            );
     }
+}
 
+/// Filters for `BillBmc::list`, dates apply to the bill creation time.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillFilter {
+    pub seller_id: Option<String>,
+    pub status: Option<BillStatus>,
+    /// Inclusive lower bound.
+    pub from: Option<TimeRfc3339>,
+    /// Exclusive upper bound.
+    pub to: Option<TimeRfc3339>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillListOptions {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(FromRow)]
+struct BillListRow {
+    serial_id: i64,
+    #[sqlx(flatten)]
+    bill: Bill,
+    sort_key: String,
+}
+
+/// Bills are always listed newest first.
+const BILL_LIST_SORT: &str = "ctime";
+
+/// Public `bill_id` of new bills.
+const BILL_ID: IdSpec = IdSpec::new("B", 10).with_checksum();
+
+pub struct BillBmc;
+
+impl BillBmc {
     pub async fn create(
+        ctx: &Ctx,
         mm: &ModelManager,
         bill_c: BillForCreate,
     ) -> Result<String> {
+        acs::require(ctx, mm, Privilege::BillCreate).await?;
+
+        bill_c.validate()?;
+
         let BillForCreate { remark, seller_id } = bill_c;
 
         // Start the transaction
         let mm = mm.new_with_txn();
         mm.dbx().begin_txn().await?;
 
+        let seller_serial_id = match seller_id {
+            Some(seller_id) => {
+                Some(SellerBmc::get_serial_id(ctx, &mm, &seller_id).await?)
+            }
+            None => None,
+        };
+
         // region:    --- Insert bill
-        let sqlx_query = sqlx::query_as::<_, (String,)>(
-            "insert into bill (bill_id, remark, seller_serial_id)
-            values ($1, $2,
-            (select serial_id from seller s where s.seller_id = $3 limit 1))
-            on conflict (bill_id) do nothing
-            returning bill_id;",
-        )
-        .bind(BillBmc::generate_bill_id())
-        .bind(remark)
-        .bind(seller_id);
+        let remark = &remark;
+        let dbx = mm.dbx();
+        let (returning_bill_id,) = dbx
+            .retry_on_id_collision("bill_bill_id_key", || async move {
+                let sqlx_query = sqlx::query_as::<_, (String,)>(
+                    "insert into bill (bill_id, remark, seller_serial_id, org_serial_id)
+                    values ($1, $2, $3, org_serial_id($4))
+                    returning bill_id;",
+                )
+                .bind(BILL_ID.generate())
+                .bind(remark)
+                .bind(seller_serial_id)
+                .bind(ctx.org_id());
 
-        let (returning_bill_id,) = mm.dbx().fetch_one(sqlx_query).await?;
+                dbx.fetch_one(sqlx_query).await
+            })
+            .await?;
 
         // Commit the transaction
         mm.dbx().commit_txn().await?;
@@ -70,15 +256,33 @@ impl BillBmc {
     }
 
     pub async fn get_by_bill_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<Bill> {
+        acs::require(ctx, mm, Privilege::BillRead).await?;
+
+        Self::fetch_by_bill_id(ctx, mm, bill_id).await
+    }
+
+    /// Unchecked, for the details.
+    async fn fetch_by_bill_id(
+        ctx: &Ctx,
         mm: &ModelManager,
         bill_id: &str,
     ) -> Result<Bill> {
+        Self::ensure_well_formed(bill_id)?;
+
         let sqlx_query = sqlx::query_as::<_, Bill>(
-            "select b.bill_id, b.remark, s.seller_id from bill b
-            inner join seller s on b.seller_serial_id = s.serial_id
-            where b.bill_id = $1 limit 1;",
+            "select b.bill_id, b.remark, s.seller_id, bs.name as status, b.ctime
+            from bill b
+            left join seller s on b.seller_serial_id = s.serial_id
+            inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+            where b.bill_id = $1 and b.org_serial_id = org_serial_id($2)
+            limit 1;",
         )
-        .bind(bill_id);
+        .bind(bill_id)
+        .bind(ctx.org_id());
 
         let user = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
             Error::BillNotFound {
@@ -88,6 +292,608 @@ impl BillBmc {
 
         Ok(user)
     }
+
+    /// The bill with its transactions and the totals of their `seller_cost`,
+    /// converted to `currency`.
+    pub async fn get_details(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+        currency: Currency,
+    ) -> Result<BillDetails> {
+        acs::require(ctx, mm, Privilege::BillRead).await?;
+
+        // NOTE: Once the bill is found for `ctx`, its rows are too.
+        let bill = Self::fetch_by_bill_id(ctx, mm, bill_id).await?;
+
+        ExchangeRateBmc::ensure_for_bills(
+            ctx,
+            mm,
+            Some(bill_id),
+            None,
+            currency,
+        )
+        .await?;
+
+        let transactions =
+            TransactionBmc::fetch_by_bill_id(ctx, mm, bill_id).await?;
+
+        let sqlx_query = sqlx::query_as::<_, (BigDecimal,)>(
+            "select coalesce(sum(convert_amount(tr.seller_cost * tk.sign,
+                tr.currency_serial_id, c.serial_id, tr.transaction_time)), 0)
+            from transaction tr
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            inner join bill b on b.serial_id = tr.bill_serial_id
+            inner join currency c on c.code = $2
+            where b.bill_id = $1;",
+        )
+        .bind(bill_id)
+        .bind(currency.code());
+
+        let (total_seller_cost,) = mm.dbx().fetch_one(sqlx_query).await?;
+
+        let sqlx_query = sqlx::query_as::<_, BillTotal>(
+            "select pm.name, sum(convert_amount(tr.seller_cost * tk.sign,
+                tr.currency_serial_id, c.serial_id, tr.transaction_time)) as total
+            from transaction tr
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            inner join bill b on b.serial_id = tr.bill_serial_id
+            inner join payment_method pm
+                on pm.serial_id = tr.payment_method_serial_id
+            inner join currency c on c.code = $2
+            where b.bill_id = $1
+            group by pm.name
+            order by pm.name;",
+        )
+        .bind(bill_id)
+        .bind(currency.code());
+
+        let totals_by_payment_method = mm.dbx().fetch_all(sqlx_query).await?;
+
+        let sqlx_query = sqlx::query_as::<_, BillTotal>(
+            "with recursive tag_ancestor (tag_serial_id, ancestor_serial_id) as (
+                select serial_id, serial_id from tag
+                union all
+                select ta.tag_serial_id, t.parent_serial_id from tag_ancestor ta
+                inner join tag t on t.serial_id = ta.ancestor_serial_id
+                where t.parent_serial_id is not null
+            )
+            select tg.name,
+                sum(convert_amount(tt.amount * tk.sign, tr.currency_serial_id,
+                    c.serial_id, tr.transaction_time)) as total
+            from transaction tr
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            inner join bill b on b.serial_id = tr.bill_serial_id
+            inner join transaction_tag tt on tt.transaction_serial_id = tr.serial_id
+            inner join tag_ancestor ta on ta.tag_serial_id = tt.tag_serial_id
+            inner join tag tg on tg.serial_id = ta.ancestor_serial_id
+            inner join currency c on c.code = $2
+            where b.bill_id = $1
+            group by tg.name
+            order by tg.name;",
+        )
+        .bind(bill_id)
+        .bind(currency.code());
+
+        let totals_by_tag = mm.dbx().fetch_all(sqlx_query).await?;
+
+        // NOTE: Each amount is converted on its own, as the line items were
+        //       rounded on their own.
+        let sqlx_query = sqlx::query_as::<_, TaxRateTotal>(
+            "select tr.tax_rate as rate,
+                sum(convert_amount(tr.taxable_value * tk.sign, tr.currency_serial_id,
+                    c.serial_id, tr.transaction_time)) as taxable_value,
+                sum(convert_amount(tr.cgst * tk.sign, tr.currency_serial_id,
+                    c.serial_id, tr.transaction_time)) as cgst,
+                sum(convert_amount(tr.sgst * tk.sign, tr.currency_serial_id,
+                    c.serial_id, tr.transaction_time)) as sgst,
+                sum(convert_amount(tr.igst * tk.sign, tr.currency_serial_id,
+                    c.serial_id, tr.transaction_time)) as igst,
+                sum(convert_amount(tr.seller_cost * tk.sign, tr.currency_serial_id,
+                    c.serial_id, tr.transaction_time)) as total
+            from transaction tr
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            inner join bill b on b.serial_id = tr.bill_serial_id
+            inner join currency c on c.code = $2
+            where b.bill_id = $1 and tr.tax_rate is not null
+            group by tr.tax_rate
+            order by tr.tax_rate;",
+        )
+        .bind(bill_id)
+        .bind(currency.code());
+
+        let tax_summary = TaxSummary::new(
+            mm.dbx().fetch_all(sqlx_query).await?,
+            &total_seller_cost,
+        );
+
+        let payments = PaymentBmc::fetch_by_bill_id(ctx, mm, bill_id).await?;
+
+        let sqlx_query = sqlx::query_as::<_, (BigDecimal,)>(
+            "select coalesce(sum(convert_amount(p.amount,
+                p.currency_serial_id, c.serial_id, p.payment_time)), 0)
+            from payment p
+            inner join bill b on b.serial_id = p.bill_serial_id
+            inner join currency c on c.code = $2
+            where b.bill_id = $1;",
+        )
+        .bind(bill_id)
+        .bind(currency.code());
+
+        let (total_paid,) = mm.dbx().fetch_one(sqlx_query).await?;
+        let outstanding = &total_seller_cost - &total_paid;
+
+        Ok(BillDetails {
+            bill,
+            transactions,
+            currency,
+            total_seller_cost,
+            totals_by_payment_method,
+            totals_by_tag,
+            tax_summary,
+            payments,
+            total_paid,
+            outstanding,
+        })
+    }
+
+    /// Draft and finalized bills of a seller with an outstanding balance
+    /// (in `currency`), oldest first.
+    pub async fn list_unpaid_by_seller_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        seller_id: &str,
+        currency: Currency,
+    ) -> Result<Vec<BillBalance>> {
+        acs::require(ctx, mm, Privilege::BillRead).await?;
+
+        ExchangeRateBmc::ensure_for_bills(
+            ctx,
+            mm,
+            None,
+            Some(seller_id),
+            currency,
+        )
+        .await?;
+
+        let sqlx_query = sqlx::query_as::<_, BillBalance>(
+            "select *, total - paid as outstanding from (
+                select b.bill_id, b.remark, s.seller_id,
+                    bs.name as status, b.ctime, c.code as currency,
+                    coalesce((select sum(convert_amount(tr.seller_cost * tk.sign,
+                            tr.currency_serial_id, c.serial_id, tr.transaction_time))
+                        from transaction tr
+                        inner join transaction_kind tk
+                            on tk.serial_id = tr.transaction_kind_serial_id
+                        where tr.bill_serial_id = b.serial_id), 0) as total,
+                    coalesce((select sum(convert_amount(p.amount,
+                            p.currency_serial_id, c.serial_id, p.payment_time))
+                        from payment p
+                        where p.bill_serial_id = b.serial_id), 0) as paid
+                from bill b
+                inner join seller s on s.serial_id = b.seller_serial_id
+                inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+                inner join currency c on c.code = $3
+                where s.seller_id = $1
+                  and s.org_serial_id = org_serial_id($4)
+                  and bs.name = any($2)
+            ) t
+            where total > paid
+            order by ctime, bill_id;",
+        )
+        .bind(seller_id)
+        .bind([
+            BillStatus::Draft.to_string(),
+            BillStatus::Finalized.to_string(),
+        ])
+        .bind(currency.code())
+        .bind(ctx.org_id());
+
+        let bills = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(bills)
+    }
+
+    /// List bills matching `filter`, newest first, paginated with the opaque
+    /// `list_options.cursor`.
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filter: BillFilter,
+        list_options: BillListOptions,
+    ) -> Result<Page<Bill>> {
+        acs::require(ctx, mm, Privilege::BillRead).await?;
+
+        let BillFilter {
+            seller_id,
+            status,
+            from,
+            to,
+        } = filter;
+        let BillListOptions { cursor, limit } = list_options;
+
+        let limit = normalize_limit(limit);
+        let (cursor_key, cursor_serial_id) = cursor
+            .map(|c| Cursor::decode_for(&c, BILL_LIST_SORT, SortOrder::Desc))
+            .transpose()?
+            .map(|c| (Some(c.key), Some(c.serial_id)))
+            .unwrap_or_default();
+
+        let sqlx_query = sqlx::query_as::<_, BillListRow>(
+            "select b.serial_id, b.bill_id, b.remark, s.seller_id,
+                bs.name as status, b.ctime,
+                b.ctime::text as sort_key
+            from bill b
+            left join seller s on s.serial_id = b.seller_serial_id
+            inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+            where b.org_serial_id = org_serial_id($8)
+              and ($1::text is null or s.seller_id = $1)
+              and ($7::text is null or bs.name = $7)
+              and ($2::timestamptz is null or b.ctime >= $2)
+              and ($3::timestamptz is null or b.ctime < $3)
+              and ($4::text is null
+                or (b.ctime, b.serial_id) < ($4::text::timestamptz, $5::bigint))
+            order by b.ctime desc, b.serial_id desc
+            limit $6;",
+        )
+        .bind(seller_id)
+        .bind(from.map(|v| v.inner()))
+        .bind(to.map(|v| v.inner()))
+        .bind(cursor_key)
+        .bind(cursor_serial_id)
+        .bind(limit + 1)
+        .bind(status.map(|v| v.to_string()))
+        .bind(ctx.org_id());
+
+        let mut rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().map(|v| {
+                Cursor {
+                    sort: BILL_LIST_SORT.to_string(),
+                    order: SortOrder::Desc,
+                    key: v.sort_key.clone(),
+                    serial_id: v.serial_id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        let items = rows.into_iter().map(|v| v.bill).collect();
+
+        Ok(Page { items, next_cursor })
+    }
+
+    /// Update a draft bill, finalized bills must be voided instead.
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+        bill_u: BillForUpdate,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::BillUpdate).await?;
+
+        bill_u.validate()?;
+
+        let BillForUpdate { remark, seller_id } = bill_u;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        Self::ensure_editable(ctx, &mm, bill_id).await?;
+
+        let seller_serial_id = match seller_id.as_ref() {
+            Some(Some(seller_id)) => {
+                Some(SellerBmc::get_serial_id(ctx, &mm, seller_id).await?)
+            }
+            _ => None,
+        };
+
+        // NOTE: `$2`/`$4` tell if the field was sent, so that an explicit
+        //       null can clear it.
+        let sqlx_query = sqlx::query(
+            "update bill set
+                remark = case when $2 then $3 else remark end,
+                seller_serial_id = case when $4 then $5 else seller_serial_id end,
+                mtime = $6
+            where bill_id = $1 and org_serial_id = org_serial_id($7);",
+        )
+        .bind(bill_id)
+        .bind(remark.is_some())
+        .bind(remark.flatten())
+        .bind(seller_id.is_some())
+        .bind(seller_serial_id)
+        .bind(now)
+        .bind(ctx.org_id());
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+
+        if count == 0 {
+            return Err(Error::BillNotFound {
+                bill_id: bill_id.to_string(),
+            });
+        }
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Delete a draft bill. Its transactions are kept and detached from it.
+    /// Finalized bills must be voided instead.
+    pub async fn delete(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::BillDelete).await?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        Self::ensure_editable(ctx, &mm, bill_id).await?;
+
+        let sqlx_query = sqlx::query(
+            "update transaction set bill_serial_id = null, mtime = $2
+            where bill_serial_id = (select serial_id from bill where bill_id = $1);",
+        )
+        .bind(bill_id)
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        let sqlx_query =
+            sqlx::query("delete from bill where bill_id = $1;").bind(bill_id);
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+
+        if count == 0 {
+            return Err(Error::BillNotFound {
+                bill_id: bill_id.to_string(),
+            });
+        }
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    pub async fn finalize(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::BillFinalize).await?;
+
+        Self::transition(ctx, mm, bill_id, BillStatus::Finalized).await
+    }
+
+    /// Mark a finalized bill paid, once nothing is outstanding (e.g., a bill
+    /// settled by a refund). The payment settling a bill marks it paid on its
+    /// own.
+    pub async fn mark_paid(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::BillPay).await?;
+
+        Self::transition(ctx, mm, bill_id, BillStatus::Paid).await
+    }
+
+    /// Void a draft or finalized bill, unless it has payments.
+    pub async fn void(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::BillVoid).await?;
+
+        Self::transition(ctx, mm, bill_id, BillStatus::Void).await
+    }
+
+    /// Fail with `BillNotFound`, before any query, for a mistyped `bill_id`.
+    pub(crate) fn ensure_well_formed(bill_id: &str) -> Result<()> {
+        if BILL_ID.is_mistyped(bill_id) {
+            return Err(Error::BillNotFound {
+                bill_id: bill_id.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Fail with `BillNotFound` for an unknown bill and with `BillNotEditable`
+    /// when it is not a draft.
+    ///
+    /// The bill row is locked (`for share`) until the end of the caller
+    /// transaction, so the bill can not be finalized in between.
+    pub(crate) async fn ensure_editable(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<()> {
+        Self::ensure_well_formed(bill_id)?;
+
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select bs.name from bill b
+            inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+            where b.bill_id = $1 and b.org_serial_id = org_serial_id($2)
+            for share of b;",
+        )
+        .bind(bill_id)
+        .bind(ctx.org_id());
+
+        let (status,) = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::BillNotFound {
+                bill_id: bill_id.to_string(),
+            },
+        )?;
+        let status = BillStatus::try_from(status).unwrap_or_default();
+
+        if !status.is_editable() {
+            return Err(Error::BillNotEditable {
+                bill_id: bill_id.to_string(),
+                status,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Outstanding balance of the bill `bill_id`, in `currency`.
+    ///
+    /// Transactions and payments are netted per currency first, so a bill
+    /// paid in its own currency is settled whatever the rates of the days in
+    /// between. What remains in another currency is converted at the rate of
+    /// `at`.
+    pub(crate) async fn outstanding(
+        mm: &ModelManager,
+        bill_id: &str,
+        currency: Currency,
+        at: OffsetDateTime,
+    ) -> Result<BigDecimal> {
+        let sqlx_query =
+            sqlx::query_as::<_, (String, Option<BigDecimal>)>(
+                "select c.code,
+                    convert_amount(x.net, x.currency_serial_id, t.serial_id, $3)
+                from (
+                    select a.currency_serial_id, sum(a.amount) as net from (
+                        select tr.currency_serial_id, tr.seller_cost * tk.sign as amount
+                        from transaction tr
+                        inner join transaction_kind tk
+                            on tk.serial_id = tr.transaction_kind_serial_id
+                        inner join bill b on b.serial_id = tr.bill_serial_id
+                        where b.bill_id = $1
+                        union all
+                        select p.currency_serial_id, -p.amount from payment p
+                        inner join bill b on b.serial_id = p.bill_serial_id
+                        where b.bill_id = $1
+                    ) a
+                    group by a.currency_serial_id
+                ) x
+                inner join currency c on c.serial_id = x.currency_serial_id
+                inner join currency t on t.code = $2
+                where x.net <> 0;",
+            )
+            .bind(bill_id)
+            .bind(currency.code())
+            .bind(at);
+
+        let mut outstanding = BigDecimal::from(0);
+        for (from, converted) in mm.dbx().fetch_all(sqlx_query).await? {
+            outstanding += converted.ok_or_else(|| {
+                exchange_rate::Error::ExchangeRateNotFound {
+                    from: Currency::try_from(from).unwrap_or_default(),
+                    to: currency,
+                    date: at.date().to_string(),
+                }
+            })?;
+        }
+
+        Ok(outstanding)
+    }
+
+    async fn transition(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+        to: BillStatus,
+    ) -> Result<()> {
+        Self::ensure_well_formed(bill_id)?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select bs.name from bill b
+            inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+            where b.bill_id = $1 and b.org_serial_id = org_serial_id($2)
+            for update of b;",
+        )
+        .bind(bill_id)
+        .bind(ctx.org_id());
+
+        let (from,) = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::BillNotFound {
+                bill_id: bill_id.to_string(),
+            },
+        )?;
+        let from = BillStatus::try_from(from).unwrap_or_default();
+
+        if !from.can_transition_to(to) {
+            return Err(Error::BillStatusTransitionInvalid {
+                bill_id: bill_id.to_string(),
+                from,
+                to,
+            });
+        }
+
+        if to == BillStatus::Void {
+            let sqlx_query = sqlx::query_as::<_, (bool,)>(
+                "select exists (select 1 from payment p
+                    inner join bill b on b.serial_id = p.bill_serial_id
+                    where b.bill_id = $1);",
+            )
+            .bind(bill_id);
+
+            let (has_payments,) = mm.dbx().fetch_one(sqlx_query).await?;
+
+            if has_payments {
+                return Err(Error::BillHasPayments {
+                    bill_id: bill_id.to_string(),
+                });
+            }
+        }
+
+        if to == BillStatus::Paid {
+            let currency = Currency::default();
+            let outstanding =
+                Self::outstanding(&mm, bill_id, currency, now).await?;
+
+            if outstanding != BigDecimal::from(0) {
+                return Err(Error::BillNotSettled {
+                    bill_id: bill_id.to_string(),
+                    outstanding: Money::new(outstanding, currency),
+                });
+            }
+        }
+
+        let sqlx_query = sqlx::query(
+            "update bill set
+                bill_status_serial_id = (select serial_id from bill_status where name = $2),
+                mtime = $3
+            where bill_id = $1;",
+        )
+        .bind(bill_id)
+        .bind(to.to_string())
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
 }
 
 // region:    --- Tests
@@ -98,7 +904,20 @@ mod tests {
     pub type Error = Box<dyn std::error::Error>; // For tests.
 
     use super::*;
-    use crate::{_dev_utils, model::seller::SellerBmc};
+    use crate::{
+        _dev_utils,
+        ctx::Ctx,
+        model::{
+            exchange_rate::{self, ExchangeRateBmc},
+            payment,
+            seller::{self, SellerBmc},
+            transaction::{
+                self, TransactionFilter, TransactionKind,
+                TransactionListOptions,
+            },
+            user::{UserBmc, UserForCreate},
+        },
+    };
     use serde_json::json;
     use serial_test::serial;
 
@@ -107,11 +926,13 @@ mod tests {
     async fn test_create_ok() -> Result<()> {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
         let fx_seller_name = "test_create_ok-seller_name-01";
         let fx_remark = Some(String::from("test_create_ok-bill_remark-01"));
 
         // SellerForCreate::
         let sellers = SellerBmc::create(
+            &ctx,
             &mm,
             serde_json::from_value(json!([
                 { "name": fx_seller_name  }
@@ -122,6 +943,7 @@ mod tests {
 
         // -- Exec
         let bill_id = BillBmc::create(
+            &ctx,
             &mm,
             BillForCreate {
                 remark: fx_remark.clone(),
@@ -131,7 +953,7 @@ mod tests {
         .await?;
 
         // -- Check
-        let bill: Bill = BillBmc::get_by_bill_id(&mm, &bill_id).await?;
+        let bill: Bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
         assert_eq!(bill.remark, fx_remark);
 
         // // -- Clean
@@ -139,6 +961,857 @@ mod tests {
 
         Ok(())
     }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_retry_on_id_collision_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_bill_id =
+            BillBmc::create(&ctx, &mm, serde_json::from_value(json!({}))?)
+                .await?;
+        // Popped from the end, the first attempt collides.
+        let mut fx_bill_ids = vec![BILL_ID.generate(), fx_bill_id.clone()];
+
+        // -- Exec
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+        let dbx = mm.dbx();
+        let (bill_id,) = dbx
+            .retry_on_id_collision("bill_bill_id_key", || {
+                let bill_id = fx_bill_ids.pop();
+                async move {
+                    let sqlx_query = sqlx::query_as::<_, (String,)>(
+                        "insert into bill (bill_id, org_serial_id)
+                        values ($1, org_serial_id('demo1'))
+                        returning bill_id;",
+                    )
+                    .bind(bill_id);
+
+                    dbx.fetch_one(sqlx_query).await
+                }
+            })
+            .await?;
+        mm.dbx().commit_txn().await?;
+
+        // -- Check
+        assert!(fx_bill_ids.is_empty(), "should have retried once");
+        assert_ne!(bill_id, fx_bill_id);
+        assert!(BILL_ID.is_valid(&bill_id));
+        BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_unknown_seller_and_bill_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_unknown_seller = json!({ "sellerId": "nope" });
+
+        // -- Exec & Check bill without seller
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "remark": "no seller" })).unwrap(),
+        )
+        .await?;
+        let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+        assert_eq!(bill.seller_id, None);
+
+        // -- Exec & Check unknown seller
+        assert!(matches!(
+            BillBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(fx_unknown_seller.clone())?
+            )
+            .await,
+            Err(super::Error::Seller(
+                crate::model::seller::Error::SellerNotFound { .. }
+            ))
+        ));
+        assert!(matches!(
+            BillBmc::update(
+                &ctx,
+                &mm,
+                &bill_id,
+                serde_json::from_value(fx_unknown_seller)?
+            )
+            .await,
+            Err(super::Error::Seller(
+                crate::model::seller::Error::SellerNotFound { .. }
+            ))
+        ));
+        let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+        assert_eq!(bill.seller_id, None);
+
+        // -- Exec & Check unknown bill
+        let res = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "paymentMethod": "Cash", "sellerCost": 1, "billId": "nope" }
+            ]))
+            .unwrap(),
+        )
+        .await;
+        assert!(
+            matches!(
+                &res,
+                Err(transaction::Error::Bill(super::Error::BillNotFound {
+                    bill_id
+                })) if bill_id == "nope"
+            ),
+            "{res:?}"
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_mistyped_ids_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_typo = |id: &str| {
+            let typo = if id[1..].starts_with('A') { "B" } else { "A" };
+            format!("{}{typo}{}", &id[..1], &id[2..])
+        };
+        let seller_ids = SellerBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([{ "name": "test_mistyped" }]))
+                .unwrap(),
+        )
+        .await?;
+        let seller_id = fx_typo(&seller_ids[0].seller_id);
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({})).unwrap(),
+        )
+        .await?;
+        let bill_id = fx_typo(&bill_id);
+
+        // -- Exec & Check
+        assert!(matches!(
+            SellerBmc::get_by_seller_id(&ctx, &mm, &seller_id).await,
+            Err(seller::Error::SellerNotFound { .. })
+        ));
+        assert!(matches!(
+            BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await,
+            Err(super::Error::BillNotFound { .. })
+        ));
+        assert!(matches!(
+            BillBmc::finalize(&ctx, &mm, &bill_id).await,
+            Err(super::Error::BillNotFound { .. })
+        ));
+        let res = PaymentBmc::create(
+            &ctx,
+            &mm,
+            &bill_id,
+            serde_json::from_value(
+                json!({ "amount": "1", "paymentMethod": "Cash" }),
+            )
+            .unwrap(),
+        )
+        .await;
+        assert!(
+            matches!(
+                &res,
+                Err(payment::Error::Bill(super::Error::BillNotFound { .. }))
+            ),
+            "{res:?}"
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_other_org_isolation_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_other_user_id = _dev_utils::seed_user(
+            &Ctx::root_ctx(),
+            &mm,
+            UserForCreate {
+                name: "other".to_string(),
+                email: "other@example.com".to_string(),
+                pwd_clear: "welcome".to_string(),
+            },
+        )
+        .await?;
+        // The own organisation of the new user.
+        let fx_other_org_id =
+            UserBmc::first_by_user_id(&Ctx::root_ctx(), &mm, &fx_other_user_id)
+                .await?
+                .and_then(|v| v.org_id)
+                .ok_or("Should have an organisation")?;
+        let fx_other_ctx =
+            Ctx::new_with_org(&fx_other_user_id, &fx_other_org_id)?;
+        let other_bill_id = BillBmc::create(
+            &fx_other_ctx,
+            &mm,
+            serde_json::from_value(json!({ "remark": "other" })).unwrap(),
+        )
+        .await?;
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "tags": ["veg"], "paymentMethod": "Cash", "sellerCost": 10, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let transaction_id = &transactions[0].transaction_id;
+
+        // -- Exec & Check reads
+        assert!(matches!(
+            SellerBmc::get_by_seller_id(&fx_other_ctx, &mm, "0001").await,
+            Err(seller::Error::SellerNotFound { .. })
+        ));
+        assert!(
+            SellerBmc::get_all(&fx_other_ctx, &mm, None)
+                .await?
+                .is_empty()
+        );
+        assert!(matches!(
+            BillBmc::get_by_bill_id(&fx_other_ctx, &mm, &bill_id).await,
+            Err(super::Error::BillNotFound { .. })
+        ));
+        let bills = BillBmc::list(
+            &fx_other_ctx,
+            &mm,
+            BillFilter::default(),
+            BillListOptions::default(),
+        )
+        .await?;
+        let bill_ids = bills.items.iter().map(|v| v.bill_id.as_str());
+        assert_eq!(bill_ids.collect::<Vec<_>>(), [other_bill_id.as_str()]);
+        let page = TransactionBmc::list(
+            &fx_other_ctx,
+            &mm,
+            TransactionFilter::default(),
+            TransactionListOptions::default(),
+        )
+        .await?;
+        assert!(page.items.is_empty());
+
+        // -- Exec & Check writes
+        assert!(matches!(
+            TransactionBmc::create(
+                &fx_other_ctx,
+                &mm,
+                serde_json::from_value(json!([
+                    { "name": "tr 2", "paymentMethod": "Cash", "sellerCost": 1, "billId": bill_id }
+                ]))
+                .unwrap(),
+            )
+            .await,
+            Err(transaction::Error::Bill(super::Error::BillNotFound { .. }))
+        ));
+        assert!(matches!(
+            TransactionBmc::delete(&fx_other_ctx, &mm, transaction_id).await,
+            Err(transaction::Error::TransactionNotFound { .. })
+        ));
+        assert!(matches!(
+            BillBmc::delete(&fx_other_ctx, &mm, &bill_id).await,
+            Err(super::Error::BillNotFound { .. })
+        ));
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+        assert_eq!(details.transactions.len(), 1);
+
+        // -- Exec & Check the other way round
+        assert!(matches!(
+            BillBmc::get_by_bill_id(&ctx, &mm, &other_bill_id).await,
+            Err(super::Error::BillNotFound { .. })
+        ));
+        BillBmc::get_by_bill_id(&fx_other_ctx, &mm, &other_bill_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_details_totals_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "allocations": [{ "tag": "veg", "amount": 8 }, { "tag": "food", "amount": 2.5 }], "paymentMethod": "Cash", "sellerCost": 10.5, "billId": bill_id },
+                { "name": "tr 2", "tags": ["veg"], "paymentMethod": "Upi", "sellerCost": 20, "billId": bill_id },
+                { "name": "tr 3", "paymentMethod": "Cash", "sellerCost": 5, "billId": bill_id },
+                { "name": "tr no bill", "paymentMethod": "Cash", "sellerCost": 1000 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+
+        // -- Check
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let totals = |totals: &[BillTotal]| {
+            totals
+                .iter()
+                .map(|v| (v.name.clone(), v.total.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(details.transactions.len(), 3);
+        assert_eq!(details.total_seller_cost, dec("35.5"));
+        assert_eq!(
+            totals(&details.totals_by_payment_method),
+            [("Cash".into(), dec("15.5")), ("Upi".into(), dec("20"))]
+        );
+        assert_eq!(
+            totals(&details.totals_by_tag),
+            [("food".into(), dec("2.5")), ("veg".into(), dec("28"))]
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_details_kinds_sign_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        let purchases = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr purchase", "tags": ["veg"], "paymentMethod": "Cash", "sellerCost": 118, "taxRate": 18, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr return", "kind": "Return", "originalTransactionId": purchases[0].transaction_id, "tags": ["veg"], "paymentMethod": "Cash", "sellerCost": 59, "taxRate": 18, "billId": bill_id },
+                { "name": "tr discount", "kind": "Discount", "paymentMethod": "Cash", "sellerCost": 10, "billId": bill_id },
+                { "name": "tr adjustment", "kind": "Adjustment", "paymentMethod": "Upi", "sellerCost": -1.5, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+        let ledger = SellerBmc::get_ledger(
+            &ctx,
+            &mm,
+            "0001",
+            Default::default(),
+            Currency::Inr,
+        )
+        .await?;
+
+        // -- Check
+        assert_eq!(
+            details.total_seller_cost,
+            dec("47.5"),
+            "118 - 59 - 10 - 1.5"
+        );
+        assert_eq!(details.outstanding, dec("47.5"));
+        assert_eq!(details.totals_by_tag[0].total, dec("59"));
+        assert_eq!(details.tax_summary.taxable_value, dec("50"));
+        assert_eq!(details.tax_summary.cgst, dec("4.5"));
+        assert_eq!(details.tax_summary.untaxed, dec("-11.5"));
+        assert_eq!(details.transactions[1].kind, TransactionKind::Return);
+        assert_eq!(ledger.closing_balance, dec("47.5"));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_details_currency_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr inr", "paymentMethod": "Cash", "sellerCost": 160, "transactionTime": "2025-01-01T10:00:00Z", "billId": bill_id },
+                { "name": "tr usd", "paymentMethod": "Card", "sellerCost": 10, "currency": "USD", "transactionTime": "2025-01-02T10:00:00Z", "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec & Check missing rate
+        let res =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await;
+        assert!(
+            matches!(
+                &res,
+                Err(super::Error::ExchangeRate(
+                    exchange_rate::Error::ExchangeRateNotFound { date, .. }
+                )) if date == "2025-01-02"
+            ),
+            "{res:?}"
+        );
+
+        // -- Exec & Check converted
+        ExchangeRateBmc::set(
+            &Ctx::root_ctx(),
+            &mm,
+            serde_json::from_value(json!([
+                { "rateDate": "2025-01-01", "from": "USD", "to": "INR", "rate": 80 },
+                { "rateDate": "2025-01-02", "from": "USD", "to": "INR", "rate": 82 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let inr =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+        let usd =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Usd).await?;
+        assert_eq!(inr.transactions[1].currency, Currency::Usd);
+        assert_eq!(inr.total_seller_cost, dec("980"), "160 + 10 * 82");
+        assert_eq!(usd.currency, Currency::Usd);
+        assert_eq!(usd.total_seller_cost, dec("12"), "160 / 80 + 10");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_details_tax_summary_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 18 a", "paymentMethod": "Cash", "sellerCost": 1180, "taxRate": 18, "billId": bill_id },
+                { "name": "tr 18 b", "paymentMethod": "Cash", "sellerCost": 100, "taxRate": 18, "billId": bill_id },
+                { "name": "tr 5", "paymentMethod": "Card", "sellerCost": 105.50, "taxRate": 5, "gstSupply": "interState", "billId": bill_id },
+                { "name": "tr exempt", "paymentMethod": "Card", "sellerCost": 20, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+
+        // -- Check
+        let tax = details.tax_summary;
+        let rates = tax.by_rate.iter().map(|v| &v.rate).collect::<Vec<_>>();
+        assert_eq!(rates, [&dec("5"), &dec("18")]);
+        assert_eq!(
+            tax.by_rate[1].taxable_value,
+            dec("1084.75"),
+            "1000 + 84.75"
+        );
+        assert_eq!(tax.by_rate[1].cgst, dec("97.63"), "90 + 7.63");
+        assert_eq!(tax.taxable_value, dec("1185.23"));
+        assert_eq!((&tax.cgst, &tax.sgst), (&dec("97.63"), &dec("97.63")));
+        assert_eq!(tax.igst, dec("5.02"));
+        assert_eq!(tax.total_tax, dec("200.28"));
+        assert_eq!(tax.round_off, dec("-0.01"));
+        assert_eq!(tax.untaxed, dec("20"));
+        assert_eq!(
+            &tax.taxable_value + &tax.total_tax + &tax.round_off + &tax.untaxed,
+            details.total_seller_cost
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_update_delete_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let mut bill_ids = Vec::new();
+        for remark in ["bill 1", "bill 2", "bill 3"] {
+            let bill_id = BillBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(
+                    json!({ "remark": remark, "sellerId": "0002" }),
+                )
+                .unwrap(),
+            )
+            .await?;
+            bill_ids.push(bill_id);
+        }
+        let fx_filter = || BillFilter {
+            seller_id: Some("0002".to_string()),
+            ..Default::default()
+        };
+
+        // -- Exec & Check list
+        let page_1 = BillBmc::list(
+            &ctx,
+            &mm,
+            fx_filter(),
+            BillListOptions {
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let page_2 = BillBmc::list(
+            &ctx,
+            &mm,
+            fx_filter(),
+            BillListOptions {
+                limit: Some(2),
+                cursor: page_1.next_cursor.clone(),
+            },
+        )
+        .await?;
+        let listed = page_1
+            .items
+            .iter()
+            .chain(page_2.items.iter())
+            .map(|v| v.bill_id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(listed, bill_ids.iter().rev().cloned().collect::<Vec<_>>());
+        assert!(page_2.next_cursor.is_none());
+
+        // -- Exec & Check update
+        BillBmc::update(
+            &ctx,
+            &mm,
+            &bill_ids[0],
+            serde_json::from_value(
+                json!({ "remark": "fixed remark", "sellerId": "0003" }),
+            )
+            .unwrap(),
+        )
+        .await?;
+        let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_ids[0]).await?;
+        assert_eq!(bill.remark.as_deref(), Some("fixed remark"));
+        assert_eq!(bill.seller_id.as_deref(), Some("0003"));
+        let res = BillBmc::update(
+            &ctx,
+            &mm,
+            &bill_ids[0],
+            serde_json::from_value(json!({ "remark": "r".repeat(257) }))
+                .unwrap(),
+        )
+        .await;
+        assert!(
+            matches!(
+                &res,
+                Err(super::Error::Validation(
+                    crate::model::validation::Error::Validation { errors },
+                )) if errors.len() == 1 && errors[0].path == "remark"
+            ),
+            "{res:?}"
+        );
+
+        // -- Exec & Check delete
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "paymentMethod": "Cash", "sellerCost": 1, "billId": bill_ids[0] }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        BillBmc::delete(&ctx, &mm, &bill_ids[0]).await?;
+        assert!(matches!(
+            BillBmc::get_by_bill_id(&ctx, &mm, &bill_ids[0]).await,
+            Err(super::Error::BillNotFound { .. })
+        ));
+        let transactions = TransactionBmc::get_by_transaction_ids(
+            &ctx,
+            &mm,
+            vec![transactions[0].transaction_id.clone()],
+        )
+        .await?;
+        assert_eq!(transactions.len(), 1, "transaction should be kept");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_status_workflow_locks_transactions_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "paymentMethod": "Cash", "sellerCost": 1, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_transaction_id = transactions[0].transaction_id.clone();
+        let is_not_editable = |res: transaction::Result<_>| {
+            matches!(
+                res,
+                Err(transaction::Error::Bill(super::Error::BillNotEditable {
+                    status: BillStatus::Finalized,
+                    ..
+                }))
+            )
+        };
+
+        // -- Exec
+        assert!(matches!(
+            BillBmc::mark_paid(&ctx, &mm, &bill_id).await,
+            Err(super::Error::BillStatusTransitionInvalid { .. })
+        ));
+        BillBmc::finalize(&ctx, &mm, &bill_id).await?;
+
+        // -- Check
+        let res = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 2", "paymentMethod": "Cash", "sellerCost": 1, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await;
+        assert!(is_not_editable(res.map(|_| ())));
+        let res = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &fx_transaction_id,
+            serde_json::from_value(json!({ "name": "tr 1 renamed" })).unwrap(),
+        )
+        .await;
+        assert!(is_not_editable(res.map(|_| ())));
+        let res = TransactionBmc::delete(&ctx, &mm, &fx_transaction_id).await;
+        assert!(is_not_editable(res));
+
+        let res = BillBmc::update(
+            &ctx,
+            &mm,
+            &bill_id,
+            serde_json::from_value(json!({ "sellerId": "0002" })).unwrap(),
+        )
+        .await;
+        assert!(
+            matches!(
+                res,
+                Err(super::Error::BillNotEditable {
+                    status: BillStatus::Finalized,
+                    ..
+                })
+            ),
+            "{res:?}"
+        );
+
+        let res = BillBmc::mark_paid(&ctx, &mm, &bill_id).await;
+        assert!(
+            matches!(
+                &res,
+                Err(super::Error::BillNotSettled { outstanding, .. })
+                    if outstanding.amount == BigDecimal::from(1)
+            ),
+            "{res:?}"
+        );
+        PaymentBmc::create(
+            &ctx,
+            &mm,
+            &bill_id,
+            serde_json::from_value(
+                json!({ "amount": "1", "paymentMethod": "Cash" }),
+            )
+            .unwrap(),
+        )
+        .await?;
+        let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+        assert_eq!(bill.status, BillStatus::Paid);
+        assert!(matches!(
+            BillBmc::void(&ctx, &mm, &bill_id).await,
+            Err(super::Error::BillStatusTransitionInvalid { .. })
+        ));
+
+        Ok(())
+    }
+    #[serial]
+    #[tokio::test]
+    async fn test_settle_across_rate_dates_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        ExchangeRateBmc::set(
+            &Ctx::root_ctx(),
+            &mm,
+            serde_json::from_value(json!([
+                { "rateDate": "2025-02-01", "from": "USD", "to": "INR", "rate": 80 },
+                { "rateDate": "2025-02-03", "from": "USD", "to": "INR", "rate": 83 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_bill =
+            async |transactions: serde_json::Value| -> Result<String> {
+                let bill_id = BillBmc::create(
+                    &ctx,
+                    &mm,
+                    serde_json::from_value(json!({ "sellerId": "0001" }))
+                        .unwrap(),
+                )
+                .await?;
+                let mut transactions = transactions;
+                for transaction in transactions.as_array_mut().unwrap() {
+                    transaction["billId"] = json!(bill_id);
+                }
+                TransactionBmc::create(
+                    &ctx,
+                    &mm,
+                    serde_json::from_value(transactions).unwrap(),
+                )
+                .await?;
+                BillBmc::finalize(&ctx, &mm, &bill_id).await?;
+                Ok(bill_id)
+            };
+        let paid_bill_id = fx_bill(json!([
+            { "name": "tr usd", "paymentMethod": "Card", "sellerCost": 100, "currency": "USD", "transactionTime": "2025-02-01T10:00:00Z" }
+        ]))
+        .await?;
+        let discounted_bill_id = fx_bill(json!([
+            { "name": "tr usd", "paymentMethod": "Card", "sellerCost": 50, "currency": "USD", "transactionTime": "2025-02-01T10:00:00Z" },
+            { "name": "tr discount", "kind": "Discount", "paymentMethod": "Card", "sellerCost": 50, "currency": "USD", "transactionTime": "2025-02-03T10:00:00Z" }
+        ]))
+        .await?;
+
+        // -- Exec
+        PaymentBmc::create(
+            &ctx,
+            &mm,
+            &paid_bill_id,
+            serde_json::from_value(json!({
+                "amount": "100",
+                "currency": "USD",
+                "paymentMethod": "Card",
+                "paymentTime": "2025-02-03T10:00:00Z"
+            }))
+            .unwrap(),
+        )
+        .await?;
+        BillBmc::mark_paid(&ctx, &mm, &discounted_bill_id).await?;
+
+        // -- Check
+        for bill_id in [paid_bill_id, discounted_bill_id] {
+            let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+            assert_eq!(bill.status, BillStatus::Paid);
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_void_with_payments_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "paymentMethod": "Cash", "sellerCost": 10, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        BillBmc::finalize(&ctx, &mm, &bill_id).await?;
+        PaymentBmc::create(
+            &ctx,
+            &mm,
+            &bill_id,
+            serde_json::from_value(
+                json!({ "amount": "4", "paymentMethod": "Cash" }),
+            )
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let res = BillBmc::void(&ctx, &mm, &bill_id).await;
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(super::Error::BillHasPayments { .. })),
+            "{res:?}"
+        );
+        let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+        assert_eq!(bill.status, BillStatus::Finalized);
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
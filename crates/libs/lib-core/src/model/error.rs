@@ -22,4 +22,22 @@ pub enum Error {
 
     #[error(transparent)]
     Transaction(#[from] model::transaction::Error),
+
+    #[error(transparent)]
+    Payment(#[from] model::payment::Error),
+
+    #[error(transparent)]
+    Tag(#[from] model::tag::Error),
+
+    #[error(transparent)]
+    Unit(#[from] model::unit::Error),
+
+    #[error(transparent)]
+    ExchangeRate(#[from] model::exchange_rate::Error),
+
+    #[error(transparent)]
+    Idempotency(#[from] model::idempotency::Error),
+
+    #[error(transparent)]
+    Org(#[from] model::org::Error),
 }
@@ -0,0 +1,31 @@
+use crate::model::acs;
+use crate::model::store::dbx;
+use crate::model::validation;
+use lib_utils::money::Currency;
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    ExchangeRateNotFound {
+        from: Currency,
+        to: Currency,
+        /// The (utc) day, e.g. `2025-01-31`.
+        date: String,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Dbx(#[from] dbx::Error),
+}
+
+// region:    --- Error Boilerplate
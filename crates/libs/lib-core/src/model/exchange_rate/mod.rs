@@ -0,0 +1,376 @@
+//! Daily exchange rates, used to convert reports and bill totals to a
+//! reporting currency.
+//!
+//! An amount is converted at the rate of the (utc) day it was paid or
+//! charged, that is the latest rate on or before that day. A rate also
+//! converts the other way round (`1 / rate`) when the inverse pair is not
+//! set.
+//!
+//! The rates are shared by all the organisations, so only set by a
+//! `SysAdmin` (`exchange_rate.set`).
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::acs::{self, Privilege};
+use crate::model::validation::{Validate, Validator};
+use bigdecimal::BigDecimal;
+use lib_utils::money::{Currency, Money};
+use lib_utils::time::TimeRfc3339;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use time::Date;
+
+mod error;
+
+pub use error::{Error, Result};
+
+// NOTE: In its own module, the macro expansion would pick the `Result` above.
+mod serde_date {
+    time::serde::format_description!(
+        pub(super) iso_date,
+        Date,
+        "[year]-[month]-[day]"
+    );
+}
+
+use serde_date::iso_date;
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRate {
+    #[serde(with = "iso_date")]
+    pub rate_date: Date,
+    #[sqlx(rename = "from_code", try_from = "String")]
+    pub from: Currency,
+    #[sqlx(rename = "to_code", try_from = "String")]
+    pub to: Currency,
+    /// Price of one `from` unit in `to`.
+    pub rate: BigDecimal,
+}
+
+/// Rate of a day, replacing the one already set for the same pair.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateForSet {
+    #[serde(with = "iso_date")]
+    rate_date: Date,
+    from: Currency,
+    to: Currency,
+    rate: BigDecimal,
+}
+
+impl Validate for ExchangeRateForSet {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator.positive(path, "rate", &self.rate).expect(
+            path,
+            "to",
+            self.from != self.to,
+            "must not be the same currency as from",
+        );
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateFilter {
+    pub from: Option<Currency>,
+    pub to: Option<Currency>,
+}
+
+/// Currency the reports and bill totals are converted to.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportCurrency {
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+pub struct ExchangeRateBmc;
+
+impl ExchangeRateBmc {
+    pub async fn set(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rates_s: Vec<ExchangeRateForSet>,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::ExchangeRateSet).await?;
+
+        rates_s.validate()?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        let mut rate_dates = Vec::with_capacity(rates_s.len());
+        let mut froms = Vec::with_capacity(rates_s.len());
+        let mut tos = Vec::with_capacity(rates_s.len());
+        let mut rates = Vec::with_capacity(rates_s.len());
+        for rate_s in rates_s {
+            rate_dates.push(rate_s.rate_date);
+            froms.push(rate_s.from.code());
+            tos.push(rate_s.to.code());
+            rates.push(rate_s.rate);
+        }
+
+        // NOTE: The last rate wins when a pair is sent twice for a day.
+        let sqlx_query = sqlx::query(
+            "insert into exchange_rate (rate_date, from_currency_serial_id, to_currency_serial_id, rate, ctime, mtime)
+            select distinct on (t.rate_date, cf.serial_id, ct.serial_id)
+                t.rate_date, cf.serial_id, ct.serial_id, t.rate, $5, $5
+            from unnest(
+                $1::date[],
+                $2::text[],
+                $3::text[],
+                $4::numeric[]
+            ) with ordinality as t(rate_date, from_code, to_code, rate, idx)
+            inner join currency cf on cf.code = t.from_code
+            inner join currency ct on ct.code = t.to_code
+            order by t.rate_date, cf.serial_id, ct.serial_id, t.idx desc
+            on conflict (rate_date, from_currency_serial_id, to_currency_serial_id)
+            do update set rate = excluded.rate, mtime = excluded.mtime;",
+        )
+        .bind(rate_dates)
+        .bind(froms)
+        .bind(tos)
+        .bind(rates)
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Rates matching `filter`, newest first.
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filter: ExchangeRateFilter,
+    ) -> Result<Vec<ExchangeRate>> {
+        acs::require(ctx, mm, Privilege::ExchangeRateRead).await?;
+
+        let ExchangeRateFilter { from, to } = filter;
+
+        let sqlx_query = sqlx::query_as::<_, ExchangeRate>(
+            "select er.rate_date, cf.code as from_code, ct.code as to_code, er.rate
+            from exchange_rate er
+            inner join currency cf on cf.serial_id = er.from_currency_serial_id
+            inner join currency ct on ct.serial_id = er.to_currency_serial_id
+            where ($1::text is null or cf.code = $1)
+              and ($2::text is null or ct.code = $2)
+            order by er.rate_date desc, cf.code, ct.code;",
+        )
+        .bind(from.map(|v| v.code()))
+        .bind(to.map(|v| v.code()));
+
+        let rates = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(rates)
+    }
+
+    /// `money` in `to`, at the rate of the day of `at`.
+    pub async fn convert(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        money: &Money,
+        to: Currency,
+        at: &TimeRfc3339,
+    ) -> Result<Money> {
+        acs::require(ctx, mm, Privilege::ExchangeRateRead).await?;
+
+        let date = at.inner().date();
+
+        let sqlx_query = sqlx::query_as::<_, (Option<BigDecimal>,)>(
+            "select exchange_rate_at(
+                (select serial_id from currency where code = $1),
+                (select serial_id from currency where code = $2),
+                $3
+            );",
+        )
+        .bind(money.currency.code())
+        .bind(to.code())
+        .bind(date);
+
+        let (rate,) = mm.dbx().fetch_one(sqlx_query).await?;
+        let rate = rate.ok_or_else(|| Error::ExchangeRateNotFound {
+            from: money.currency,
+            to,
+            date: date.to_string(),
+        })?;
+
+        Ok(money.convert(&rate, to))
+    }
+
+    /// Fail when an amount of the bills (of `ctx`) of `bill_id` and/or
+    /// `seller_id` (their transactions and payments) can not be converted to
+    /// `to`.
+    ///
+    /// The `convert_amount` sql function is null on a missing rate, which
+    /// `sum` would silently skip, so the reports call this first.
+    pub(crate) async fn ensure_for_bills(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: Option<&str>,
+        seller_id: Option<&str>,
+        to: Currency,
+    ) -> Result<()> {
+        let sqlx_query = sqlx::query_as::<_, (String, Date)>(
+            "select c.code, x.at_date from (
+                select tr.currency_serial_id,
+                    (tr.transaction_time at time zone 'UTC')::date as at_date
+                from transaction tr
+                inner join bill b on b.serial_id = tr.bill_serial_id
+                left join seller s on s.serial_id = b.seller_serial_id
+                where b.org_serial_id = org_serial_id($4)
+                  and ($1::text is null or b.bill_id = $1)
+                  and ($2::text is null or s.seller_id = $2)
+                union
+                select p.currency_serial_id,
+                    (p.payment_time at time zone 'UTC')::date
+                from payment p
+                inner join bill b on b.serial_id = p.bill_serial_id
+                left join seller s on s.serial_id = b.seller_serial_id
+                where b.org_serial_id = org_serial_id($4)
+                  and ($1::text is null or b.bill_id = $1)
+                  and ($2::text is null or s.seller_id = $2)
+            ) x
+            inner join currency c on c.serial_id = x.currency_serial_id
+            where exchange_rate_at(
+                x.currency_serial_id,
+                (select serial_id from currency where code = $3),
+                x.at_date
+            ) is null
+            order by x.at_date, c.code
+            limit 1;",
+        )
+        .bind(bill_id)
+        .bind(seller_id)
+        .bind(to.code())
+        .bind(ctx.org_id());
+
+        match mm.dbx().fetch_optional(sqlx_query).await? {
+            Some((from, date)) => Err(Error::ExchangeRateNotFound {
+                from: Currency::try_from(from).unwrap_or_default(),
+                to,
+                date: date.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::validation;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_set_list_convert_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_time = |v: &str| TimeRfc3339::try_from(v).unwrap();
+        let fx_usd = Money::new(dec("10"), Currency::Usd);
+        ExchangeRateBmc::set(
+            &Ctx::root_ctx(),
+            &mm,
+            serde_json::from_value(json!([
+                { "rateDate": "2025-01-01", "from": "USD", "to": "INR", "rate": 80 },
+                { "rateDate": "2025-01-03", "from": "USD", "to": "INR", "rate": 82 },
+                { "rateDate": "2025-01-03", "from": "USD", "to": "INR", "rate": 83 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let rates =
+            ExchangeRateBmc::list(&ctx, &mm, ExchangeRateFilter::default())
+                .await?;
+        let day_2 = ExchangeRateBmc::convert(
+            &ctx,
+            &mm,
+            &fx_usd,
+            Currency::Inr,
+            &fx_time("2025-01-02T23:00:00Z"),
+        )
+        .await?;
+        let day_3 = ExchangeRateBmc::convert(
+            &ctx,
+            &mm,
+            &fx_usd,
+            Currency::Inr,
+            &fx_time("2025-01-03T00:00:00Z"),
+        )
+        .await?;
+        let inverse = ExchangeRateBmc::convert(
+            &ctx,
+            &mm,
+            &Money::new(dec("166"), Currency::Inr),
+            Currency::Usd,
+            &fx_time("2025-01-04T00:00:00Z"),
+        )
+        .await?;
+        let before = ExchangeRateBmc::convert(
+            &ctx,
+            &mm,
+            &fx_usd,
+            Currency::Inr,
+            &fx_time("2024-12-31T00:00:00Z"),
+        )
+        .await;
+
+        // -- Check
+        assert_eq!(rates.len(), 2, "one rate per day and pair");
+        assert_eq!(rates[0].rate, dec("83"), "last rate wins");
+        assert_eq!(day_2.to_string(), "800.00 INR");
+        assert_eq!(day_3.to_string(), "830.00 INR");
+        assert_eq!(inverse.to_string(), "2.00 USD");
+        assert!(matches!(
+            before,
+            Err(super::Error::ExchangeRateNotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_set_validation_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+
+        // -- Exec
+        let res = ExchangeRateBmc::set(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "rateDate": "2025-01-01", "from": "USD", "to": "USD", "rate": 0 }
+            ]))
+            .unwrap(),
+        )
+        .await;
+
+        // -- Check
+        let Err(super::Error::Validation(validation::Error::Validation {
+            errors,
+        })) = res
+        else {
+            return Err("Should fail validation".into());
+        };
+        let paths = errors.iter().map(|v| v.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, ["[0].rate", "[0].to"]);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
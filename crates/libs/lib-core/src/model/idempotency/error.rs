@@ -0,0 +1,22 @@
+use crate::model::store::dbx;
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    IdempotencyKeyReused {
+        key: String,
+    },
+
+    IdempotencyKeyInProgress {
+        key: String,
+    },
+
+    #[error(transparent)]
+    Dbx(#[from] dbx::Error),
+}
+
+// region:    --- Error Boilerplate
@@ -0,0 +1,400 @@
+//! Stored first responses of the create requests sent with an
+//! `Idempotency-Key`, so a client retrying on a flaky connection does not
+//! create the same entities twice.
+//!
+//! Keys are scoped per user and active organisation. The request (method,
+//! path and body) is kept as a sha256 hash, so a key reused for another route
+//! or with another body is rejected rather than replayed.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use lib_utils::time::TimeRfc3339;
+use sqlx::prelude::FromRow;
+use time::Duration;
+
+mod error;
+
+pub use error::{Error, Result};
+
+/// How long a stored response is replayed.
+const IDEMPOTENCY_TTL: Duration = Duration::hours(24);
+
+/// How long a request without a stored response holds its key, after which
+/// it is considered lost (e.g., the server stopped mid request).
+const IN_PROGRESS_TTL: Duration = Duration::minutes(5);
+
+/// Response of the first request with a key.
+#[derive(Debug, FromRow)]
+pub struct StoredResponse {
+    pub status: i16,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum IdempotencyBegin {
+    /// First request with this key, it must be executed then `complete`d
+    /// (or `abort`ed).
+    New,
+    Replay(StoredResponse),
+}
+
+pub struct IdempotencyBmc;
+
+impl IdempotencyBmc {
+    /// Claim `key` for the user and the organisation of `ctx`, unless it was already used within
+    /// the TTL. The expired keys (of all the users) are purged first.
+    pub async fn begin(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        key: &str,
+        method: &str,
+        path: &str,
+        request_body: &[u8],
+    ) -> Result<IdempotencyBegin> {
+        let now = TimeRfc3339::now_utc().inner();
+
+        let mut request = format!("{method} {path}\n").into_bytes();
+        request.extend_from_slice(request_body);
+
+        let sqlx_query = sqlx::query(
+            "delete from idempotency_key
+            where ctime < $2
+                or (ctime < $1 and response_status is null);",
+        )
+        .bind(now - IN_PROGRESS_TTL)
+        .bind(now - IDEMPOTENCY_TTL);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "insert into idempotency_key (user_id, org_serial_id, key, request_hash, ctime)
+            values ($1, org_serial_id($5), $2, sha256($3), $4)
+            on conflict (user_id, org_serial_id, key) do nothing
+            returning serial_id;",
+        )
+        .bind(ctx.user_id())
+        .bind(key)
+        .bind(&request)
+        .bind(now)
+        .bind(ctx.org_id());
+
+        if mm.dbx().fetch_optional(sqlx_query).await?.is_some() {
+            return Ok(IdempotencyBegin::New);
+        }
+
+        let sqlx_query =
+            sqlx::query_as::<_, (bool, Option<i16>, Option<Vec<u8>>)>(
+                "select request_hash = sha256($3), response_status,
+                    response_body
+                from idempotency_key
+                where user_id = $1 and key = $2
+                  and org_serial_id is not distinct from org_serial_id($4);",
+            )
+            .bind(ctx.user_id())
+            .bind(key)
+            .bind(&request)
+            .bind(ctx.org_id());
+
+        // NOTE: A missing key was just aborted by the first request.
+        match mm.dbx().fetch_optional(sqlx_query).await? {
+            Some((false, ..)) => Err(Error::IdempotencyKeyReused {
+                key: key.to_string(),
+            }),
+            Some((true, Some(status), body)) => {
+                Ok(IdempotencyBegin::Replay(StoredResponse {
+                    status,
+                    body: body.unwrap_or_default(),
+                }))
+            }
+            Some((true, None, _)) | None => {
+                Err(Error::IdempotencyKeyInProgress {
+                    key: key.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Store the response to replay for `key`.
+    pub async fn complete(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<()> {
+        let sqlx_query = sqlx::query(
+            "update idempotency_key set response_status = $3, response_body = $4
+            where user_id = $1 and key = $2
+              and org_serial_id is not distinct from org_serial_id($5);",
+        )
+        .bind(ctx.user_id())
+        .bind(key)
+        .bind(response.status)
+        .bind(response.body)
+        .bind(ctx.org_id());
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Release `key`, e.g. when the request failed and may be retried.
+    pub async fn abort(ctx: &Ctx, mm: &ModelManager, key: &str) -> Result<()> {
+        let sqlx_query = sqlx::query(
+            "delete from idempotency_key
+            where user_id = $1 and key = $2 and response_status is null
+              and org_serial_id is not distinct from org_serial_id($3);",
+        )
+        .bind(ctx.user_id())
+        .bind(key)
+        .bind(ctx.org_id());
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::org::OrgBmc;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_begin_complete_replay_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_other_ctx = Ctx::new("demo2")?;
+        let fx_key = "test_begin_complete_replay_ok-key";
+        let fx_path = "/api/bill";
+        let fx_body = br#"{"sellerId":"0001"}"#;
+
+        // -- Exec & Check first request
+        let begin =
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+                .await?;
+        assert!(matches!(begin, IdempotencyBegin::New));
+        assert!(matches!(
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+                .await,
+            Err(super::Error::IdempotencyKeyInProgress { .. })
+        ));
+        IdempotencyBmc::complete(
+            &ctx,
+            &mm,
+            fx_key,
+            StoredResponse {
+                status: 200,
+                body: b"stored".to_vec(),
+            },
+        )
+        .await?;
+
+        // -- Exec & Check repeats
+        let IdempotencyBegin::Replay(replay) =
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+                .await?
+        else {
+            return Err("Should replay".into());
+        };
+        assert_eq!(replay.status, 200);
+        assert_eq!(replay.body, b"stored");
+        assert!(matches!(
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, b"{}")
+                .await,
+            Err(super::Error::IdempotencyKeyReused { .. })
+        ));
+
+        // -- Exec & Check other user
+        let begin = IdempotencyBmc::begin(
+            &fx_other_ctx,
+            &mm,
+            fx_key,
+            "POST",
+            fx_path,
+            b"{}",
+        )
+        .await?;
+        assert!(matches!(begin, IdempotencyBegin::New));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_begin_other_route_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_key = "test_begin_other_route_err-key";
+        let fx_body = br#"{"remark":"same"}"#;
+        IdempotencyBmc::begin(
+            &ctx,
+            &mm,
+            fx_key,
+            "POST",
+            "/api/transaction",
+            fx_body,
+        )
+        .await?;
+        IdempotencyBmc::complete(
+            &ctx,
+            &mm,
+            fx_key,
+            StoredResponse {
+                status: 200,
+                body: b"transaction".to_vec(),
+            },
+        )
+        .await?;
+
+        // -- Exec
+        let res = IdempotencyBmc::begin(
+            &ctx,
+            &mm,
+            fx_key,
+            "POST",
+            "/api/bill",
+            fx_body,
+        )
+        .await;
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(super::Error::IdempotencyKeyReused { .. })),
+            "{res:?}"
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_begin_other_org_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_org_id = OrgBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "name": "test_begin_other_org" }))?,
+        )
+        .await?;
+        let fx_org_ctx = Ctx::new_with_org("demo1", &fx_org_id)?;
+        let fx_key = "test_begin_other_org_ok-key";
+        let fx_path = "/api/bill";
+        let fx_body = br#"{"remark":"same"}"#;
+        IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+            .await?;
+        IdempotencyBmc::complete(
+            &ctx,
+            &mm,
+            fx_key,
+            StoredResponse {
+                status: 200,
+                body: b"bill of demo1".to_vec(),
+            },
+        )
+        .await?;
+
+        // -- Exec
+        let begin = IdempotencyBmc::begin(
+            &fx_org_ctx,
+            &mm,
+            fx_key,
+            "POST",
+            fx_path,
+            fx_body,
+        )
+        .await?;
+
+        // -- Check
+        assert!(matches!(begin, IdempotencyBegin::New), "{begin:?}");
+        IdempotencyBmc::abort(&fx_org_ctx, &mm, fx_key).await?;
+        let begin =
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+                .await?;
+        assert!(matches!(begin, IdempotencyBegin::Replay(..)), "{begin:?}");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_abort_and_expired_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_key = "test_abort_and_expired_ok-key";
+        let fx_other_key = "test_abort_and_expired_ok-other-key";
+        let fx_path = "/api/transaction";
+        let fx_body = b"[]";
+
+        // -- Exec & Check abort
+        IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+            .await?;
+        IdempotencyBmc::abort(&ctx, &mm, fx_key).await?;
+        let begin =
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, b"{}")
+                .await?;
+        assert!(matches!(begin, IdempotencyBegin::New));
+
+        // -- Exec & Check expired
+        IdempotencyBmc::complete(
+            &ctx,
+            &mm,
+            fx_key,
+            StoredResponse {
+                status: 200,
+                body: Vec::new(),
+            },
+        )
+        .await?;
+        let sqlx_query = sqlx::query(
+            "update idempotency_key set ctime = ctime - $1 where key = $2;",
+        )
+        .bind(IDEMPOTENCY_TTL + Duration::seconds(1))
+        .bind(fx_key);
+        mm.dbx().execute(sqlx_query).await?;
+        let begin =
+            IdempotencyBmc::begin(&ctx, &mm, fx_key, "POST", fx_path, fx_body)
+                .await?;
+        assert!(matches!(begin, IdempotencyBegin::New));
+
+        // -- Exec & Check purge
+        let sqlx_query = sqlx::query(
+            "update idempotency_key set ctime = ctime - $1 where key = $2;",
+        )
+        .bind(IN_PROGRESS_TTL + Duration::seconds(1))
+        .bind(fx_key);
+        mm.dbx().execute(sqlx_query).await?;
+        IdempotencyBmc::begin(
+            &ctx,
+            &mm,
+            fx_other_key,
+            "POST",
+            fx_path,
+            fx_body,
+        )
+        .await?;
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "select count(*) from idempotency_key where key = $1;",
+        )
+        .bind(fx_key);
+        let (count,) = mm.dbx().fetch_one(sqlx_query).await?;
+        assert_eq!(count, 0);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
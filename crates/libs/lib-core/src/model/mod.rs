@@ -7,7 +7,7 @@
 //! - All application code data access must go through the Model layer.
 //! - The `ModelManager` holds the internal states/resources
 //!   needed by ModelControllers to access data.
-//!   (e.g., db_pool, S3 client, redis client).
+//!   (e.g., db_pool, mailer, S3 client, redis client).
 //!   (`Bmc` is short for Backend Model Controller).
 //! - In frameworks like Axum, Tauri, `ModelManager` are typically used as App State.
 //! - ModelManager are designed to be passed as an argument
@@ -16,19 +16,31 @@
 
 // region:    --- Modules
 
-mod acs;
 mod error;
 mod store;
 
+pub mod acs;
 pub mod bill;
+pub mod exchange_rate;
+pub mod idempotency;
+pub mod org;
+pub mod pagination;
+pub mod payment;
 pub mod seller;
+pub mod tag;
+pub mod tax;
 pub mod transaction;
+pub mod unit;
 pub mod user;
+pub mod validation;
 
 pub use self::error::{Error, Result};
 
+use crate::config::core_config;
+use crate::mail::{MailSender, OutboxMailSender};
 use crate::model::store::dbx::Dbx;
 use crate::model::store::new_db_pool;
+use std::sync::Arc;
 
 // endregion: --- Modules
 
@@ -37,6 +49,7 @@ use crate::model::store::new_db_pool;
 #[derive(Clone)]
 pub struct ModelManager {
     dbx: Dbx,
+    mailer: Arc<dyn MailSender>,
 }
 
 impl ModelManager {
@@ -45,17 +58,34 @@ impl ModelManager {
             Error::CantCreateModelManagerProvider(ex.to_string())
         })?;
         let dbx = Dbx::new(db_pool, false);
-        Ok(ModelManager { dbx })
+        let mailer =
+            Arc::new(OutboxMailSender::new(&core_config().MAIL_OUTBOX_DIR));
+        Ok(ModelManager { dbx, mailer })
+    }
+
+    /// Send the mails with `mailer` rather than to the outbox folder.
+    pub fn with_mailer(self, mailer: impl MailSender + 'static) -> Self {
+        ModelManager {
+            mailer: Arc::new(mailer),
+            ..self
+        }
     }
 
     pub fn new_with_txn(&self) -> ModelManager {
         let dbx = Dbx::new(self.dbx.db().clone(), true);
-        ModelManager { dbx }
+        ModelManager {
+            dbx,
+            mailer: self.mailer.clone(),
+        }
     }
 
     pub fn dbx(&self) -> &Dbx {
         &self.dbx
     }
+
+    pub fn mailer(&self) -> &dyn MailSender {
+        self.mailer.as_ref()
+    }
 }
 
 // endregion: --- ModelManager
@@ -0,0 +1,49 @@
+use crate::model::org::OrgRole;
+use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::validation;
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    /// Also when the user of the ctx is not a member of it.
+    OrgNotFound {
+        org_id: String,
+    },
+
+    OrgMemberNotFound {
+        user_id_or_email: String,
+    },
+
+    OrgMemberAlreadyExists,
+
+    OrgRoleRequired {
+        role: OrgRole,
+    },
+
+    /// An organisation keeps at least one owner.
+    OrgLastOwner {
+        org_id: String,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Dbx(dbx::Error),
+}
+
+impl From<dbx::Error> for Error {
+    fn from(value: dbx::Error) -> Self {
+        match value.resolve_unique_violation() {
+            Some(UniqueViolation { .. }) => Self::OrgMemberAlreadyExists,
+            None => Self::Dbx(value),
+        }
+    }
+}
+
+// region:    --- Error Boilerplate
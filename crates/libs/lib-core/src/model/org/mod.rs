@@ -0,0 +1,629 @@
+//! Organisations, the workspaces owning the books (sellers, bills,
+//! transactions and tags).
+//!
+//! Every user gets an organisation of their own on creation, and may be a
+//! member of others. The `*Bmc` functions work on the active organisation of
+//! the ctx, which the user switches with `OrgBmc::switch`.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::validation::{Validate, Validator};
+use lib_utils::{id::IdSpec, time::TimeRfc3339};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+mod error;
+
+pub use error::{Error, Result};
+
+/// Also the `acs` role of the member, which grants its privileges on the
+/// books of the organisation.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum OrgRole {
+    /// Manages the members, owners included.
+    Owner,
+    /// Manages the members, but not the owners.
+    Admin,
+    #[default]
+    Member,
+    Viewer,
+}
+
+impl OrgRole {
+    pub fn can_manage_members(&self) -> bool {
+        matches!(self, Self::Owner | Self::Admin)
+    }
+}
+
+impl TryFrom<String> for OrgRole {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Org {
+    pub org_id: String,
+    pub name: String,
+    /// Role of the user of the ctx.
+    #[sqlx(try_from = "String")]
+    pub role: OrgRole,
+    /// Whether it is the organisation of the ctx.
+    #[sqlx(skip)]
+    pub active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgForCreate {
+    name: String,
+}
+
+impl Validate for OrgForCreate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator.required(path, "name", &self.name).max_len(
+            path,
+            "name",
+            Some(self.name.trim()),
+            128,
+        );
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgMember {
+    pub user_id: String,
+    pub name: String,
+    pub email: String,
+    #[sqlx(try_from = "String")]
+    pub role: OrgRole,
+}
+
+/// An existing user, by email, to add to an organisation.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgMemberForAdd {
+    email: String,
+    #[serde(default)]
+    role: OrgRole,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgMemberForUpdate {
+    role: OrgRole,
+}
+
+/// Public `org_id` of new organisations.
+const ORG_ID: IdSpec = IdSpec::new("O", 10);
+
+pub struct OrgBmc;
+
+impl OrgBmc {
+    /// Create an organisation owned by the user of `ctx`.
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_c: OrgForCreate,
+    ) -> Result<String> {
+        org_c.validate()?;
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let org_id =
+            Self::insert_with_owner(&mm, ctx.user_id(), org_c.name.trim())
+                .await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(org_id)
+    }
+
+    /// Insert an organisation owned by `user_id`, within the transaction of
+    /// `mm` (e.g., of the user creation).
+    pub(crate) async fn insert_with_owner(
+        mm: &ModelManager,
+        user_id: &str,
+        name: &str,
+    ) -> Result<String> {
+        let now = TimeRfc3339::now_utc().inner();
+
+        let dbx = mm.dbx();
+        let (serial_id, org_id) = dbx
+            .retry_on_id_collision("organisation_org_id_key", || async move {
+                let sqlx_query = sqlx::query_as::<_, (i64, String)>(
+                    "insert into organisation (org_id, name, ctime, mtime)
+                    values ($1, $2, $3, $3)
+                    returning serial_id, org_id;",
+                )
+                .bind(ORG_ID.generate())
+                .bind(name)
+                .bind(now);
+
+                dbx.fetch_one(sqlx_query).await
+            })
+            .await?;
+
+        let sqlx_query = sqlx::query(
+            "insert into org_member (org_serial_id, user_serial_id, role, ctime, mtime)
+            select $1, u.serial_id, $3, $4, $4 from users u
+            where u.user_id = $2;",
+        )
+        .bind(serial_id)
+        .bind(user_id)
+        .bind(OrgRole::Owner.to_string())
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(org_id)
+    }
+
+    /// Organisations of the user of `ctx`, by name.
+    pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Org>> {
+        let sqlx_query = sqlx::query_as::<_, Org>(
+            "select o.org_id, o.name, om.role
+            from organisation o
+            inner join org_member om on om.org_serial_id = o.serial_id
+            inner join users u on u.serial_id = om.user_serial_id
+            where u.user_id = $1
+            order by lower(o.name), o.org_id;",
+        )
+        .bind(ctx.user_id());
+
+        let mut orgs = mm.dbx().fetch_all(sqlx_query).await?;
+        for org in orgs.iter_mut() {
+            org.active = ctx.org_id() == Some(org.org_id.as_str());
+        }
+
+        Ok(orgs)
+    }
+
+    /// Make `org_id` the organisation of the next requests of the user of
+    /// `ctx`.
+    pub async fn switch(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+    ) -> Result<()> {
+        Self::role_of(ctx, mm, org_id).await?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query(
+            "update users set
+              active_org_serial_id = org_serial_id($2),
+              mtime = $3
+            where user_id = $1;",
+        )
+        .bind(ctx.user_id())
+        .bind(org_id)
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Members of `org_id`, for any of its members.
+    pub async fn list_members(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+    ) -> Result<Vec<OrgMember>> {
+        Self::role_of(ctx, mm, org_id).await?;
+
+        let sqlx_query = sqlx::query_as::<_, OrgMember>(
+            "select u.user_id, u.name, u.email, om.role
+            from org_member om
+            inner join users u on u.serial_id = om.user_serial_id
+            where om.org_serial_id = org_serial_id($1)
+            order by lower(u.name), u.user_id;",
+        )
+        .bind(org_id);
+
+        let members = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(members)
+    }
+
+    /// Add the user of `member_a.email`, only an owner may add an owner.
+    pub async fn add_member(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+        member_a: OrgMemberForAdd,
+    ) -> Result<OrgMember> {
+        let OrgMemberForAdd { email, role } = member_a;
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        Self::ensure_can_grant(ctx, &mm, org_id, role).await?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query_as::<_, OrgMember>(
+            "with inserted as (
+                insert into org_member (org_serial_id, user_serial_id, role, ctime, mtime)
+                select org_serial_id($1), u.serial_id, $3, $4, $4 from users u
+                where u.email = $2
+                returning user_serial_id, role
+            )
+            select u.user_id, u.name, u.email, i.role
+            from inserted i
+            inner join users u on u.serial_id = i.user_serial_id;",
+        )
+        .bind(org_id)
+        .bind(email.trim())
+        .bind(role.to_string())
+        .bind(now);
+
+        let member = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::OrgMemberNotFound {
+                user_id_or_email: email.trim().to_string(),
+            },
+        )?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(member)
+    }
+
+    /// Change the role of the member `user_id`, only an owner may change the
+    /// role of an owner or make one.
+    pub async fn update_member(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+        user_id: &str,
+        member_u: OrgMemberForUpdate,
+    ) -> Result<()> {
+        let OrgMemberForUpdate { role } = member_u;
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        Self::ensure_can_grant(ctx, &mm, org_id, role).await?;
+        let current = Self::member_role(&mm, org_id, user_id).await?;
+        Self::ensure_can_grant(ctx, &mm, org_id, current).await?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query(
+            "update org_member set role = $3, mtime = $4
+            where org_serial_id = org_serial_id($1)
+              and user_serial_id = (select serial_id from users where user_id = $2);",
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .bind(role.to_string())
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Self::ensure_owner_left(&mm, org_id).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Remove the member `user_id`. Members may leave by themselves, only an
+    /// owner may remove an owner.
+    pub async fn remove_member(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+        user_id: &str,
+    ) -> Result<()> {
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let current = Self::member_role(&mm, org_id, user_id).await?;
+        if user_id == ctx.user_id() {
+            Self::role_of(ctx, &mm, org_id).await?;
+        } else {
+            Self::ensure_can_grant(ctx, &mm, org_id, current).await?;
+        }
+
+        let sqlx_query = sqlx::query(
+            "delete from org_member
+            where org_serial_id = org_serial_id($1)
+              and user_serial_id = (select serial_id from users where user_id = $2);",
+        )
+        .bind(org_id)
+        .bind(user_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Self::ensure_owner_left(&mm, org_id).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Role of the user of `ctx` in `org_id`, locking the organisation so
+    /// its members change one request at a time.
+    async fn role_of(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+    ) -> Result<OrgRole> {
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select om.role
+            from organisation o
+            inner join org_member om on om.org_serial_id = o.serial_id
+            inner join users u on u.serial_id = om.user_serial_id
+            where o.org_id = $1 and u.user_id = $2
+            for update of o;",
+        )
+        .bind(org_id)
+        .bind(ctx.user_id());
+
+        let (role,) = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::OrgNotFound {
+                org_id: org_id.to_string(),
+            },
+        )?;
+
+        Ok(OrgRole::try_from(role).unwrap_or_default())
+    }
+
+    async fn member_role(
+        mm: &ModelManager,
+        org_id: &str,
+        user_id: &str,
+    ) -> Result<OrgRole> {
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select om.role
+            from org_member om
+            inner join users u on u.serial_id = om.user_serial_id
+            where om.org_serial_id = org_serial_id($1) and u.user_id = $2;",
+        )
+        .bind(org_id)
+        .bind(user_id);
+
+        let (role,) = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::OrgMemberNotFound {
+                user_id_or_email: user_id.to_string(),
+            },
+        )?;
+
+        Ok(OrgRole::try_from(role).unwrap_or_default())
+    }
+
+    /// Fail unless the user of `ctx` manages the members of `org_id`, and is
+    /// an owner for `role` being the owner role.
+    async fn ensure_can_grant(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        org_id: &str,
+        role: OrgRole,
+    ) -> Result<()> {
+        let own_role = Self::role_of(ctx, mm, org_id).await?;
+
+        if !own_role.can_manage_members() {
+            return Err(Error::OrgRoleRequired {
+                role: OrgRole::Admin,
+            });
+        }
+        if role == OrgRole::Owner && own_role != OrgRole::Owner {
+            return Err(Error::OrgRoleRequired {
+                role: OrgRole::Owner,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn ensure_owner_left(mm: &ModelManager, org_id: &str) -> Result<()> {
+        let sqlx_query = sqlx::query_as::<_, (bool,)>(
+            "select exists (
+                select 1 from org_member
+                where org_serial_id = org_serial_id($1) and role = $2
+            );",
+        )
+        .bind(org_id)
+        .bind(OrgRole::Owner.to_string());
+
+        let (exists,) = mm.dbx().fetch_one(sqlx_query).await?;
+        if !exists {
+            return Err(Error::OrgLastOwner {
+                org_id: org_id.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::bill::BillBmc;
+    use crate::model::user::{UserBmc, UserForCreate};
+    use serde_json::json;
+    use serial_test::serial;
+
+    async fn seed_other_user(mm: &ModelManager) -> Result<String> {
+        let user_id = _dev_utils::seed_user(
+            &Ctx::root_ctx(),
+            mm,
+            UserForCreate {
+                name: "other".to_string(),
+                email: "other@example.com".to_string(),
+                pwd_clear: "welcome".to_string(),
+            },
+        )
+        .await?;
+        Ok(user_id)
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_add_member_switch_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_other_user_id = seed_other_user(&mm).await?;
+
+        // -- Exec
+        let org_id = OrgBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "name": " Team " }))?,
+        )
+        .await?;
+        let member = OrgBmc::add_member(
+            &ctx,
+            &mm,
+            &org_id,
+            serde_json::from_value(json!({ "email": "other@example.com" }))?,
+        )
+        .await?;
+        let other_ctx = Ctx::new(&fx_other_user_id)?;
+        OrgBmc::switch(&other_ctx, &mm, &org_id).await?;
+
+        // -- Check
+        let orgs = OrgBmc::list(&ctx, &mm).await?;
+        let orgs = orgs
+            .iter()
+            .map(|v| (v.name.as_str(), v.role, v.active))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            orgs,
+            [
+                ("demo1", OrgRole::Owner, true),
+                ("Team", OrgRole::Owner, false)
+            ]
+        );
+        assert_eq!(member.user_id, fx_other_user_id);
+        assert_eq!(member.role, OrgRole::Member);
+        let members = OrgBmc::list_members(&ctx, &mm, &org_id).await?;
+        assert_eq!(members.len(), 2);
+
+        // The members share the books of the organisation.
+        let user =
+            UserBmc::first_by_user_id(&Ctx::root_ctx(), &mm, &fx_other_user_id)
+                .await?
+                .ok_or("Should have user")?;
+        assert_eq!(user.org_id.as_deref(), Some(org_id.as_str()));
+        let team_ctx = Ctx::new_with_org("demo1", &org_id)?;
+        let other_ctx = Ctx::new_with_org(&fx_other_user_id, &org_id)?;
+        let bill_id = BillBmc::create(
+            &team_ctx,
+            &mm,
+            serde_json::from_value(json!({ "remark": "team" }))?,
+        )
+        .await?;
+        BillBmc::get_by_bill_id(&other_ctx, &mm, &bill_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_member_roles_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_other_user_id = seed_other_user(&mm).await?;
+        let other_ctx = Ctx::new(&fx_other_user_id)?;
+        let fx_member = || json!({ "email": "demo1@example.com" });
+
+        // -- Exec & Check not a member
+        assert!(matches!(
+            OrgBmc::switch(&other_ctx, &mm, "demo1").await,
+            Err(super::Error::OrgNotFound { .. })
+        ));
+        assert!(matches!(
+            OrgBmc::add_member(
+                &other_ctx,
+                &mm,
+                "demo1",
+                serde_json::from_value(fx_member())?
+            )
+            .await,
+            Err(super::Error::OrgNotFound { .. })
+        ));
+
+        // -- Exec & Check member and admin
+        OrgBmc::add_member(
+            &ctx,
+            &mm,
+            "demo1",
+            serde_json::from_value(
+                json!({ "email": "other@example.com", "role": "Admin" }),
+            )?,
+        )
+        .await?;
+        assert!(matches!(
+            OrgBmc::update_member(
+                &other_ctx,
+                &mm,
+                "demo1",
+                "demo1",
+                serde_json::from_value(json!({ "role": "Member" }))?
+            )
+            .await,
+            Err(super::Error::OrgRoleRequired {
+                role: OrgRole::Owner
+            })
+        ));
+        assert!(matches!(
+            OrgBmc::add_member(
+                &ctx,
+                &mm,
+                "demo1",
+                serde_json::from_value(fx_member())?
+            )
+            .await,
+            Err(super::Error::OrgMemberAlreadyExists)
+        ));
+
+        // -- Exec & Check last owner
+        assert!(matches!(
+            OrgBmc::remove_member(&ctx, &mm, "demo1", "demo1").await,
+            Err(super::Error::OrgLastOwner { .. })
+        ));
+        OrgBmc::remove_member(&ctx, &mm, "demo1", &fx_other_user_id).await?;
+        let members = OrgBmc::list_members(&ctx, &mm, "demo1").await?;
+        assert_eq!(members.len(), 1);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
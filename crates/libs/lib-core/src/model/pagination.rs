@@ -0,0 +1,138 @@
+//! Keyset (cursor) pagination helpers shared by the `*Bmc::list` functions.
+//!
+//! The cursor handed to clients is opaque: a b64u encoded json of the sort
+//! column, the sort order, the sort key of the last returned row (as text)
+//! and its `serial_id` as a tie breaker.
+
+use lib_utils::b64::{b64u_decode, b64u_encode};
+use serde::{Deserialize, Serialize};
+
+// region:    --- Error
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    CursorInvalid,
+    CursorSortMismatch,
+}
+
+// endregion: --- Error
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 200;
+
+/// Clamp the client provided limit to `1..=MAX_LIMIT`.
+pub fn normalize_limit(limit: Option<i64>) -> i64 {
+    match limit {
+        Some(v) if (1..=MAX_LIMIT).contains(&v) => v,
+        _ => DEFAULT_LIMIT,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+
+    /// Row comparison operator used to seek past the cursor.
+    pub fn seek_op(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => ">",
+            SortOrder::Desc => "<",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Cursor {
+    pub sort: String,
+    pub order: SortOrder,
+    pub key: String,
+    pub serial_id: i64,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        // NOTE: Serializing a plain struct of strings/ints can not fail.
+        b64u_encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let bytes = b64u_decode(cursor).map_err(|_| Error::CursorInvalid)?;
+        serde_json::from_slice(&bytes).map_err(|_| Error::CursorInvalid)
+    }
+
+    /// Decode the cursor and make sure it was issued for the same sort.
+    pub fn decode_for(
+        cursor: &str,
+        sort: &str,
+        order: SortOrder,
+    ) -> Result<Self> {
+        let cursor = Self::decode(cursor)?;
+        if cursor.sort != sort || cursor.order != order {
+            return Err(Error::CursorSortMismatch);
+        }
+
+        Ok(cursor)
+    }
+}
+
+/// One page of a keyset paginated list.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cursor = Cursor {
+            sort: "sellerCost".to_string(),
+            order: SortOrder::Asc,
+            key: "1700.00".to_string(),
+            serial_id: 42,
+        };
+
+        // -- Exec
+        let cursor = Cursor::decode_for(
+            &fx_cursor.encode(),
+            "sellerCost",
+            SortOrder::Asc,
+        )?;
+
+        // -- Check
+        assert_eq!(cursor.key, fx_cursor.key);
+        assert_eq!(cursor.serial_id, fx_cursor.serial_id);
+        assert!(matches!(
+            Cursor::decode_for(&fx_cursor.encode(), "name", SortOrder::Asc),
+            Err(super::Error::CursorSortMismatch)
+        ));
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,53 @@
+use crate::model::acs;
+use crate::model::bill::{self, BillStatus};
+use crate::model::exchange_rate;
+use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::validation;
+use lib_utils::money::Money;
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    PaymentNotUnique,
+
+    PaymentBillNotPayable {
+        bill_id: String,
+        status: BillStatus,
+    },
+
+    PaymentExceedsOutstanding {
+        bill_id: String,
+        outstanding: Money,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Bill(#[from] bill::Error),
+
+    #[error(transparent)]
+    ExchangeRate(#[from] exchange_rate::Error),
+
+    #[error(transparent)]
+    Dbx(dbx::Error),
+}
+
+impl From<dbx::Error> for Error {
+    fn from(value: dbx::Error) -> Self {
+        match value.resolve_unique_violation() {
+            Some(UniqueViolation { .. }) => Self::PaymentNotUnique,
+            None => Self::Dbx(value),
+        }
+    }
+}
+
+// region:    --- Error Boilerplate
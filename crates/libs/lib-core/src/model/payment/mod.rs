@@ -0,0 +1,327 @@
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::acs::{self, Privilege};
+use crate::model::bill::{self, BillBmc, BillStatus};
+use crate::model::transaction::PaymentMethod;
+use crate::model::validation::{Validate, Validator};
+use bigdecimal::BigDecimal;
+use lib_utils::money::{Currency, Money};
+use lib_utils::{id::IdSpec, time::TimeRfc3339};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+mod error;
+
+pub use error::{Error, Result};
+
+/// Fields required for recording a payment against a bill
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentForCreate {
+    amount: BigDecimal,
+    /// Defaults to `INR`.
+    #[serde(default)]
+    currency: Currency,
+    payment_method: PaymentMethod,
+    /// Defaults to now.
+    payment_time: Option<TimeRfc3339>,
+    /// Free text, e.g. a cheque number or an upi transaction id.
+    reference: Option<String>,
+}
+
+impl Validate for PaymentForCreate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .positive(path, "amount", &self.amount)
+            .amount(path, "amount", &self.amount)
+            .max_len(path, "reference", self.reference.as_deref(), 128);
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Payment {
+    pub payment_id: String,
+    pub bill_id: String,
+    pub amount: BigDecimal,
+    #[sqlx(try_from = "String")]
+    pub currency: Currency,
+    #[sqlx(try_from = "String")]
+    pub payment_method: PaymentMethod,
+    pub payment_time: TimeRfc3339,
+    pub reference: Option<String>,
+}
+
+/// Public `payment_id` of new payments.
+const PAYMENT_ID: IdSpec = IdSpec::new("P", 20);
+
+pub struct PaymentBmc;
+
+impl PaymentBmc {
+    /// Record a (partial) payment of a finalized bill.
+    ///
+    /// The amount can not exceed the outstanding balance of the bill, in the
+    /// currency of the payment. The payment settling the balance marks the
+    /// bill as `Paid`.
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+        payment_c: PaymentForCreate,
+    ) -> Result<String> {
+        acs::require(ctx, mm, Privilege::PaymentCreate).await?;
+
+        BillBmc::ensure_well_formed(bill_id)?;
+
+        payment_c.validate()?;
+
+        let PaymentForCreate {
+            amount,
+            currency,
+            payment_method,
+            payment_time,
+            reference,
+        } = payment_c;
+
+        let now = TimeRfc3339::now_utc().inner();
+        let payment_time = payment_time.map(|v| v.inner()).unwrap_or(now);
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        // -- Lock the bill, so concurrent payments see each other.
+        let sqlx_query = sqlx::query_as::<_, (i64, String)>(
+            "select b.serial_id, bs.name from bill b
+            inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+            where b.bill_id = $1 and b.org_serial_id = org_serial_id($2)
+            for update of b;",
+        )
+        .bind(bill_id)
+        .bind(ctx.org_id());
+
+        let (bill_serial_id, status) =
+            mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+                bill::Error::BillNotFound {
+                    bill_id: bill_id.to_string(),
+                },
+            )?;
+        let status = BillStatus::try_from(status).unwrap_or_default();
+
+        if status != BillStatus::Finalized {
+            return Err(Error::PaymentBillNotPayable {
+                bill_id: bill_id.to_string(),
+                status,
+            });
+        }
+
+        let outstanding =
+            BillBmc::outstanding(&mm, bill_id, currency, now).await?;
+
+        if amount > outstanding {
+            return Err(Error::PaymentExceedsOutstanding {
+                bill_id: bill_id.to_string(),
+                outstanding: Money::new(outstanding, currency),
+            });
+        }
+
+        let settles_bill = amount == outstanding;
+
+        let payment_method = &payment_method.to_string();
+        let currency = currency.code();
+        let (amount, reference) = (&amount, &reference);
+
+        let dbx = mm.dbx();
+        let (payment_id,) = dbx
+            .retry_on_id_collision("payment_payment_id_key", || async move {
+                let sqlx_query = sqlx::query_as::<_, (String,)>(
+                    "insert into payment (payment_id, bill_serial_id, amount, currency_serial_id, payment_time, payment_method_serial_id, reference) values
+                    ($1, $2, $3, (select serial_id from currency c where c.code = $7), $4, (select serial_id from payment_method p where p.name = $5 limit 1), $6)
+                    returning payment_id;",
+                )
+                .bind(PAYMENT_ID.generate())
+                .bind(bill_serial_id)
+                .bind(amount)
+                .bind(payment_time)
+                .bind(payment_method)
+                .bind(reference)
+                .bind(currency);
+
+                dbx.fetch_one(sqlx_query).await
+            })
+            .await?;
+
+        if settles_bill {
+            let sqlx_query = sqlx::query(
+                "update bill set
+                    bill_status_serial_id = (select serial_id from bill_status where name = $2),
+                    mtime = $3
+                where serial_id = $1;",
+            )
+            .bind(bill_serial_id)
+            .bind(BillStatus::Paid.to_string())
+            .bind(now);
+
+            mm.dbx().execute(sqlx_query).await?;
+        }
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(payment_id)
+    }
+
+    /// Payments of a bill, oldest first.
+    pub async fn get_by_bill_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<Vec<Payment>> {
+        acs::require(ctx, mm, Privilege::BillRead).await?;
+
+        Self::fetch_by_bill_id(ctx, mm, bill_id).await
+    }
+
+    /// Unchecked, for `BillBmc::get_details`.
+    pub(crate) async fn fetch_by_bill_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<Vec<Payment>> {
+        let sqlx_query = sqlx::query_as::<_, Payment>(
+            "select p.payment_id, b.bill_id, p.amount, cu.code as currency,
+                pm.name as payment_method, p.payment_time, p.reference
+            from payment p
+            inner join bill b on b.serial_id = p.bill_serial_id
+            inner join currency cu on cu.serial_id = p.currency_serial_id
+            inner join payment_method pm
+                on pm.serial_id = p.payment_method_serial_id
+            where b.bill_id = $1 and b.org_serial_id = org_serial_id($2)
+            order by p.payment_time, p.serial_id;",
+        )
+        .bind(bill_id)
+        .bind(ctx.org_id());
+
+        let payments = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(payments)
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::bill::BillBmc;
+    use crate::model::transaction::TransactionBmc;
+    use crate::model::validation::{self, FieldErrorCode};
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_partial_settlement_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_payment = |amount: &str| -> PaymentForCreate {
+            serde_json::from_value(
+                json!({ "amount": amount, "paymentMethod": "Upi", "reference": "ref" }),
+            )
+            .unwrap()
+        };
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0004" })).unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "paymentMethod": "Cash", "sellerCost": 60, "billId": bill_id },
+                { "name": "tr 2", "paymentMethod": "Cash", "sellerCost": 40, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec & Check draft bill
+        assert!(matches!(
+            PaymentBmc::create(&ctx, &mm, &bill_id, fx_payment("10")).await,
+            Err(super::Error::PaymentBillNotPayable {
+                status: BillStatus::Draft,
+                ..
+            })
+        ));
+
+        // -- Exec & Check partial payment
+        BillBmc::finalize(&ctx, &mm, &bill_id).await?;
+        PaymentBmc::create(&ctx, &mm, &bill_id, fx_payment("30")).await?;
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+        assert_eq!(details.payments.len(), 1);
+        assert_eq!(details.total_paid, dec("30"));
+        assert_eq!(details.outstanding, dec("70"));
+        let unpaid =
+            BillBmc::list_unpaid_by_seller_id(&ctx, &mm, "0004", Currency::Inr)
+                .await?;
+        assert_eq!(unpaid.len(), 1);
+        assert_eq!(unpaid[0].outstanding, dec("70"));
+        assert!(matches!(
+            PaymentBmc::create(&ctx, &mm, &bill_id, fx_payment("70.01")).await,
+            Err(super::Error::PaymentExceedsOutstanding { .. })
+        ));
+        let res = PaymentBmc::create(
+            &ctx,
+            &mm,
+            &bill_id,
+            serde_json::from_value(json!({
+                "amount": "0",
+                "paymentMethod": "Upi",
+                "reference": "r".repeat(129)
+            }))
+            .unwrap(),
+        )
+        .await;
+        let Err(super::Error::Validation(validation::Error::Validation {
+            errors,
+        })) = res
+        else {
+            return Err(format!("Should fail validation: {res:?}").into());
+        };
+        let errors = errors
+            .iter()
+            .map(|v| (v.path.as_str(), v.code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("amount", FieldErrorCode::NotPositive),
+                ("reference", FieldErrorCode::TooLong),
+            ]
+        );
+
+        // -- Exec & Check settlement
+        PaymentBmc::create(&ctx, &mm, &bill_id, fx_payment("70")).await?;
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+        assert_eq!(details.bill.status, BillStatus::Paid);
+        assert_eq!(details.outstanding, dec("0"));
+        let unpaid =
+            BillBmc::list_unpaid_by_seller_id(&ctx, &mm, "0004", Currency::Inr)
+                .await?;
+        assert!(unpaid.is_empty());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -1,4 +1,7 @@
+use crate::model::acs;
+use crate::model::exchange_rate;
 use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::validation;
 use serde::Serialize;
 use serde_with::serde_as;
 
@@ -13,6 +16,20 @@ pub enum Error {
 
     SellerNotUnique,
 
+    SellerMergeSame {
+        seller_id: String,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    ExchangeRate(#[from] exchange_rate::Error),
+
     #[error(transparent)]
     Dbx(dbx::Error),
 }
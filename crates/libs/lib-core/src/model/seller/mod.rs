@@ -1,8 +1,14 @@
 use std::collections::HashSet;
 
+use crate::ctx::Ctx;
 use crate::model::ModelManager;
-use lib_utils::b58::b58_encode;
-use rand::RngCore as _;
+use crate::model::acs::{self, Privilege};
+use crate::model::bill::BillStatus;
+use crate::model::exchange_rate::ExchangeRateBmc;
+use crate::model::store::like_contains_pattern;
+use crate::model::validation::{Validate, Validator};
+use bigdecimal::BigDecimal;
+use lib_utils::{id::IdSpec, money::Currency, time::TimeRfc3339};
 use serde::{Deserialize, Serialize};
 
 mod error;
@@ -10,11 +16,81 @@ mod error;
 pub use error::{Error, Result};
 use sqlx::prelude::FromRow;
 
-/// Fields required for creating new bill
+/// Fields required for creating new seller
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SellerForCreate {
     name: String,
+    phone: Option<String>,
+    address: Option<String>,
+    tax_id: Option<String>,
+    notes: Option<String>,
+}
+
+impl Validate for SellerForCreate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .required(path, "name", &self.name)
+            .max_len(path, "name", Some(self.name.trim()), 64)
+            .max_len(path, "phone", self.phone.as_deref(), 32)
+            .max_len(path, "address", self.address.as_deref(), 512)
+            .max_len(path, "taxId", self.tax_id.as_deref(), 32)
+            .max_len(path, "notes", self.notes.as_deref(), 1024);
+    }
+}
+
+/// Fields that can be changed on an existing seller.
+/// An explicit `null` clears the value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellerForUpdate {
+    name: Option<String>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    phone: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    address: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    tax_id: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    notes: Option<Option<String>>,
+}
+
+impl Validate for SellerForUpdate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        if let Some(name) = self.name.as_deref() {
+            validator.required(path, "name", name).max_len(
+                path,
+                "name",
+                Some(name.trim()),
+                64,
+            );
+        }
+        validator
+            .max_len(
+                path,
+                "phone",
+                self.phone.as_ref().and_then(Option::as_deref),
+                32,
+            )
+            .max_len(
+                path,
+                "address",
+                self.address.as_ref().and_then(Option::as_deref),
+                512,
+            )
+            .max_len(
+                path,
+                "taxId",
+                self.tax_id.as_ref().and_then(Option::as_deref),
+                32,
+            )
+            .max_len(
+                path,
+                "notes",
+                self.notes.as_ref().and_then(Option::as_deref),
+                1024,
+            );
+    }
 }
 
 #[derive(Debug, Serialize, FromRow)]
@@ -22,30 +98,127 @@ pub struct SellerForCreate {
 pub struct Seller {
     pub seller_id: String,
     pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub tax_id: Option<String>,
+    pub notes: Option<String>,
+    /// Archived sellers are hidden from listings and search.
+    pub archived_at: Option<TimeRfc3339>,
+}
+
+/// Period of a seller ledger, on the entry time.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellerLedgerFilter {
+    /// Inclusive lower bound, older entries make up the opening balance.
+    pub from: Option<TimeRfc3339>,
+    /// Exclusive upper bound.
+    pub to: Option<TimeRfc3339>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, strum_macros::Display)]
+pub enum SellerLedgerEntryKind {
+    Bill,
+    Payment,
+}
+
+impl TryFrom<String> for SellerLedgerEntryKind {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "Bill" => Ok(Self::Bill),
+            "Payment" => Ok(Self::Payment),
+            _ => Err(value),
+        }
+    }
+}
+
+/// A bill (debit) or a payment (credit) of a seller.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SellerLedgerEntry {
+    pub time: TimeRfc3339,
+    #[sqlx(try_from = "String")]
+    pub kind: SellerLedgerEntryKind,
+    /// The `bill_id` or the `payment_id`.
+    pub entry_id: String,
+    pub bill_id: String,
+    /// Bill remark or payment reference.
+    pub description: Option<String>,
+    pub debit: BigDecimal,
+    pub credit: BigDecimal,
+    /// What is owed to the seller after this entry.
+    #[sqlx(skip)]
+    pub balance: BigDecimal,
+}
+
+/// Statement of account of a seller, entries are in time order and all
+/// amounts in `currency`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellerLedger {
+    pub seller: Seller,
+    pub currency: Currency,
+    pub opening_balance: BigDecimal,
+    pub entries: Vec<SellerLedgerEntry>,
+    pub total_debit: BigDecimal,
+    pub total_credit: BigDecimal,
+    pub closing_balance: BigDecimal,
+}
+
+/// A `search_by_name` match.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SellerSearchResult {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub seller: Seller,
+    /// Trigram similarity in `0..=1`, `1` is an exact (word) match.
+    pub score: f32,
+    pub last_bill_time: Option<TimeRfc3339>,
 }
 
+/// Public `seller_id` of new sellers.
+const SELLER_ID: IdSpec = IdSpec::new("S", 10).with_checksum();
+
 pub struct SellerBmc;
 
 impl SellerBmc {
-    fn generate_seller_id() -> String {
-        let mut key = [0u8; 64]; // 512 bits = 64 bytes
-        rand::rng().fill_bytes(&mut key);
-        b58_encode(key)
-            .chars()
-            .take(10)
-            .collect::<String>()
-            .to_uppercase()
+    /// Fail with `SellerNotFound`, before any query, for a mistyped
+    /// `seller_id`.
+    fn ensure_well_formed(seller_id: &str) -> Result<()> {
+        if SELLER_ID.is_mistyped(seller_id) {
+            return Err(Error::SellerNotFound {
+                name_or_id: format!("seller_id: {}", seller_id),
+            });
+        }
+
+        Ok(())
     }
 
     pub async fn create(
+        ctx: &Ctx,
         mm: &ModelManager,
         sellers_c: Vec<SellerForCreate>,
     ) -> Result<Vec<Seller>> {
+        acs::require(ctx, mm, Privilege::SellerCreate).await?;
+
+        sellers_c.validate()?;
+
+        // -- Keep the first seller of each name.
+        let mut names = HashSet::new();
+        let sellers_c = sellers_c
+            .into_iter()
+            .map(|mut v| {
+                v.name = v.name.trim().to_string();
+                v
+            })
+            .filter(|v| names.insert(v.name.clone()))
+            .collect::<Vec<SellerForCreate>>();
         let sellers = sellers_c
             .iter()
-            .map(|v| v.name.trim())
-            .collect::<HashSet<&str>>()
-            .into_iter()
+            .map(|v| v.name.as_str())
             .collect::<Vec<&str>>();
 
         // Start the transaction
@@ -55,36 +228,56 @@ impl SellerBmc {
         // region:    --- Insert Sellers
         let sqlx_query = sqlx::query_as::<_, (String,)>(
             "select name from unnest($1::text[]) as t(name)
-            where t.name not in (select name from seller);",
+            where t.name not in (
+                select name from seller
+                where org_serial_id = org_serial_id($2));",
         )
-        .bind(&sellers);
+        .bind(&sellers)
+        .bind(ctx.org_id());
 
-        let new_sellers: Vec<String> = mm
+        let new_names: HashSet<String> = mm
             .dbx()
             .fetch_all(sqlx_query)
             .await?
             .into_iter()
             .map(|v| v.0)
             .collect();
+        let new_sellers = sellers_c
+            .into_iter()
+            .filter(|v| new_names.contains(&v.name))
+            .collect::<Vec<SellerForCreate>>();
 
-        let mut seller_ids = Vec::with_capacity(new_sellers.len());
-        (0..new_sellers.len())
-            .for_each(|_| seller_ids.push(SellerBmc::generate_seller_id()));
+        let new_sellers = &new_sellers;
+        let dbx = mm.dbx();
+        let all = dbx
+            .retry_on_id_collision("seller_seller_id_key", || async move {
+                let seller_ids = (0..new_sellers.len())
+                    .map(|_| SELLER_ID.generate())
+                    .collect::<Vec<String>>();
 
-        let sqlx_query = sqlx::query_as::<_, Seller>(
-            "insert into seller (name, seller_id)
-            select * from unnest(
-                $1::text[],
-                $2::text[]
-            ) as t(name, seller_id)
-            on conflict (seller_id) do nothing
-            returning seller_id, name;
-            ",
-        )
-        .bind(new_sellers)
-        .bind(seller_ids);
+                let sqlx_query = sqlx::query_as::<_, Seller>(
+                    "insert into seller (org_serial_id, name, seller_id, phone, address, tax_id, notes)
+                    select org_serial_id($7), t.* from unnest(
+                        $1::text[],
+                        $2::text[],
+                        $3::text[],
+                        $4::text[],
+                        $5::text[],
+                        $6::text[]
+                    ) as t(name, seller_id, phone, address, tax_id, notes)
+                    returning seller_id, name, phone, address, tax_id, notes, archived_at;",
+                )
+                .bind(new_sellers.iter().map(|v| &v.name).collect::<Vec<_>>())
+                .bind(seller_ids)
+                .bind(new_sellers.iter().map(|v| &v.phone).collect::<Vec<_>>())
+                .bind(new_sellers.iter().map(|v| &v.address).collect::<Vec<_>>())
+                .bind(new_sellers.iter().map(|v| &v.tax_id).collect::<Vec<_>>())
+                .bind(new_sellers.iter().map(|v| &v.notes).collect::<Vec<_>>())
+                .bind(ctx.org_id());
 
-        let all: Vec<Seller> = mm.dbx().fetch_all(sqlx_query).await?;
+                dbx.fetch_all(sqlx_query).await
+            })
+            .await?;
 
         // Commit the transaction
         mm.dbx().commit_txn().await?;
@@ -94,11 +287,21 @@ impl SellerBmc {
         Ok(all)
     }
 
-    pub async fn get_by_name(mm: &ModelManager, name: &str) -> Result<Seller> {
+    pub async fn get_by_name(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        name: &str,
+    ) -> Result<Seller> {
+        acs::require(ctx, mm, Privilege::SellerRead).await?;
+
         let sqlx_query = sqlx::query_as::<_, Seller>(
-            "select seller_id, name from seller where name = $1 limit 1;",
+            "select seller_id, name, phone, address, tax_id, notes, archived_at
+            from seller
+            where name = $1 and org_serial_id = org_serial_id($2)
+            limit 1;",
         )
-        .bind(name);
+        .bind(name)
+        .bind(ctx.org_id());
 
         let user = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
             Error::SellerNotFound {
@@ -110,13 +313,31 @@ impl SellerBmc {
     }
 
     pub async fn get_by_seller_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        seller_id: &str,
+    ) -> Result<Seller> {
+        acs::require(ctx, mm, Privilege::SellerRead).await?;
+
+        Self::fetch_by_seller_id(ctx, mm, seller_id).await
+    }
+
+    /// Unchecked, for the ledger.
+    async fn fetch_by_seller_id(
+        ctx: &Ctx,
         mm: &ModelManager,
         seller_id: &str,
     ) -> Result<Seller> {
+        Self::ensure_well_formed(seller_id)?;
+
         let sqlx_query = sqlx::query_as::<_, Seller>(
-            "select seller_id, name from seller where seller_id = $1 limit 1;",
+            "select seller_id, name, phone, address, tax_id, notes, archived_at
+            from seller
+            where seller_id = $1 and org_serial_id = org_serial_id($2)
+            limit 1;",
         )
-        .bind(seller_id);
+        .bind(seller_id)
+        .bind(ctx.org_id());
 
         let user = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
             Error::SellerNotFound {
@@ -127,47 +348,379 @@ impl SellerBmc {
         Ok(user)
     }
 
+    /// Resolve a `seller_id` for the foreign keys of other tables.
+    pub(crate) async fn get_serial_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        seller_id: &str,
+    ) -> Result<i64> {
+        Self::ensure_well_formed(seller_id)?;
+
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "select serial_id from seller
+            where seller_id = $1 and org_serial_id = org_serial_id($2);",
+        )
+        .bind(seller_id)
+        .bind(ctx.org_id());
+
+        let (serial_id,) = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::SellerNotFound {
+                name_or_id: format!("seller_id: {}", seller_id),
+            },
+        )?;
+
+        Ok(serial_id)
+    }
+
     pub async fn get_all(
+        ctx: &Ctx,
         mm: &ModelManager,
         limit: Option<i32>,
     ) -> Result<Vec<Seller>> {
+        acs::require(ctx, mm, Privilege::SellerRead).await?;
+
         let limit = match limit {
             Some(v) if (1..=50).contains(&v) => v,
             _ => 10,
         };
 
         let sqlx_query = sqlx::query_as::<_, Seller>(
-            "select seller_id, name from seller limit $1;",
+            "select seller_id, name, phone, address, tax_id, notes, archived_at
+            from seller
+            where archived_at is null and org_serial_id = org_serial_id($2)
+            limit $1;",
         )
-        .bind(limit);
+        .bind(limit)
+        .bind(ctx.org_id());
 
         let sellers = mm.dbx().fetch_all(sqlx_query).await?;
 
         Ok(sellers)
     }
 
+    /// Typo tolerant search (pg_trgm), best match first. Sellers with the
+    /// same score are ordered by their latest bill.
     pub async fn search_by_name(
+        ctx: &Ctx,
         mm: &ModelManager,
         name: &str,
         limit: Option<i32>,
-    ) -> Result<Vec<Seller>> {
+    ) -> Result<Vec<SellerSearchResult>> {
+        acs::require(ctx, mm, Privilege::SellerRead).await?;
+
         let limit = match limit {
             Some(v) if (1..=50).contains(&v) => v,
             _ => 10,
         };
 
-        let search_name = format!("%{}%", name);
+        let name = name.trim();
 
-        let sqlx_query = sqlx::query_as::<_, Seller>(
-            "select seller_id, name from seller where name ilike $1 limit $2;",
+        // NOTE: `word_similarity` scores "abc" against "Abc Store" as a full
+        //       match, where `similarity` would penalize the longer name.
+        let sqlx_query = sqlx::query_as::<_, SellerSearchResult>(
+            "select s.seller_id, s.name, s.phone, s.address, s.tax_id, s.notes,
+                s.archived_at,
+                greatest(similarity(s.name, $1), word_similarity($1, s.name)) as score,
+                lb.last_bill_time
+            from seller s
+            left join lateral (
+                select max(b.ctime) as last_bill_time from bill b
+                where b.seller_serial_id = s.serial_id
+            ) lb on true
+            where s.archived_at is null
+              and s.org_serial_id = org_serial_id($3)
+              and (s.name % $1 or $1 <% s.name or s.name ilike $4)
+            order by score desc, lb.last_bill_time desc nulls last, s.name
+            limit $2;",
         )
-        .bind(search_name)
-        .bind(limit);
+        .bind(name)
+        .bind(limit)
+        .bind(ctx.org_id())
+        .bind(like_contains_pattern(name));
 
         let sellers = mm.dbx().fetch_all(sqlx_query).await?;
 
         Ok(sellers)
     }
+
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        seller_id: &str,
+        seller_u: SellerForUpdate,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::SellerUpdate).await?;
+
+        seller_u.validate()?;
+
+        Self::ensure_well_formed(seller_id)?;
+
+        let SellerForUpdate {
+            name,
+            phone,
+            address,
+            tax_id,
+            notes,
+        } = seller_u;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // NOTE: The booleans tell if the field was sent, so that an explicit
+        //       null can clear it.
+        let sqlx_query = sqlx::query(
+            "update seller set
+                name = coalesce($2, name),
+                phone = case when $3 then $4 else phone end,
+                address = case when $5 then $6 else address end,
+                tax_id = case when $7 then $8 else tax_id end,
+                notes = case when $9 then $10 else notes end,
+                mtime = $11
+            where seller_id = $1 and org_serial_id = org_serial_id($12);",
+        )
+        .bind(seller_id)
+        .bind(name.map(|v| v.trim().to_string()))
+        .bind(phone.is_some())
+        .bind(phone.flatten())
+        .bind(address.is_some())
+        .bind(address.flatten())
+        .bind(tax_id.is_some())
+        .bind(tax_id.flatten())
+        .bind(notes.is_some())
+        .bind(notes.flatten())
+        .bind(now)
+        .bind(ctx.org_id());
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+
+        if count == 0 {
+            return Err(Error::SellerNotFound {
+                name_or_id: format!("seller_id: {}", seller_id),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Soft delete, the seller and its bills are kept.
+    pub async fn archive(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        seller_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::SellerUpdate).await?;
+
+        Self::ensure_well_formed(seller_id)?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query(
+            "update seller set archived_at = coalesce(archived_at, $2), mtime = $2
+            where seller_id = $1 and org_serial_id = org_serial_id($3);",
+        )
+        .bind(seller_id)
+        .bind(now)
+        .bind(ctx.org_id());
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+
+        if count == 0 {
+            return Err(Error::SellerNotFound {
+                name_or_id: format!("seller_id: {}", seller_id),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Merge the duplicate seller `from` into the seller `into`.
+    ///
+    /// Every bill of `from` is moved to `into`, the contact details missing
+    /// on `into` are taken from `from`, then `from` is deleted.
+    pub async fn merge(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        from: &str,
+        into: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::SellerMerge).await?;
+
+        Self::ensure_well_formed(from)?;
+        Self::ensure_well_formed(into)?;
+
+        if from == into {
+            return Err(Error::SellerMergeSame {
+                seller_id: from.to_string(),
+            });
+        }
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        // -- Lock both sellers, in a stable order to avoid deadlocks.
+        let sqlx_query = sqlx::query_as::<_, (i64, String)>(
+            "select serial_id, seller_id from seller
+            where seller_id = any($1) and org_serial_id = org_serial_id($2)
+            order by serial_id
+            for update;",
+        )
+        .bind([from, into])
+        .bind(ctx.org_id());
+
+        let sellers = mm.dbx().fetch_all(sqlx_query).await?;
+        let serial_id_of = |seller_id: &str| {
+            sellers
+                .iter()
+                .find(|(_, v)| v == seller_id)
+                .map(|(serial_id, _)| *serial_id)
+                .ok_or(Error::SellerNotFound {
+                    name_or_id: format!("seller_id: {}", seller_id),
+                })
+        };
+        let from_serial_id = serial_id_of(from)?;
+        let into_serial_id = serial_id_of(into)?;
+
+        let sqlx_query = sqlx::query(
+            "update bill set seller_serial_id = $2, mtime = $3
+            where seller_serial_id = $1;",
+        )
+        .bind(from_serial_id)
+        .bind(into_serial_id)
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        let sqlx_query = sqlx::query(
+            "update seller s set
+                phone = coalesce(s.phone, f.phone),
+                address = coalesce(s.address, f.address),
+                tax_id = coalesce(s.tax_id, f.tax_id),
+                notes = coalesce(s.notes, f.notes),
+                mtime = $3
+            from seller f
+            where s.serial_id = $2 and f.serial_id = $1;",
+        )
+        .bind(from_serial_id)
+        .bind(into_serial_id)
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        let sqlx_query =
+            sqlx::query("delete from seller where serial_id = $1;")
+                .bind(from_serial_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Ledger of a seller: the totals of its bills (void ones excluded) as
+    /// debits and its payments as credits, with a running balance, converted
+    /// to `currency`. A bill crediting more than it charges (e.g., only
+    /// returns) is a credit.
+    pub async fn get_ledger(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        seller_id: &str,
+        filter: SellerLedgerFilter,
+        currency: Currency,
+    ) -> Result<SellerLedger> {
+        acs::require(ctx, mm, Privilege::SellerRead).await?;
+
+        let SellerLedgerFilter { from, to } = filter;
+
+        // NOTE: The bills of a seller are of the organisation of the seller.
+        let seller = Self::fetch_by_seller_id(ctx, mm, seller_id).await?;
+
+        ExchangeRateBmc::ensure_for_bills(
+            ctx,
+            mm,
+            None,
+            Some(seller_id),
+            currency,
+        )
+        .await?;
+
+        // NOTE: Entries at the same time list the bill before its payment.
+        let sqlx_query = sqlx::query_as::<_, SellerLedgerEntry>(
+            "select time, kind, entry_id, bill_id, description, debit, credit
+            from (
+                select b.ctime as time, 'Bill' as kind, b.serial_id,
+                    b.bill_id as entry_id, b.bill_id, b.remark as description,
+                    greatest(bt.total, 0) as debit,
+                    greatest(-bt.total, 0) as credit
+                from bill b
+                inner join seller s on s.serial_id = b.seller_serial_id
+                inner join bill_status bs on bs.serial_id = b.bill_status_serial_id
+                inner join currency c on c.code = $4
+                inner join lateral (
+                    select coalesce(sum(convert_amount(tr.seller_cost * tk.sign,
+                        tr.currency_serial_id, c.serial_id, tr.transaction_time)), 0) as total
+                    from transaction tr
+                    inner join transaction_kind tk
+                        on tk.serial_id = tr.transaction_kind_serial_id
+                    where tr.bill_serial_id = b.serial_id
+                ) bt on true
+                where s.seller_id = $1 and bs.name <> $3
+                union all
+                select p.payment_time, 'Payment', p.serial_id,
+                    p.payment_id, b.bill_id, p.reference,
+                    0::numeric, convert_amount(p.amount,
+                        p.currency_serial_id, c.serial_id, p.payment_time)
+                from payment p
+                inner join bill b on b.serial_id = p.bill_serial_id
+                inner join seller s on s.serial_id = b.seller_serial_id
+                inner join currency c on c.code = $4
+                where s.seller_id = $1
+            ) e
+            where $2::timestamptz is null or e.time < $2
+            order by e.time, e.kind, e.serial_id;",
+        )
+        .bind(seller_id)
+        .bind(to.map(|v| v.inner()))
+        .bind(BillStatus::Void.to_string())
+        .bind(currency.code());
+
+        let rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+        // -- Entries before `from` only count toward the opening balance.
+        let (opening, mut entries): (Vec<_>, Vec<_>) =
+            rows.into_iter().partition(|v| {
+                from.as_ref()
+                    .is_some_and(|from| v.time.inner() < from.inner())
+            });
+
+        let opening_balance = opening
+            .iter()
+            .map(|v| &v.debit - &v.credit)
+            .sum::<BigDecimal>();
+
+        let mut balance = opening_balance.clone();
+        for entry in entries.iter_mut() {
+            balance += &entry.debit - &entry.credit;
+            entry.balance = balance.clone();
+        }
+
+        let total_debit = entries.iter().map(|v| &v.debit).sum::<BigDecimal>();
+        let total_credit =
+            entries.iter().map(|v| &v.credit).sum::<BigDecimal>();
+
+        Ok(SellerLedger {
+            seller,
+            currency,
+            opening_balance,
+            entries,
+            total_debit,
+            total_credit,
+            closing_balance: balance,
+        })
+    }
 }
 
 // region:    --- Tests
@@ -179,6 +732,11 @@ mod tests {
 
     use super::*;
     use crate::_dev_utils;
+    use crate::model::bill::BillBmc;
+    use crate::model::payment::PaymentBmc;
+    use crate::model::transaction::TransactionBmc;
+    use crate::model::validation::{self, FieldErrorCode};
+    use serde_json::json;
     use serial_test::serial;
 
     #[serial]
@@ -186,20 +744,27 @@ mod tests {
     async fn test_create_ok() -> Result<()> {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
         let fx_name = "test_create_ok-seller-01";
 
         // -- Exec
         let sellers = SellerBmc::create(
+            &ctx,
             &mm,
             vec![SellerForCreate {
                 name: fx_name.to_string(),
+                phone: None,
+                address: None,
+                tax_id: None,
+                notes: None,
             }],
         )
         .await?;
 
         // -- Check
         let seller: Seller =
-            SellerBmc::get_by_seller_id(&mm, &sellers[0].seller_id).await?;
+            SellerBmc::get_by_seller_id(&ctx, &mm, &sellers[0].seller_id)
+                .await?;
         assert_eq!(seller.name, fx_name);
 
         // // -- Clean
@@ -207,6 +772,279 @@ mod tests {
 
         Ok(())
     }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_ledger_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_bill = async |seller_cost: i64| -> Result<String> {
+            let bill_id = BillBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(json!({ "sellerId": "0003" })).unwrap(),
+            )
+            .await?;
+            TransactionBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(json!([
+                    { "name": "tr", "paymentMethod": "Cash", "sellerCost": seller_cost, "billId": bill_id }
+                ]))
+                .unwrap(),
+            )
+            .await?;
+            BillBmc::finalize(&ctx, &mm, &bill_id).await?;
+            Ok(bill_id)
+        };
+        let bill_id_1 = fx_bill(100).await?;
+        PaymentBmc::create(
+            &ctx,
+            &mm,
+            &bill_id_1,
+            serde_json::from_value(
+                json!({ "amount": 40, "paymentMethod": "Cash" }),
+            )
+            .unwrap(),
+        )
+        .await?;
+        let fx_from = TimeRfc3339::now_utc();
+        let bill_id_2 = fx_bill(50).await?;
+        let bill_id_void = fx_bill(999).await?;
+        BillBmc::void(&ctx, &mm, &bill_id_void).await?;
+
+        // -- Exec
+        let ledger = SellerBmc::get_ledger(
+            &ctx,
+            &mm,
+            "0003",
+            SellerLedgerFilter::default(),
+            Currency::Inr,
+        )
+        .await?;
+        let ledger_from = SellerBmc::get_ledger(
+            &ctx,
+            &mm,
+            "0003",
+            SellerLedgerFilter {
+                from: Some(fx_from),
+                to: None,
+            },
+            Currency::Inr,
+        )
+        .await?;
+
+        // -- Check
+        let entries = ledger
+            .entries
+            .iter()
+            .map(|v| (v.bill_id.clone(), v.kind.to_string(), v.balance.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                (bill_id_1.clone(), "Bill".into(), dec("100")),
+                (bill_id_1, "Payment".into(), dec("60")),
+                (bill_id_2.clone(), "Bill".into(), dec("110")),
+            ]
+        );
+        assert_eq!(ledger.total_debit, dec("150"));
+        assert_eq!(ledger.total_credit, dec("40"));
+        assert_eq!(ledger_from.opening_balance, dec("60"));
+        assert_eq!(ledger_from.entries.len(), 1);
+        assert_eq!(ledger_from.entries[0].bill_id, bill_id_2);
+        assert_eq!(ledger_from.closing_balance, dec("110"));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_archive_merge_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let sellers = SellerBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "test_merge-abc store", "phone": "98450", "notes": "dup" },
+                { "name": "test_merge-Abc" }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_from = sellers[0].seller_id.clone();
+        let fx_into = sellers[1].seller_id.clone();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": fx_from })).unwrap(),
+        )
+        .await?;
+
+        // -- Exec & Check update
+        SellerBmc::update(
+            &ctx,
+            &mm,
+            &fx_into,
+            serde_json::from_value(
+                json!({ "taxId": "29ABCDE1234F1Z5", "notes": null }),
+            )
+            .unwrap(),
+        )
+        .await?;
+        let seller = SellerBmc::get_by_seller_id(&ctx, &mm, &fx_into).await?;
+        assert_eq!(seller.tax_id.as_deref(), Some("29ABCDE1234F1Z5"));
+        assert_eq!(seller.name, "test_merge-Abc");
+
+        // -- Exec & Check merge
+        assert!(matches!(
+            SellerBmc::merge(&ctx, &mm, &fx_into, &fx_into).await,
+            Err(super::Error::SellerMergeSame { .. })
+        ));
+        SellerBmc::merge(&ctx, &mm, &fx_from, &fx_into).await?;
+        let bill = BillBmc::get_by_bill_id(&ctx, &mm, &bill_id).await?;
+        assert_eq!(bill.seller_id.as_deref(), Some(fx_into.as_str()));
+        let seller = SellerBmc::get_by_seller_id(&ctx, &mm, &fx_into).await?;
+        assert_eq!(seller.phone.as_deref(), Some("98450"));
+        assert!(matches!(
+            SellerBmc::get_by_seller_id(&ctx, &mm, &fx_from).await,
+            Err(super::Error::SellerNotFound { .. })
+        ));
+
+        // -- Exec & Check archive
+        SellerBmc::archive(&ctx, &mm, &fx_into).await?;
+        let seller = SellerBmc::get_by_seller_id(&ctx, &mm, &fx_into).await?;
+        assert!(seller.archived_at.is_some());
+        let found =
+            SellerBmc::search_by_name(&ctx, &mm, "test_merge", None).await?;
+        assert!(found.is_empty());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_validation_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let sellers = SellerBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(
+                json!([{ "name": "test_update_validation" }]),
+            )
+            .unwrap(),
+        )
+        .await?;
+        let fx_seller_id = &sellers[0].seller_id;
+
+        // -- Exec
+        let res = SellerBmc::update(
+            &ctx,
+            &mm,
+            fx_seller_id,
+            serde_json::from_value(json!({
+                "name": "   ",
+                "phone": "9".repeat(33),
+                "address": "a".repeat(513),
+                "taxId": null,
+                "notes": "n".repeat(1025)
+            }))
+            .unwrap(),
+        )
+        .await;
+
+        // -- Check
+        let Err(super::Error::Validation(validation::Error::Validation {
+            errors,
+        })) = res
+        else {
+            return Err(format!("Should fail validation: {res:?}").into());
+        };
+        let errors = errors
+            .iter()
+            .map(|v| (v.path.as_str(), v.code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("name", FieldErrorCode::Required),
+                ("phone", FieldErrorCode::TooLong),
+                ("address", FieldErrorCode::TooLong),
+                ("notes", FieldErrorCode::TooLong),
+            ]
+        );
+        let res = SellerBmc::update(
+            &ctx,
+            &mm,
+            fx_seller_id,
+            serde_json::from_value(json!({ "name": "s".repeat(65) })).unwrap(),
+        )
+        .await;
+        assert!(matches!(&res, Err(super::Error::Validation(..))), "{res:?}");
+        let seller =
+            SellerBmc::get_by_seller_id(&ctx, &mm, fx_seller_id).await?;
+        assert_eq!(seller.name, "test_update_validation");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_search_by_name_ranked_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let sellers = SellerBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "Mahalakshmi Traders" },
+                { "name": "Mahalaxmi Stores" },
+                { "name": "Ravi Vegetables" },
+                { "name": "Ravi Vegetables Market" }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let seller_id_of = |name: &str| {
+            sellers
+                .iter()
+                .find(|v| v.name == name)
+                .map(|v| v.seller_id.clone())
+                .unwrap()
+        };
+        BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({
+                "sellerId": seller_id_of("Ravi Vegetables Market")
+            }))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let typo =
+            SellerBmc::search_by_name(&ctx, &mm, "mahalakshmi", None).await?;
+        let recent =
+            SellerBmc::search_by_name(&ctx, &mm, "ravi veg", None).await?;
+
+        // -- Check
+        assert_eq!(typo[0].seller.name, "Mahalakshmi Traders");
+        assert!(typo[0].score > 0.99);
+        assert!(typo.iter().any(|v| v.seller.name == "Mahalaxmi Stores"));
+        assert_eq!(recent[0].seller.name, "Ravi Vegetables Market");
+        assert!(recent[0].last_bill_time.is_some());
+        assert_eq!(recent[0].score, recent[1].score);
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
@@ -13,6 +13,9 @@ use tokio::sync::Mutex;
 
 // endregion: --- Modules
 
+/// Attempts of `Dbx::retry_on_id_collision` before giving up.
+const MAX_ID_ATTEMPTS: usize = 5;
+
 /// Database global state
 #[derive(Debug, Clone)]
 pub struct Dbx {
@@ -225,4 +228,56 @@ impl Dbx {
 
         Ok(row_affected)
     }
+
+    /// Run `insert` again while it fails on `constraint`, the unique
+    /// constraint of a generated public id. `insert` must generate new ids on
+    /// each call.
+    ///
+    /// In a transaction, each attempt runs in a savepoint so that a collision
+    /// does not abort the whole transaction.
+    pub async fn retry_on_id_collision<T, F, Fut>(
+        &self,
+        constraint: &str,
+        mut insert: F,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let in_txn = self.with_txn && self.txn_holder.lock().await.is_some();
+
+        let mut attempt = 1;
+        loop {
+            if in_txn {
+                self.execute(sqlx::query("savepoint public_id;")).await?;
+            }
+
+            match insert().await {
+                Ok(value) => {
+                    if in_txn {
+                        self.execute(sqlx::query(
+                            "release savepoint public_id;",
+                        ))
+                        .await?;
+                    }
+                    return Ok(value);
+                }
+                Err(ex)
+                    if attempt < MAX_ID_ATTEMPTS
+                        && ex
+                            .resolve_unique_violation()
+                            .is_some_and(|v| v.constraint == constraint) =>
+                {
+                    if in_txn {
+                        self.execute(sqlx::query(
+                            "rollback to savepoint public_id;",
+                        ))
+                        .await?;
+                    }
+                    attempt += 1;
+                }
+                Err(ex) => return Err(ex),
+            }
+        }
+    }
 }
@@ -26,6 +26,17 @@ pub async fn new_db_pool() -> sqlx::Result<Db> {
         .await
 }
 
+/// `like`/`ilike` pattern matching the values containing `v` literally, with
+/// the default `\` escape character.
+pub(in crate::model) fn like_contains_pattern(v: &str) -> String {
+    let v = v
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{v}%")
+}
+
 // NOTE 1) This is not an ideal situation; however, with sqlx 0.7.1, when executing `cargo test`, some tests that use sqlx fail at a
 //         rather low level (in the tokio scheduler). It appears to be a low-level thread/async issue, as removing/adding
 //         tests causes different tests to fail. The cause remains uncertain, but setting max_connections to 1 resolves the issue.
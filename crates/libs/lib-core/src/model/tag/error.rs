@@ -0,0 +1,50 @@
+use crate::model::acs;
+use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::{transaction, validation};
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    TagNotFound {
+        name: String,
+    },
+
+    TagNotUnique,
+
+    TagMergeSame {
+        name: String,
+    },
+
+    TagParentCycle {
+        name: String,
+        parent: String,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Transaction(#[from] transaction::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Dbx(dbx::Error),
+}
+
+impl From<dbx::Error> for Error {
+    fn from(value: dbx::Error) -> Self {
+        match value.resolve_unique_violation() {
+            Some(UniqueViolation { .. }) => Self::TagNotUnique,
+            None => Self::Dbx(value),
+        }
+    }
+}
+
+// region:    --- Error Boilerplate
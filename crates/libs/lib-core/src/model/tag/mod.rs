@@ -0,0 +1,481 @@
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::acs::{self, Privilege};
+use crate::model::transaction::TransactionBmc;
+use crate::model::validation::{Validate, Validator};
+use bigdecimal::BigDecimal;
+use lib_utils::time::TimeRfc3339;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+mod error;
+
+pub use error::{Error, Result};
+
+/// Tags are created on the fly by `TransactionBmc` (trimmed, lowercased) and
+/// identified by their name among the tags of their organisation.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub name: String,
+    pub parent: Option<String>,
+    /// Names from the root tag, e.g. `groceries/vegetables`.
+    pub path: String,
+    /// Number of transactions tagged with this exact tag.
+    pub usage_count: i64,
+    /// Default GST rate (in percent) of the new transactions with this tag.
+    pub tax_rate: Option<BigDecimal>,
+}
+
+/// Fields that can be changed on an existing tag.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagForUpdate {
+    /// Rename the tag.
+    name: Option<String>,
+    /// An explicit `null` makes it a root tag.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    parent: Option<Option<String>>,
+    /// An explicit `null` removes the default tax rate.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    tax_rate: Option<Option<BigDecimal>>,
+}
+
+impl Validate for TagForUpdate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        if let Some(name) = self.name.as_deref() {
+            validator.required(path, "name", name).max_len(
+                path,
+                "name",
+                Some(name.trim()),
+                64,
+            );
+        }
+        validator.percent(
+            path,
+            "taxRate",
+            self.tax_rate.as_ref().and_then(Option::as_ref),
+        );
+    }
+}
+
+pub struct TagBmc;
+
+impl TagBmc {
+    fn normalize(name: &str) -> String {
+        name.trim().to_lowercase()
+    }
+
+    /// All tags ordered by path, so children follow their parent.
+    pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Tag>> {
+        acs::require(ctx, mm, Privilege::TagRead).await?;
+
+        // NOTE: The parent of a tag is a tag of the same organisation.
+        let sqlx_query = sqlx::query_as::<_, Tag>(
+            "with recursive tag_path (serial_id, path) as (
+                select serial_id, name::text from tag
+                where parent_serial_id is null
+                  and org_serial_id = org_serial_id($1)
+                union all
+                select t.serial_id, tp.path || '/' || t.name from tag t
+                inner join tag_path tp on tp.serial_id = t.parent_serial_id
+            )
+            select t.name, p.name as parent, tp.path,
+                (select count(*) from transaction_tag tt
+                    where tt.tag_serial_id = t.serial_id) as usage_count,
+                t.tax_rate
+            from tag t
+            inner join tag_path tp on tp.serial_id = t.serial_id
+            left join tag p on p.serial_id = t.parent_serial_id
+            order by tp.path;",
+        )
+        .bind(ctx.org_id());
+
+        let tags = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(tags)
+    }
+
+    /// Rename a tag, change its parent and/or its default tax rate.
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        name: &str,
+        tag_u: TagForUpdate,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::TagUpdate).await?;
+
+        tag_u.validate()?;
+
+        let TagForUpdate {
+            name: new_name,
+            parent,
+            tax_rate,
+        } = tag_u;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let serial_id = Self::lock_serial_id(ctx, &mm, name).await?;
+
+        if let Some(parent) = parent {
+            let parent_serial_id = match parent {
+                Some(parent) => {
+                    let parent_serial_id =
+                        Self::lock_serial_id(ctx, &mm, &parent).await?;
+                    if Self::is_ancestor_or_self(
+                        &mm,
+                        serial_id,
+                        parent_serial_id,
+                    )
+                    .await?
+                    {
+                        return Err(Error::TagParentCycle {
+                            name: name.to_string(),
+                            parent,
+                        });
+                    }
+                    Some(parent_serial_id)
+                }
+                None => None,
+            };
+
+            let sqlx_query = sqlx::query(
+                "update tag set parent_serial_id = $2, mtime = $3
+                where serial_id = $1;",
+            )
+            .bind(serial_id)
+            .bind(parent_serial_id)
+            .bind(now);
+
+            mm.dbx().execute(sqlx_query).await?;
+        }
+
+        if let Some(tax_rate) = tax_rate {
+            let sqlx_query = sqlx::query(
+                "update tag set tax_rate = $2, mtime = $3 where serial_id = $1;",
+            )
+            .bind(serial_id)
+            .bind(tax_rate)
+            .bind(now);
+
+            mm.dbx().execute(sqlx_query).await?;
+        }
+
+        if let Some(new_name) = new_name {
+            let sqlx_query = sqlx::query(
+                "update tag set name = $2, mtime = $3 where serial_id = $1;",
+            )
+            .bind(serial_id)
+            .bind(Self::normalize(&new_name))
+            .bind(now);
+
+            mm.dbx().execute(sqlx_query).await?;
+        }
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Merge the tag `from` into the tag `into`: its transactions (and their
+    /// allocated amounts) and its children move to `into`, then `from` is
+    /// deleted.
+    pub async fn merge(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        from: &str,
+        into: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::TagMerge).await?;
+
+        if Self::normalize(from) == Self::normalize(into) {
+            return Err(Error::TagMergeSame {
+                name: from.to_string(),
+            });
+        }
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let from_serial_id = Self::lock_serial_id(ctx, &mm, from).await?;
+        let into_serial_id = Self::lock_serial_id(ctx, &mm, into).await?;
+
+        // NOTE: `into` would become the parent of the children of `from`.
+        if Self::is_ancestor_or_self(&mm, from_serial_id, into_serial_id)
+            .await?
+        {
+            return Err(Error::TagParentCycle {
+                name: from.to_string(),
+                parent: into.to_string(),
+            });
+        }
+
+        let sqlx_query = sqlx::query(
+            "insert into transaction_tag (transaction_serial_id, tag_serial_id, amount)
+            select tt.transaction_serial_id, $2, tt.amount from transaction_tag tt
+            where tt.tag_serial_id = $1
+            on conflict (transaction_serial_id, tag_serial_id)
+            do update set amount = transaction_tag.amount + excluded.amount;",
+        )
+        .bind(from_serial_id)
+        .bind(into_serial_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        let sqlx_query = sqlx::query(
+            "update tag set parent_serial_id = $2, mtime = $3
+            where parent_serial_id = $1;",
+        )
+        .bind(from_serial_id)
+        .bind(into_serial_id)
+        .bind(now);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        let sqlx_query = sqlx::query("delete from tag where serial_id = $1;")
+            .bind(from_serial_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Delete a tag, it is removed from its transactions and its children
+    /// become root tags. What was allocated to it goes to the other tags of
+    /// each transaction, in proportion.
+    pub async fn delete(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        name: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::TagDelete).await?;
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let serial_id = Self::lock_serial_id(ctx, &mm, name).await?;
+
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "select tr.serial_id from transaction tr
+            where tr.serial_id in (
+                select transaction_serial_id from transaction_tag
+                where tag_serial_id = $1)
+            for update;",
+        )
+        .bind(serial_id);
+
+        let transaction_serial_ids = mm
+            .dbx()
+            .fetch_all(sqlx_query)
+            .await?
+            .into_iter()
+            .map(|v| v.0)
+            .collect::<Vec<i64>>();
+
+        let sqlx_query = sqlx::query("delete from tag where serial_id = $1;")
+            .bind(serial_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        TransactionBmc::rebalance_allocations(&mm, &transaction_serial_ids)
+            .await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    async fn lock_serial_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        name: &str,
+    ) -> Result<i64> {
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "select serial_id from tag
+            where name = $1 and org_serial_id = org_serial_id($2)
+            for update;",
+        )
+        .bind(Self::normalize(name))
+        .bind(ctx.org_id());
+
+        let (serial_id,) = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::TagNotFound {
+                name: name.to_string(),
+            },
+        )?;
+
+        Ok(serial_id)
+    }
+
+    /// True when `serial_id` is `of_serial_id` or one of its ancestors.
+    async fn is_ancestor_or_self(
+        mm: &ModelManager,
+        serial_id: i64,
+        of_serial_id: i64,
+    ) -> Result<bool> {
+        let sqlx_query = sqlx::query_as::<_, (bool,)>(
+            "with recursive ancestor (serial_id, parent_serial_id) as (
+                select serial_id, parent_serial_id from tag where serial_id = $2
+                union all
+                select t.serial_id, t.parent_serial_id from tag t
+                inner join ancestor a on a.parent_serial_id = t.serial_id
+            )
+            select exists (select 1 from ancestor where serial_id = $1);",
+        )
+        .bind(serial_id)
+        .bind(of_serial_id);
+
+        let (is_ancestor,) = mm.dbx().fetch_one(sqlx_query).await?;
+
+        Ok(is_ancestor)
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::bill::BillBmc;
+    use crate::model::transaction::TransactionBmc;
+    use bigdecimal::BigDecimal;
+    use lib_utils::money::Currency;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_hierarchy_rename_merge_delete_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0001" })).unwrap(),
+        )
+        .await?;
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "tags": ["vegetables"], "paymentMethod": "Cash", "sellerCost": 10, "billId": bill_id },
+                { "name": "tr 2", "tags": ["groceries", "fruits"], "paymentMethod": "Cash", "sellerCost": 5, "billId": bill_id },
+                { "name": "tr 3", "tags": ["veggies"], "paymentMethod": "Cash", "sellerCost": 2, "billId": bill_id }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_parent = |parent: &str| -> TagForUpdate {
+            serde_json::from_value(json!({ "parent": parent })).unwrap()
+        };
+
+        // -- Exec & Check hierarchy
+        TagBmc::update(&ctx, &mm, "vegetables", fx_parent("groceries")).await?;
+        TagBmc::update(&ctx, &mm, "Fruits", fx_parent("groceries")).await?;
+        assert!(matches!(
+            TagBmc::update(&ctx, &mm, "groceries", fx_parent("vegetables"))
+                .await,
+            Err(super::Error::TagParentCycle { .. })
+        ));
+        let details =
+            BillBmc::get_details(&ctx, &mm, &bill_id, Currency::Inr).await?;
+        let groceries = details
+            .totals_by_tag
+            .iter()
+            .find(|v| v.name == "groceries")
+            .unwrap();
+        assert_eq!(groceries.total, dec("15"), "tr 2 counted once");
+
+        // -- Exec & Check rename, merge
+        TagBmc::update(
+            &ctx,
+            &mm,
+            "vegetables",
+            serde_json::from_value(json!({ "name": "Veg" })).unwrap(),
+        )
+        .await?;
+        TagBmc::merge(&ctx, &mm, "veggies", "veg").await?;
+        let tags = TagBmc::list(&ctx, &mm).await?;
+        let veg = tags.iter().find(|v| v.name == "veg").unwrap();
+        assert_eq!(veg.path, "groceries/veg");
+        assert_eq!(veg.usage_count, 2);
+        assert!(tags.iter().all(|v| v.name != "veggies"));
+
+        // -- Exec & Check delete
+        TagBmc::delete(&ctx, &mm, "groceries").await?;
+        let tags = TagBmc::list(&ctx, &mm).await?;
+        let veg = tags.iter().find(|v| v.name == "veg").unwrap();
+        assert_eq!(veg.parent, None);
+        assert!(matches!(
+            TagBmc::delete(&ctx, &mm, "groceries").await,
+            Err(super::Error::TagNotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_name_validation_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 1", "tags": ["test_rename"], "paymentMethod": "Cash", "sellerCost": 10 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_names = [" ".to_string(), "a".repeat(65)];
+
+        for fx_name in fx_names {
+            // -- Exec
+            let res = TagBmc::update(
+                &ctx,
+                &mm,
+                "test_rename",
+                serde_json::from_value(json!({ "name": fx_name })).unwrap(),
+            )
+            .await;
+
+            // -- Check
+            assert!(
+                matches!(
+                    &res,
+                    Err(super::Error::Validation(
+                        crate::model::validation::Error::Validation { errors },
+                    )) if errors.len() == 1 && errors[0].path == "name"
+                ),
+                "{res:?}"
+            );
+        }
+        let tags = TagBmc::list(&ctx, &mm).await?;
+        assert!(tags.iter().any(|v| v.name == "test_rename"));
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
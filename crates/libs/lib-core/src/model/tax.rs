@@ -0,0 +1,253 @@
+//! GST (goods and services tax) of the transactions.
+//!
+//! The `seller_cost` of a transaction is what the seller charges, tax
+//! included. Its taxable value is derived from the tax rate, then the tax is
+//! split into CGST and SGST (half of the rate each) for an intra-state
+//! supply, or charged as IGST for an inter-state supply. Whatever the
+//! rounding leaves is the `round_off`, as on the seller invoices.
+//!
+//! The tax is rounded per transaction, as configured by `TaxRounding`.
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GstSupply {
+    /// Charged as CGST + SGST.
+    #[default]
+    IntraState,
+    /// Charged as IGST.
+    InterState,
+}
+
+impl GstSupply {
+    pub fn is_inter_state(&self) -> bool {
+        matches!(self, Self::InterState)
+    }
+
+    pub fn from_inter_state(inter_state: bool) -> Self {
+        if inter_state {
+            Self::InterState
+        } else {
+            Self::IntraState
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, strum_macros::EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum TaxRoundingMode {
+    #[default]
+    HalfUp,
+    HalfEven,
+    /// Away from zero.
+    Up,
+    /// Toward zero.
+    Down,
+}
+
+impl From<TaxRoundingMode> for RoundingMode {
+    fn from(value: TaxRoundingMode) -> Self {
+        match value {
+            TaxRoundingMode::HalfUp => RoundingMode::HalfUp,
+            TaxRoundingMode::HalfEven => RoundingMode::HalfEven,
+            TaxRoundingMode::Up => RoundingMode::Up,
+            TaxRoundingMode::Down => RoundingMode::Down,
+        }
+    }
+}
+
+/// How the taxable value and the tax amounts are rounded.
+#[derive(Clone, Copy, Debug)]
+pub struct TaxRounding {
+    pub mode: TaxRoundingMode,
+    /// Digits kept after the decimal point, e.g. 0 for whole rupees.
+    pub scale: i64,
+}
+
+impl Default for TaxRounding {
+    fn default() -> Self {
+        Self {
+            mode: TaxRoundingMode::HalfUp,
+            scale: 2,
+        }
+    }
+}
+
+impl TaxRounding {
+    fn round(&self, value: BigDecimal) -> BigDecimal {
+        value.with_scale_round(self.scale, self.mode.into())
+    }
+}
+
+/// The tax of a transaction, in the transaction currency.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionTax {
+    /// In percent, e.g. `18` for the 18% slab.
+    pub rate: BigDecimal,
+    pub supply: GstSupply,
+    pub taxable_value: BigDecimal,
+    pub cgst: BigDecimal,
+    pub sgst: BigDecimal,
+    pub igst: BigDecimal,
+    /// `seller_cost - taxable_value - cgst - sgst - igst`.
+    pub round_off: BigDecimal,
+}
+
+impl TransactionTax {
+    /// Tax included in `seller_cost` at `rate`.
+    pub fn compute(
+        seller_cost: &BigDecimal,
+        rate: BigDecimal,
+        supply: GstSupply,
+        rounding: &TaxRounding,
+    ) -> Self {
+        let hundred = BigDecimal::from(100);
+        let zero = BigDecimal::from(0);
+
+        // NOTE: The taxable value is always kept to the paisa, only the
+        //       tax amounts may be rounded further (e.g., to the rupee).
+        let taxable_value = (seller_cost * &hundred / (&hundred + &rate))
+            .with_scale_round(2, rounding.mode.into());
+
+        let (cgst, sgst, igst) = match supply {
+            GstSupply::IntraState => {
+                let half = rounding
+                    .round(&taxable_value * &rate / BigDecimal::from(200));
+                (half.clone(), half, zero)
+            }
+            GstSupply::InterState => {
+                let igst = rounding.round(&taxable_value * &rate / &hundred);
+                (zero.clone(), zero, igst)
+            }
+        };
+
+        let round_off = seller_cost - &taxable_value - &cgst - &sgst - &igst;
+
+        Self {
+            rate,
+            supply,
+            taxable_value,
+            cgst,
+            sgst,
+            igst,
+            round_off,
+        }
+    }
+}
+
+/// Totals of the transactions charged at one tax rate.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxRateTotal {
+    pub rate: BigDecimal,
+    pub taxable_value: BigDecimal,
+    pub cgst: BigDecimal,
+    pub sgst: BigDecimal,
+    pub igst: BigDecimal,
+    /// The `seller_cost` of these transactions.
+    pub total: BigDecimal,
+}
+
+/// Tax of a set of transactions (e.g., a bill), per rate and overall.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxSummary {
+    pub by_rate: Vec<TaxRateTotal>,
+    pub taxable_value: BigDecimal,
+    pub cgst: BigDecimal,
+    pub sgst: BigDecimal,
+    pub igst: BigDecimal,
+    pub total_tax: BigDecimal,
+    pub round_off: BigDecimal,
+    /// The `seller_cost` of the transactions without a tax rate.
+    pub untaxed: BigDecimal,
+}
+
+impl TaxSummary {
+    /// Sum up `by_rate`, `total` being the `seller_cost` of all the
+    /// transactions (taxed or not).
+    pub fn new(by_rate: Vec<TaxRateTotal>, total: &BigDecimal) -> Self {
+        let zero = BigDecimal::from(0);
+        let sum = |f: fn(&TaxRateTotal) -> &BigDecimal| {
+            by_rate.iter().map(f).fold(zero.clone(), |acc, v| acc + v)
+        };
+
+        let taxable_value = sum(|v| &v.taxable_value);
+        let cgst = sum(|v| &v.cgst);
+        let sgst = sum(|v| &v.sgst);
+        let igst = sum(|v| &v.igst);
+        let taxed = sum(|v| &v.total);
+        let total_tax = &cgst + &sgst + &igst;
+        let round_off = &taxed - &taxable_value - &total_tax;
+        let untaxed = total - &taxed;
+
+        Self {
+            by_rate,
+            taxable_value,
+            cgst,
+            sgst,
+            igst,
+            total_tax,
+            round_off,
+            untaxed,
+        }
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_compute_and_rounding_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_rupee = TaxRounding {
+            mode: TaxRoundingMode::HalfUp,
+            scale: 0,
+        };
+
+        // -- Exec
+        let intra = TransactionTax::compute(
+            &dec("1180"),
+            dec("18"),
+            GstSupply::IntraState,
+            &TaxRounding::default(),
+        );
+        let inter = TransactionTax::compute(
+            &dec("105.50"),
+            dec("5"),
+            GstSupply::InterState,
+            &TaxRounding::default(),
+        );
+        let rupee = TransactionTax::compute(
+            &dec("105.50"),
+            dec("5"),
+            GstSupply::IntraState,
+            &fx_rupee,
+        );
+
+        // -- Check
+        assert_eq!(intra.taxable_value, dec("1000"));
+        assert_eq!((&intra.cgst, &intra.sgst), (&dec("90"), &dec("90")));
+        assert_eq!(intra.round_off, dec("0"));
+        assert_eq!(inter.taxable_value, dec("100.48"));
+        assert_eq!(inter.igst, dec("5.02"));
+        assert_eq!(inter.round_off, dec("0"));
+        assert_eq!(rupee.cgst, dec("3"), "2.512 rounded to the rupee");
+        assert_eq!(rupee.round_off, dec("-0.98"));
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
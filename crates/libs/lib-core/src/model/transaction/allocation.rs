@@ -0,0 +1,229 @@
+//! Portions of the seller cost of a transaction assigned to its tags (e.g.,
+//! `groceries`, `cleaning` and `office` of one supermarket receipt). A tag
+//! may stand for a cost centre.
+//!
+//! The allocations of a tagged transaction always sum up to its seller cost,
+//! so the tag reports count each cost once.
+
+use crate::model::validation::{Validate, Validator, join_path};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagAllocation {
+    pub tag: String,
+    /// Has the sign of the seller cost (only an adjustment is negative).
+    pub amount: BigDecimal,
+}
+
+impl Validate for TagAllocation {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .required(path, "tag", &self.tag)
+            .max_len(path, "tag", Some(self.tag.trim()), 64)
+            .amount(path, "amount", &self.amount.abs());
+    }
+}
+
+/// Validate the `allocations` of a transaction (at `path`) of `seller_cost`.
+pub(crate) fn validate_allocations(
+    path: &str,
+    allocations: &[TagAllocation],
+    seller_cost: &BigDecimal,
+    validator: &mut Validator,
+) {
+    let zero = BigDecimal::from(0);
+    for (idx, allocation) in allocations.iter().enumerate() {
+        let path = join_path(path, &format!("allocations[{idx}]"));
+        allocation.validate_at(&path, validator);
+        validator.expect(
+            &path,
+            "amount",
+            &allocation.amount * seller_cost >= zero,
+            "must have the sign of the seller cost",
+        );
+    }
+
+    let sum = allocations.iter().fold(zero, |acc, v| acc + &v.amount);
+    let tags = allocations
+        .iter()
+        .map(|v| v.tag.trim().to_lowercase())
+        .collect::<HashSet<String>>();
+    validator
+        .expect(
+            path,
+            "allocations",
+            &sum == seller_cost,
+            "must sum up to the seller cost",
+        )
+        .expect(
+            path,
+            "allocations",
+            tags.len() == allocations.len(),
+            "must not allocate to a tag twice",
+        );
+}
+
+/// Allocations of a transaction of `seller_cost` to its (normalized) tags,
+/// in input order.
+///
+/// The tags of `allocations` are tags of the transaction too, and the
+/// `tags` left out of them get nothing. Without `allocations`, the seller
+/// cost is split evenly across the tags.
+pub(crate) fn allocate(
+    seller_cost: &BigDecimal,
+    tags: Option<Vec<String>>,
+    allocations: Option<Vec<TagAllocation>>,
+) -> Vec<TagAllocation> {
+    let mut seen = HashSet::new();
+    let mut allocated = Vec::new();
+
+    let explicit = allocations.is_some();
+    for allocation in allocations.into_iter().flatten() {
+        let tag = allocation.tag.trim().to_lowercase();
+        if seen.insert(tag.clone()) {
+            allocated.push(TagAllocation {
+                tag,
+                amount: allocation.amount,
+            });
+        }
+    }
+    for tag in tags.into_iter().flatten() {
+        let tag = tag.trim().to_lowercase();
+        if seen.insert(tag.clone()) {
+            allocated.push(TagAllocation {
+                tag,
+                amount: BigDecimal::from(0),
+            });
+        }
+    }
+
+    if !explicit {
+        let weights = vec![BigDecimal::from(0); allocated.len()];
+        for (allocation, amount) in
+            allocated.iter_mut().zip(split(seller_cost, &weights))
+        {
+            allocation.amount = amount;
+        }
+    }
+
+    allocated
+}
+
+/// Split `total` (to the cent) in proportion to `weights`, evenly when they
+/// are all zero. The cents left by the rounding go to the largest remainders,
+/// then to the first weights.
+pub(crate) fn split(
+    total: &BigDecimal,
+    weights: &[BigDecimal],
+) -> Vec<BigDecimal> {
+    let to_cents =
+        |v: &BigDecimal| (v * BigDecimal::from(100)).round(0).to_i128();
+
+    let total_cents = to_cents(total).unwrap_or_default();
+    let mut weights = weights
+        .iter()
+        .map(|v| to_cents(v).unwrap_or_default().abs())
+        .collect::<Vec<i128>>();
+    if weights.iter().all(|v| *v == 0) {
+        weights.iter_mut().for_each(|v| *v = 1);
+    }
+    let weight_sum = weights.iter().sum::<i128>();
+    if weight_sum == 0 {
+        return Vec::new();
+    }
+
+    let sign = total_cents.signum();
+    let total_cents = total_cents.abs();
+
+    let mut cents = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+    for (idx, weight) in weights.iter().enumerate() {
+        cents.push(total_cents * weight / weight_sum);
+        remainders.push((total_cents * weight % weight_sum, idx));
+    }
+
+    let left = total_cents - cents.iter().sum::<i128>();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (_, idx) in remainders.into_iter().take(left as usize) {
+        cents[idx] += 1;
+    }
+
+    cents
+        .into_iter()
+        .map(|v| BigDecimal::new((sign * v).into(), 2))
+        .collect()
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_split_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let zeros = [dec("0"), dec("0"), dec("0")];
+
+        // -- Exec
+        let even = split(&dec("10"), &zeros);
+        let proportional = split(&dec("90"), &[dec("20"), dec("40")]);
+        let negative = split(&dec("-0.05"), &[dec("1"), dec("1")]);
+
+        // -- Check
+        assert_eq!(even, [dec("3.34"), dec("3.33"), dec("3.33")]);
+        assert_eq!(proportional, [dec("30"), dec("60")]);
+        assert_eq!(negative, [dec("-0.03"), dec("-0.02")]);
+        assert!(split(&dec("1"), &[]).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allocate_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_tags = || Some(vec!["Food ".to_string(), "home".to_string()]);
+        let fx_allocations = vec![TagAllocation {
+            tag: "office".to_string(),
+            amount: dec("10"),
+        }];
+
+        // -- Exec
+        let even = allocate(&dec("10"), fx_tags(), None);
+        let explicit = allocate(&dec("10"), fx_tags(), Some(fx_allocations));
+
+        // -- Check
+        let pairs = |v: &[TagAllocation]| {
+            v.iter()
+                .map(|v| (v.tag.clone(), v.amount.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            pairs(&even),
+            [
+                ("food".into(), "5.00".into()),
+                ("home".into(), "5.00".into())
+            ]
+        );
+        assert_eq!(
+            pairs(&explicit),
+            [
+                ("office".into(), "10".into()),
+                ("food".into(), "0".into()),
+                ("home".into(), "0".into())
+            ]
+        );
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
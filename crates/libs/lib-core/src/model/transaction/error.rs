@@ -1,4 +1,7 @@
+use crate::model::acs;
 use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::{bill, pagination, validation};
+use bigdecimal::BigDecimal;
 use serde::Serialize;
 use serde_with::serde_as;
 
@@ -13,6 +16,57 @@ pub enum Error {
 
     TransactionNotUnique,
 
+    TransactionUnitCostMismatch {
+        unit_type: String,
+        expected_seller_cost: BigDecimal,
+        seller_cost: BigDecimal,
+    },
+
+    TransactionUnitCostNotResolved {
+        unit_type: String,
+    },
+
+    TransactionOriginalNotFound {
+        transaction_id: String,
+    },
+
+    TransactionOriginalInvalid {
+        transaction_id: String,
+        reason: &'static str,
+    },
+
+    /// The refunds, returns and discounts of the purchase `transaction_id`
+    /// would add up to more than its seller cost.
+    TransactionExceedsOriginal {
+        transaction_id: String,
+        seller_cost: BigDecimal,
+        credited: BigDecimal,
+    },
+
+    /// Other transactions link to it, e.g. the refunds of a purchase.
+    TransactionHasLinked {
+        transaction_id: String,
+    },
+
+    /// The tags of the transaction have different default tax rates.
+    TransactionTaxRateAmbiguous {
+        name: String,
+        tags: Vec<String>,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Bill(#[from] bill::Error),
+
+    #[error(transparent)]
+    Pagination(#[from] pagination::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
     #[error(transparent)]
     Dbx(dbx::Error),
 }
@@ -1,15 +1,30 @@
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
+use crate::core_config;
+use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::model::acs::{self, Privilege};
+use crate::model::bill::BillBmc;
+use crate::model::pagination::{Cursor, Page, SortOrder, normalize_limit};
+use crate::model::store::like_contains_pattern;
+use crate::model::tax::{GstSupply, TransactionTax};
+use crate::model::validation::{Validate, Validator, join_path};
 use bigdecimal::BigDecimal;
-use lib_utils::{b58::b58_encode, time::TimeRfc3339};
-use rand::RngCore as _;
+use lib_utils::{id::IdSpec, money::Currency, time::TimeRfc3339};
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
+mod allocation;
 mod error;
 
+pub use allocation::TagAllocation;
 pub use error::{Error, Result};
 
+use allocation::{allocate, split, validate_allocations};
+
 #[derive(
     Clone,
     Debug,
@@ -30,12 +45,96 @@ pub enum PaymentMethod {
     Unknown,
 }
 
+impl TryFrom<String> for PaymentMethod {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// What a transaction is, see the `transaction_kind` table for how each
+/// kind counts toward the totals.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    PartialEq,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::IntoStaticStr,
+    strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum TransactionKind {
+    #[default]
+    Purchase,
+    /// Money given back by the seller for a purchase.
+    Refund,
+    /// Goods of a purchase given back to the seller.
+    Return,
+    /// Taken off what is owed to the seller.
+    Discount,
+    /// A correction either way, its `seller_cost` may be negative.
+    Adjustment,
+}
+
+impl TransactionKind {
+    /// Kinds that must link to the purchase they credit.
+    fn requires_original(&self) -> bool {
+        matches!(self, Self::Refund | Self::Return)
+    }
+
+    fn allows_original(&self) -> bool {
+        !matches!(self, Self::Purchase)
+    }
+}
+
+impl TryFrom<String> for TransactionKind {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnitCost {
-    unit_type: String,
-    unit: BigDecimal,
-    cost_per_unit: BigDecimal,
+    pub unit_type: String,
+    pub unit: BigDecimal,
+    pub cost_per_unit: BigDecimal,
+}
+
+impl Validate for UnitCost {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .required(path, "unitType", &self.unit_type)
+            .max_len(path, "unitType", Some(self.unit_type.trim()), 64)
+            .positive(path, "unit", &self.unit)
+            .amount(path, "costPerUnit", &self.cost_per_unit);
+    }
+}
+
+impl UnitCost {
+    /// `unit * cost_per_unit` must match the `seller_cost` of the transaction,
+    /// give or take one hundredth for rounding.
+    fn check_seller_cost(&self, seller_cost: &BigDecimal) -> Result<()> {
+        let expected_seller_cost = (&self.unit * &self.cost_per_unit).round(2);
+        let tolerance = BigDecimal::new(1.into(), 2);
+
+        if (&expected_seller_cost - seller_cost.round(2)).abs() > tolerance {
+            return Err(Error::TransactionUnitCostMismatch {
+                unit_type: self.unit_type.trim().to_string(),
+                expected_seller_cost,
+                seller_cost: seller_cost.clone(),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,538 +144,2825 @@ pub struct TransactionForCreate {
     transaction_time: Option<TimeRfc3339>,
     remark: Option<String>,
     tags: Option<Vec<String>>,
+    /// Split of the seller cost across tags (added to `tags`), defaults to
+    /// an even split across `tags`.
+    allocations: Option<Vec<TagAllocation>>,
     payment_method: PaymentMethod,
     unit_cost: Option<UnitCost>,
     seller_cost: BigDecimal,
+    /// Of the seller cost and the unit cost, defaults to `INR`.
+    #[serde(default)]
+    currency: Currency,
+    /// GST rate (in percent) included in the seller cost. Defaults to the
+    /// rate of the unit type, else to the rate of the tags.
+    tax_rate: Option<BigDecimal>,
+    #[serde(default)]
+    gst_supply: GstSupply,
+    #[serde(default)]
+    kind: TransactionKind,
+    /// The purchase a refund or a return (or optionally a discount or an
+    /// adjustment) applies to.
+    original_transaction_id: Option<String>,
     bill_id: Option<String>,
 }
 
+impl Validate for TransactionForCreate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator
+            .required(path, "name", &self.name)
+            .max_len(path, "name", Some(&self.name), 128)
+            .max_len(path, "remark", self.remark.as_deref(), 256)
+            .not_in_future(
+                path,
+                "transactionTime",
+                self.transaction_time.as_ref().map(|v| v.inner()),
+            )
+            .percent(path, "taxRate", self.tax_rate.as_ref())
+            .expect(
+                path,
+                "originalTransactionId",
+                !self.kind.requires_original()
+                    || self.original_transaction_id.is_some(),
+                "is required for a refund or a return",
+            )
+            .expect(
+                path,
+                "originalTransactionId",
+                self.kind.allows_original()
+                    || self.original_transaction_id.is_none(),
+                "must not be set for a purchase",
+            )
+            .max_len(
+                path,
+                "originalTransactionId",
+                self.original_transaction_id.as_deref(),
+                30,
+            )
+            .max_len(path, "billId", self.bill_id.as_deref(), 30);
+
+        if self.kind == TransactionKind::Adjustment {
+            validator.amount(path, "sellerCost", &self.seller_cost.abs());
+        } else {
+            validator.amount(path, "sellerCost", &self.seller_cost);
+        }
+
+        for (idx, tag) in self.tags.iter().flatten().enumerate() {
+            let field = format!("tags[{idx}]");
+            validator.required(path, &field, tag).max_len(
+                path,
+                &field,
+                Some(tag.trim()),
+                64,
+            );
+        }
+
+        if let Some(allocations) = self.allocations.as_deref() {
+            validate_allocations(
+                path,
+                allocations,
+                &self.seller_cost,
+                validator,
+            );
+        }
+
+        if let Some(unit_cost) = self.unit_cost.as_ref() {
+            unit_cost.validate_at(&join_path(path, "unitCost"), validator);
+        }
+    }
+}
+
+/// Fields that can be changed on an existing transaction.
+///
+/// Absent fields are left untouched. For nullable fields, an explicit
+/// `null` clears the value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionForUpdate {
+    name: Option<String>,
+    transaction_time: Option<TimeRfc3339>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    remark: Option<Option<String>>,
+    /// Replaces all the tags of the transaction, splitting the seller cost
+    /// evenly across them unless `allocations` is set.
+    tags: Option<Vec<String>>,
+    /// Replaces the allocations (and the tags, see `TransactionForCreate`).
+    /// Otherwise, the current allocations follow the seller cost.
+    allocations: Option<Vec<TagAllocation>>,
+    payment_method: Option<PaymentMethod>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    unit_cost: Option<Option<UnitCost>>,
+    seller_cost: Option<BigDecimal>,
+    currency: Option<Currency>,
+    /// An explicit `null` removes the tax.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    tax_rate: Option<Option<BigDecimal>>,
+    gst_supply: Option<GstSupply>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    bill_id: Option<Option<String>>,
+}
+
+impl Validate for TransactionForUpdate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        if let Some(name) = self.name.as_deref() {
+            validator.required(path, "name", name).max_len(
+                path,
+                "name",
+                Some(name),
+                128,
+            );
+        }
+        // NOTE: The sign is checked against the kind of the transaction.
+        if let Some(seller_cost) = self.seller_cost.as_ref() {
+            validator.amount(path, "sellerCost", &seller_cost.abs());
+        }
+        if let Some(Some(unit_cost)) = self.unit_cost.as_ref() {
+            unit_cost.validate_at(&join_path(path, "unitCost"), validator);
+        }
+        // NOTE: Their sum is checked against the resulting seller cost.
+        for (idx, allocation) in self.allocations.iter().flatten().enumerate() {
+            allocation.validate_at(
+                &join_path(path, &format!("allocations[{idx}]")),
+                validator,
+            );
+        }
+        validator
+            .max_len(
+                path,
+                "remark",
+                self.remark.as_ref().and_then(|v| v.as_deref()),
+                256,
+            )
+            .not_in_future(
+                path,
+                "transactionTime",
+                self.transaction_time.as_ref().map(|v| v.inner()),
+            )
+            .percent(
+                path,
+                "taxRate",
+                self.tax_rate.as_ref().and_then(Option::as_ref),
+            )
+            .max_len(
+                path,
+                "billId",
+                self.bill_id.as_ref().and_then(|v| v.as_deref()),
+                30,
+            );
+    }
+}
+
 /// Fields required for creating new transaction
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Transaction {
+    pub transaction_id: String,
+    pub name: String,
+    pub transaction_time: TimeRfc3339,
+    pub remark: Option<String>,
+    pub tags: Vec<String>,
+    /// Split of `seller_cost` across `tags`, by tag name.
+    pub allocations: Vec<TagAllocation>,
+    pub payment_method: PaymentMethod,
+    pub unit_cost: Option<UnitCost>,
+    pub seller_cost: BigDecimal,
+    pub currency: Currency,
+    /// GST included in `seller_cost`, if any.
+    pub tax: Option<TransactionTax>,
+    pub kind: TransactionKind,
+    pub original_transaction_id: Option<String>,
+    pub bill_id: Option<String>,
+}
+
+/// Filters for `TransactionBmc::list`. All set filters must match.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionFilter {
+    /// Inclusive lower bound on `transaction_time`.
+    pub from: Option<TimeRfc3339>,
+    /// Exclusive upper bound on `transaction_time`.
+    pub to: Option<TimeRfc3339>,
+    pub tag: Option<String>,
+    pub payment_method: Option<PaymentMethod>,
+    pub bill_id: Option<String>,
+    pub seller_id: Option<String>,
+    /// Case insensitive substring of the transaction name.
+    pub name: Option<String>,
+    pub min_cost: Option<BigDecimal>,
+    pub max_cost: Option<BigDecimal>,
+    pub kind: Option<TransactionKind>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, strum_macros::AsRefStr)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum TransactionSortBy {
+    #[default]
+    TransactionTime,
+    Name,
+    SellerCost,
+    PaymentMethod,
+    BillId,
+    SellerName,
+}
+
+impl TransactionSortBy {
+    /// Sql sort expression (never null) and its type for the cursor cast.
+    fn sql_expr_and_type(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::TransactionTime => ("tr.transaction_time", "timestamptz"),
+            Self::Name => ("tr.name", "text"),
+            Self::SellerCost => ("tr.seller_cost", "numeric"),
+            Self::PaymentMethod => ("pm.name", "text"),
+            Self::BillId => ("coalesce(bl.bill_id, '')", "text"),
+            Self::SellerName => ("coalesce(sl.name, '')", "text"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionListOptions {
+    #[serde(default)]
+    pub sort_by: TransactionSortBy,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TransactionRow {
+    serial_id: i64,
     transaction_id: String,
     name: String,
-    transaction_time: TimeRfc3339,
     remark: Option<String>,
-    tags: Vec<String>,
-    payment_method: PaymentMethod,
-    unit_cost: Option<UnitCost>,
+    transaction_time: TimeRfc3339,
+    payment_method_name: String,
     seller_cost: BigDecimal,
+    currency_code: String,
+    tax_rate: Option<BigDecimal>,
+    inter_state: bool,
+    taxable_value: Option<BigDecimal>,
+    cgst: Option<BigDecimal>,
+    sgst: Option<BigDecimal>,
+    igst: Option<BigDecimal>,
+    transaction_kind_name: String,
+    original_transaction_id: Option<String>,
     bill_id: Option<String>,
 }
 
-pub struct TransactionBmc;
+impl TransactionRow {
+    fn tax(&self) -> Option<TransactionTax> {
+        let rate = self.tax_rate.clone()?;
+        let taxable_value = self.taxable_value.clone().unwrap_or_default();
+        let cgst = self.cgst.clone().unwrap_or_default();
+        let sgst = self.sgst.clone().unwrap_or_default();
+        let igst = self.igst.clone().unwrap_or_default();
+
+        Some(TransactionTax {
+            round_off: &self.seller_cost
+                - &taxable_value
+                - &cgst
+                - &sgst
+                - &igst,
+            rate,
+            supply: GstSupply::from_inter_state(self.inter_state),
+            taxable_value,
+            cgst,
+            sgst,
+            igst,
+        })
+    }
+}
 
-impl TransactionBmc {
-    fn generate_transaction_id() -> String {
-        let mut key = [0u8; 64]; // 512 bits = 64 bytes
-        rand::rng().fill_bytes(&mut key);
-        b58_encode(key)
-            .chars()
-            .take(20)
-            .collect::<String>()
-            .to_uppercase()
+#[derive(sqlx::FromRow)]
+struct TransactionListRow {
+    #[sqlx(flatten)]
+    row: TransactionRow,
+    sort_key: String,
+}
+
+/// Default tax rates of the tags (of `ctx`) and unit types, by name.
+struct DefaultTaxRates {
+    tags: HashMap<String, BigDecimal>,
+    unit_types: HashMap<String, BigDecimal>,
+}
+
+impl DefaultTaxRates {
+    async fn load(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        tags: &[String],
+        unit_types: &[String],
+    ) -> Result<Self> {
+        let sqlx_query = sqlx::query_as::<_, (bool, String, BigDecimal)>(
+            "select false, name, tax_rate from tag
+            where name = any($1) and tax_rate is not null
+              and org_serial_id = org_serial_id($3)
+            union all
+            select true, name, tax_rate from unit_type
+            where name = any($2) and tax_rate is not null;",
+        )
+        .bind(tags)
+        .bind(unit_types)
+        .bind(ctx.org_id());
+
+        let mut rates = Self {
+            tags: HashMap::new(),
+            unit_types: HashMap::new(),
+        };
+        for (is_unit_type, name, rate) in mm.dbx().fetch_all(sqlx_query).await?
+        {
+            if is_unit_type {
+                rates.unit_types.insert(name, rate);
+            } else {
+                rates.tags.insert(name, rate);
+            }
+        }
+
+        Ok(rates)
+    }
+
+    /// The rate of the unit type, else the one rate of the tags.
+    fn resolve<'a>(
+        &self,
+        name: &str,
+        tags: impl Iterator<Item = &'a String>,
+        unit_cost: Option<&UnitCost>,
+    ) -> Result<Option<BigDecimal>> {
+        if let Some(rate) =
+            unit_cost.and_then(|v| self.unit_types.get(v.unit_type.trim()))
+        {
+            return Ok(Some(rate.clone()));
+        }
+
+        let mut tag_rates = tags
+            .filter_map(|v| {
+                let tag = v.trim().to_lowercase();
+                self.tags.get(&tag).map(|rate| (tag, rate))
+            })
+            .collect::<Vec<(String, &BigDecimal)>>();
+        tag_rates.sort();
+        tag_rates.dedup();
+
+        match tag_rates.as_slice() {
+            [] => Ok(None),
+            [(_, rate), rest @ ..] if rest.iter().all(|v| v.1 == *rate) => {
+                Ok(Some((*rate).clone()))
+            }
+            _ => Err(Error::TransactionTaxRateAmbiguous {
+                name: name.to_string(),
+                tags: tag_rates.into_iter().map(|v| v.0).collect(),
+            }),
+        }
+    }
+}
+
+/// Tax columns of the transactions, bound as arrays.
+struct TaxColumns {
+    rates: Vec<Option<BigDecimal>>,
+    inter_states: Vec<bool>,
+    taxable_values: Vec<Option<BigDecimal>>,
+    cgsts: Vec<Option<BigDecimal>>,
+    sgsts: Vec<Option<BigDecimal>>,
+    igsts: Vec<Option<BigDecimal>>,
+}
+
+impl From<Vec<Option<TransactionTax>>> for TaxColumns {
+    fn from(taxes: Vec<Option<TransactionTax>>) -> Self {
+        let mut columns = Self {
+            rates: Vec::with_capacity(taxes.len()),
+            inter_states: Vec::with_capacity(taxes.len()),
+            taxable_values: Vec::with_capacity(taxes.len()),
+            cgsts: Vec::with_capacity(taxes.len()),
+            sgsts: Vec::with_capacity(taxes.len()),
+            igsts: Vec::with_capacity(taxes.len()),
+        };
+        for tax in taxes {
+            columns
+                .inter_states
+                .push(tax.as_ref().is_some_and(|v| v.supply.is_inter_state()));
+            columns.rates.push(tax.as_ref().map(|v| v.rate.clone()));
+            columns
+                .taxable_values
+                .push(tax.as_ref().map(|v| v.taxable_value.clone()));
+            columns.cgsts.push(tax.as_ref().map(|v| v.cgst.clone()));
+            columns.sgsts.push(tax.as_ref().map(|v| v.sgst.clone()));
+            columns.igsts.push(tax.map(|v| v.igst));
+        }
+        columns
     }
+}
+
+/// Public `transaction_id` of new transactions.
+const TRANSACTION_ID: IdSpec = IdSpec::new("T", 20);
 
+pub struct TransactionBmc;
+
+impl TransactionBmc {
+    /// Create the transactions with a fixed number of statements, whatever
+    /// their count. They are returned in input order.
     pub async fn create(
+        ctx: &Ctx,
         mm: &ModelManager,
         transaction_c: Vec<TransactionForCreate>,
     ) -> Result<Vec<Transaction>> {
+        acs::require(ctx, mm, Privilege::TransactionCreate).await?;
+
+        transaction_c.validate()?;
+
         let now = TimeRfc3339::now_utc().inner();
 
+        for transaction in transaction_c.iter() {
+            if let Some(unit_cost) = transaction.unit_cost.as_ref() {
+                unit_cost.check_seller_cost(&transaction.seller_cost)?;
+            }
+        }
+
         let tags = transaction_c
             .iter()
-            .filter_map(|v| v.tags.as_ref())
-            .flatten()
+            .flat_map(|v| {
+                let allocated = v.allocations.iter().flatten().map(|v| &v.tag);
+                v.tags.iter().flatten().chain(allocated)
+            })
             .map(|v| v.trim().to_lowercase())
             .collect::<HashSet<String>>()
             .into_iter()
             .collect::<Vec<String>>();
 
-        let unit_types = transaction_c
+        let per_unit_costs = transaction_c
             .iter()
             .filter_map(|v| v.unit_cost.as_ref())
-            .map(|v| v.unit_type.trim())
-            .collect::<HashSet<&str>>()
-            .into_iter()
-            .collect::<Vec<&str>>();
+            .map(|v| (v.unit_type.trim(), v.cost_per_unit.clone()))
+            .collect::<Vec<(&str, BigDecimal)>>();
 
-        let per_unit_costs = transaction_c
+        let bill_ids = transaction_c
             .iter()
-            .filter_map(|v| v.unit_cost.as_ref())
-            .map(|v| (v.unit_type.trim(), v.cost_per_unit.clone()));
+            .filter_map(|v| v.bill_id.as_deref())
+            .collect::<HashSet<&str>>();
+
+        let original_ids = transaction_c
+            .iter()
+            .filter_map(|v| v.original_transaction_id.clone())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect::<Vec<String>>();
 
         // Start the transaction
         let mm = mm.new_with_txn();
         mm.dbx().begin_txn().await?;
 
-        // region:    --- Insert tags
-        let sqlx_query = sqlx::query_as::<_, (String,)>(
-            "select name from unnest($1::text[]) as t(name)
-            where t.name not in (select name from tag);",
-        )
-        .bind(&tags);
-
-        let new_tags: Vec<String> = mm
-            .dbx()
-            .fetch_all(sqlx_query)
-            .await?
-            .into_iter()
-            .map(|v| v.0)
-            .collect();
-
-        let ctimes = vec![now; new_tags.len()];
-        let mtimes = vec![now; new_tags.len()];
-
-        let sqlx_query = sqlx::query(
-            "insert into tag (name, ctime, mtime)
-            select * from unnest(
-                $1::text[],
-                $2::timestamptz[],
-                $3::timestamptz[]
-            ) as t(name, ctime, mtime)
-            on conflict (name) do nothing;",
-        )
-        .bind(new_tags)
-        .bind(ctimes)
-        .bind(mtimes);
-
-        mm.dbx().execute(sqlx_query).await?;
-
-        // endregion: --- Insert tags
-
-        // region:    --- Insert unit_type
-
-        let sqlx_query = sqlx::query_as::<_, (String,)>(
-            "select name from unnest($1::text[]) as t(name)
-            where t.name not in (select name from unit_type);",
-        )
-        .bind(&unit_types);
-
-        let new_unit_types: Vec<String> = mm
-            .dbx()
-            .fetch_all(sqlx_query)
-            .await?
-            .into_iter()
-            .map(|v| v.0)
-            .collect();
-
-        let ctimes = vec![now; new_unit_types.len()];
-        let mtimes = vec![now; new_unit_types.len()];
-
-        let sqlx_query = sqlx::query(
-            "insert into unit_type (name, ctime, mtime)
-            select * from unnest(
-                $1::text[],
-                $2::timestamptz[],
-                $3::timestamptz[]
-            ) as t(name, ctime, mtime)
-            on conflict (name) do nothing;",
-        )
-        .bind(new_unit_types)
-        .bind(ctimes)
-        .bind(mtimes);
-
-        mm.dbx().execute(sqlx_query).await?;
-        // endregion: --- Insert unit_type
-
-        // region:    --- Insert unit_cost
-
-        let (unit_types, costs_per_unit): (Vec<&str>, Vec<BigDecimal>) =
-            per_unit_costs.unzip();
-
-        let sqlx_query = sqlx::query_as::<_, (String, BigDecimal)>(
-            "select unit_type, cost_per_unit from unnest(
-                $1::text[],
-                $2::numeric(12, 2)[]
-        	 ) as t(unit_type, cost_per_unit)
-            where 
-            (t.unit_type, t.cost_per_unit)
-            not in (
-                select unit_type, cost_per_unit from unit_cost uc
-                inner join unit_type ut
-                on uc.unit_type_serial_id = ut.serial_id
-            );",
-        )
-        .bind(unit_types)
-        .bind(costs_per_unit);
-
-        let (new_unit_types, new_unit_types_costs): (
-            Vec<String>,
-            Vec<BigDecimal>,
-        ) = mm.dbx().fetch_all(sqlx_query).await?.into_iter().unzip();
-
-        let sqlx_query = sqlx::query(
-            "insert into unit_cost (unit_type_serial_id, cost_per_unit) 
-                select ut.serial_id, t.cost_per_unit from
-                unnest(
-                    $1::text[],
-                    $2::float8[]
-            	) as t(unit_type, cost_per_unit) inner join unit_type ut on t.unit_type = ut.name;",
-        )
-        .bind(new_unit_types)
-        .bind(new_unit_types_costs);
+        for bill_id in bill_ids {
+            BillBmc::ensure_editable(ctx, &mm, bill_id).await?;
+        }
 
-        mm.dbx().execute(sqlx_query).await?;
-        // endregion: --- Insert unit_cost
+        let original_currencies =
+            Self::lock_originals(ctx, &mm, &original_ids).await?;
 
-        let mut completed_transaction_ids: Vec<String> =
-            Vec::with_capacity(transaction_c.len());
+        let unit_types = per_unit_costs
+            .iter()
+            .map(|v| v.0.to_string())
+            .collect::<Vec<String>>();
 
-        for transaction in transaction_c {
+        Self::insert_tags(ctx, &mm, &tags, now).await?;
+        Self::insert_unit_costs(&mm, per_unit_costs, now).await?;
+
+        let default_tax_rates =
+            DefaultTaxRates::load(ctx, &mm, &tags, &unit_types).await?;
+        let tax_rounding = core_config().TAX_ROUNDING;
+
+        // region:    --- Insert transaction
+
+        let count = transaction_c.len();
+        let mut names = Vec::with_capacity(count);
+        let mut remarks = Vec::with_capacity(count);
+        let mut transaction_times = Vec::with_capacity(count);
+        let mut payment_methods = Vec::with_capacity(count);
+        let mut seller_costs = Vec::with_capacity(count);
+        let mut currencies = Vec::with_capacity(count);
+        let mut taxes = Vec::with_capacity(count);
+        let mut kinds = Vec::with_capacity(count);
+        let mut transaction_original_ids = Vec::with_capacity(count);
+        let mut transaction_bill_ids = Vec::with_capacity(count);
+        // Tags and unit costs by the index of their transaction.
+        let mut transaction_tags = Vec::new();
+        let mut transaction_units = Vec::new();
+
+        for (idx, transaction) in transaction_c.into_iter().enumerate() {
             let TransactionForCreate {
                 name,
                 transaction_time,
                 remark,
                 tags,
+                allocations,
                 payment_method,
                 unit_cost,
                 seller_cost,
+                currency,
+                tax_rate,
+                gst_supply,
+                kind,
+                original_transaction_id,
                 bill_id,
             } = transaction;
 
-            let sqlx_query = sqlx::query_as::<_, (String, i64)>(
-                "insert into transaction (transaction_id, name, remark, transaction_time, payment_method_serial_id, seller_cost, bill_serial_id) values
-                ($1, $2, $3, $4, (select serial_id from payment_method p where p.name = $5 limit 1), $6,
-                (select serial_id from bill b where b.bill_id = $7 limit 1) )
-                returning transaction_id, serial_id;",
-            )
-            .bind(TransactionBmc::generate_transaction_id())
-            .bind(name)
-            .bind(remark)
-            .bind(transaction_time.map(|v| v.inner()).unwrap_or(now))
-            .bind(payment_method.to_string())
-            .bind(seller_cost)
-            .bind(bill_id);
-
-            let (transaction_id, transaction_serial_id) =
-                mm.dbx().fetch_one(sqlx_query).await?;
-
-            println!("transaction id: {}", transaction_id);
-            completed_transaction_ids.push(transaction_id);
-
-            if let Some(tags) = tags {
-                for tag in tags {
-                    let sqlx_query = sqlx::query(
-                    "insert into transaction_tag (transaction_serial_id, tag_serial_id) values
-                    ($1, (select serial_id from tag t where t.name = $2 limit 1))",
-                )
-                .bind(transaction_serial_id)
-                .bind(tag.trim().to_lowercase());
-
-                    mm.dbx().execute(sqlx_query).await?;
-                }
+            if let Some(original_transaction_id) =
+                original_transaction_id.as_deref()
+                && original_currencies.get(original_transaction_id)
+                    != Some(&currency)
+            {
+                return Err(Error::TransactionOriginalInvalid {
+                    transaction_id: original_transaction_id.to_string(),
+                    reason: "is in another currency",
+                });
             }
 
-            if let Some(unit) = unit_cost {
-                let sqlx_query = sqlx::query(
-                    "insert into transaction_unit (transaction_serial_id, unit,unit_cost_serial_id) values
-                    ($1, $2,
-                    (select uc.serial_id from unit_cost uc where
-                    (uc.unit_type_serial_id, uc.cost_per_unit) <> ((select ut.serial_id from unit_type ut where ut.name = $3), $4) limit 1))",
+            let allocations = allocate(&seller_cost, tags, allocations);
+
+            let tax_rate = match tax_rate {
+                Some(tax_rate) => Some(tax_rate),
+                None => default_tax_rates.resolve(
+                    &name,
+                    allocations.iter().map(|v| &v.tag),
+                    unit_cost.as_ref(),
+                )?,
+            };
+            taxes.push(tax_rate.map(|rate| {
+                TransactionTax::compute(
+                    &seller_cost,
+                    rate,
+                    gst_supply,
+                    &tax_rounding,
                 )
-                .bind(transaction_serial_id)
-                .bind(unit.unit)
-                .bind(unit.unit_type.trim())
-                .bind(unit.cost_per_unit);
-
-                mm.dbx().execute(sqlx_query).await?;
+            }));
+
+            names.push(name);
+            remarks.push(remark);
+            transaction_times
+                .push(transaction_time.map(|v| v.inner()).unwrap_or(now));
+            payment_methods.push(payment_method.to_string());
+            seller_costs.push(seller_cost);
+            currencies.push(currency.code());
+            kinds.push(kind.to_string());
+            transaction_original_ids.push(original_transaction_id);
+            transaction_bill_ids.push(bill_id);
+            for allocation in allocations {
+                transaction_tags.push((idx, allocation));
+            }
+            if let Some(unit_cost) = unit_cost {
+                transaction_units.push((idx, unit_cost));
             }
         }
 
+        // NOTE: Bound by reference, so that every attempt can bind them.
+        let (names, remarks, transaction_times) =
+            (&names, &remarks, &transaction_times);
+        let (payment_methods, seller_costs, currencies) =
+            (&payment_methods, &seller_costs, &currencies);
+        let transaction_bill_ids = &transaction_bill_ids;
+        let (kinds, transaction_original_ids) =
+            (&kinds, &transaction_original_ids);
+        let org_id = ctx.org_id();
+        let taxes = TaxColumns::from(taxes);
+        let taxes = &taxes;
+
+        let dbx = mm.dbx();
+        let (transaction_ids, serial_ids) = dbx
+            .retry_on_id_collision("transaction_transaction_id_key", || async move {
+                let transaction_ids = (0..count)
+                    .map(|_| TRANSACTION_ID.generate())
+                    .collect::<Vec<String>>();
+
+                let sqlx_query = sqlx::query_as::<_, (String, i64)>(
+                    "insert into transaction (transaction_id, name, remark, transaction_time, payment_method_serial_id, seller_cost, currency_serial_id, bill_serial_id, tax_rate, inter_state, taxable_value, cgst, sgst, igst, transaction_kind_serial_id, original_transaction_serial_id, org_serial_id)
+                    select t.transaction_id, t.name, t.remark, t.transaction_time,
+                        (select serial_id from payment_method p where p.name = t.payment_method limit 1),
+                        t.seller_cost,
+                        (select serial_id from currency c where c.code = t.currency),
+                        (select serial_id from bill b where b.bill_id = t.bill_id limit 1),
+                        t.tax_rate, t.inter_state, t.taxable_value, t.cgst, t.sgst, t.igst,
+                        (select serial_id from transaction_kind k where k.name = t.kind),
+                        (select serial_id from transaction o where o.transaction_id = t.original_transaction_id),
+                        org_serial_id($17)
+                    from unnest(
+                        $1::text[],
+                        $2::text[],
+                        $3::text[],
+                        $4::timestamptz[],
+                        $5::text[],
+                        $6::numeric[],
+                        $7::text[],
+                        $8::text[],
+                        $9::numeric[],
+                        $10::bool[],
+                        $11::numeric[],
+                        $12::numeric[],
+                        $13::numeric[],
+                        $14::numeric[],
+                        $15::text[],
+                        $16::text[]
+                    ) as t(transaction_id, name, remark, transaction_time, payment_method, seller_cost, currency, bill_id, tax_rate, inter_state, taxable_value, cgst, sgst, igst, kind, original_transaction_id)
+                    returning transaction_id, serial_id;",
+                )
+                .bind(&transaction_ids)
+                .bind(names)
+                .bind(remarks)
+                .bind(transaction_times)
+                .bind(payment_methods)
+                .bind(seller_costs)
+                .bind(currencies)
+                .bind(transaction_bill_ids)
+                .bind(&taxes.rates)
+                .bind(&taxes.inter_states)
+                .bind(&taxes.taxable_values)
+                .bind(&taxes.cgsts)
+                .bind(&taxes.sgsts)
+                .bind(&taxes.igsts)
+                .bind(kinds)
+                .bind(transaction_original_ids)
+                .bind(org_id);
+
+                let serial_ids = dbx.fetch_all(sqlx_query).await?;
+
+                Ok((transaction_ids, serial_ids))
+            })
+            .await?;
+        let serial_ids =
+            serial_ids.into_iter().collect::<HashMap<String, i64>>();
+        let serial_ids = transaction_ids
+            .iter()
+            .map(|v| serial_ids.get(v).copied())
+            .collect::<Option<Vec<i64>>>()
+            .ok_or(Error::TransactionNotUnique)?;
+        // endregion: --- Insert transaction
+
+        // region:    --- Insert transaction_tag
+
+        Self::insert_transaction_tags(
+            ctx,
+            &mm,
+            transaction_tags
+                .into_iter()
+                .map(|(idx, allocation)| (serial_ids[idx], allocation))
+                .collect(),
+        )
+        .await?;
+        // endregion: --- Insert transaction_tag
+
+        Self::insert_transaction_units(
+            &mm,
+            transaction_units
+                .into_iter()
+                .map(|(idx, unit)| (serial_ids[idx], unit))
+                .collect(),
+        )
+        .await?;
+
+        Self::ensure_within_originals(&mm, &original_ids).await?;
+
         // Commit the transaction
         mm.dbx().commit_txn().await?;
 
         let transactions =
-            Self::get_by_transaction_ids(&mm, completed_transaction_ids)
-                .await?;
+            Self::fetch_by_transaction_ids(ctx, &mm, transaction_ids).await?;
 
         Ok(transactions)
     }
 
-    pub async fn get_by_transaction_ids(
+    pub async fn update(
+        ctx: &Ctx,
         mm: &ModelManager,
-        transaction_ids: Vec<String>,
-    ) -> Result<Vec<Transaction>> {
-        #[derive(sqlx::FromRow)]
-        struct TransactionTable {
-            serial_id: i64,
-            transaction_id: String,
-            name: String,
-            remark: Option<String>,
-            transaction_time: TimeRfc3339,
-            payment_method_name: String,
-            seller_cost: BigDecimal,
-            bill_id: Option<String>,
-        }
+        transaction_id: &str,
+        transaction_u: TransactionForUpdate,
+    ) -> Result<Transaction> {
+        acs::require(ctx, mm, Privilege::TransactionUpdate).await?;
+
+        transaction_u.validate()?;
+
+        let TransactionForUpdate {
+            name,
+            transaction_time,
+            remark,
+            tags,
+            allocations,
+            payment_method,
+            unit_cost,
+            seller_cost,
+            currency,
+            tax_rate,
+            gst_supply,
+            bill_id,
+        } = transaction_u;
 
-        #[derive(sqlx::FromRow)]
-        struct TransactionTagsTable {
-            transaction_serial_id: i64,
-            tag_name: String,
-        }
+        let now = TimeRfc3339::now_utc().inner();
 
-        #[derive(Debug, sqlx::FromRow)]
-        struct TransactionUnitTable {
-            transaction_serial_id: i64,
-            unit: BigDecimal,
-            cost_per_unit: BigDecimal,
-            unit_type_name: String,
-        }
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
 
-        let sqlx_query = sqlx::query_as::<_, TransactionTable>(
+        // -- Lock and load the current row.
+        let sqlx_query = sqlx::query_as::<_, TransactionRow>(
             "select tr.serial_id, tr.transaction_id, tr.name,
                 tr.remark, tr.transaction_time,
                 pm.name as payment_method_name,
-                tr.seller_cost,
+                tr.seller_cost, cu.code as currency_code,
+                tr.tax_rate, tr.inter_state, tr.taxable_value,
+                tr.cgst, tr.sgst, tr.igst,
+                tk.name as transaction_kind_name,
+                otr.transaction_id as original_transaction_id,
                 bl.bill_id from transaction tr
             inner join payment_method pm
                 on pm.serial_id = tr.payment_method_serial_id
+            inner join currency cu
+                on cu.serial_id = tr.currency_serial_id
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            left join transaction otr
+                on otr.serial_id = tr.original_transaction_serial_id
             left join bill bl
                 on bl.serial_id = tr.bill_serial_id
-            where transaction_id in (select unnest($1::text[]));
-           ",
+            where tr.transaction_id = $1
+              and tr.org_serial_id = org_serial_id($2)
+            for update of tr;",
         )
-        .bind(&transaction_ids);
+        .bind(transaction_id)
+        .bind(ctx.org_id());
+
+        let current = mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+            Error::TransactionNotFound {
+                transaction_id: transaction_id.to_string(),
+            },
+        )?;
+        let original_transaction_id = current.original_transaction_id.clone();
+
+        // -- Both the current and the target bill must be editable.
+        if let Some(bill_id) = current.bill_id.as_deref() {
+            BillBmc::ensure_editable(ctx, &mm, bill_id).await?;
+        }
+        if let Some(Some(bill_id)) = bill_id.as_ref() {
+            BillBmc::ensure_editable(ctx, &mm, bill_id).await?;
+        }
 
-        let transactions = mm.dbx().fetch_all(sqlx_query).await?;
-        let transaction_serial_ids = transactions
-            .iter()
-            .map(|v| v.serial_id)
-            .collect::<Vec<i64>>();
+        // -- Only an adjustment may have a negative seller cost.
+        let kind = TransactionKind::from_str(&current.transaction_kind_name)
+            .unwrap_or_default();
+        if let Some(seller_cost) = seller_cost.as_ref()
+            && kind != TransactionKind::Adjustment
+        {
+            let mut validator = Validator::default();
+            validator.amount("", "sellerCost", seller_cost);
+            validator.finish()?;
+        }
 
-        let sqlx_query = sqlx::query_as::<_, TransactionTagsTable>(
-            "select tr.transaction_serial_id, tg.name as tag_name from transaction_tag tr
+        // -- The resulting unit cost must still match the seller cost.
+        let unit_cost_to_check = match unit_cost.as_ref() {
+            Some(unit_cost) => unit_cost.clone(),
+            None if seller_cost.is_some() => {
+                Self::get_unit_cost(&mm, current.serial_id).await?
+            }
+            None => None,
+        };
+        if let Some(unit_cost_to_check) = unit_cost_to_check {
+            unit_cost_to_check.check_seller_cost(
+                seller_cost.as_ref().unwrap_or(&current.seller_cost),
+            )?;
+        }
+
+        // -- The tax and the allocations follow the resulting seller cost.
+        let seller_cost = seller_cost.unwrap_or(current.seller_cost);
+        if let Some(allocations) = allocations.as_deref() {
+            let mut validator = Validator::default();
+            validate_allocations("", allocations, &seller_cost, &mut validator);
+            validator.finish()?;
+        }
+        let gst_supply = gst_supply
+            .unwrap_or(GstSupply::from_inter_state(current.inter_state));
+        let tax = tax_rate.unwrap_or(current.tax_rate).map(|rate| {
+            TransactionTax::compute(
+                &seller_cost,
+                rate,
+                gst_supply,
+                &core_config().TAX_ROUNDING,
+            )
+        });
+        let tax = TaxColumns::from(vec![tax]);
+
+        // region:    --- Update transaction
+        let sqlx_query = sqlx::query(
+            "update transaction set
+                name = $2,
+                remark = $3,
+                transaction_time = $4,
+                payment_method_serial_id = (select serial_id from payment_method p where p.name = $5 limit 1),
+                seller_cost = $6,
+                currency_serial_id = (select serial_id from currency c where c.code = $9),
+                bill_serial_id = (select serial_id from bill b where b.bill_id = $7 limit 1),
+                mtime = $8,
+                tax_rate = $10,
+                inter_state = $11,
+                taxable_value = $12,
+                cgst = $13,
+                sgst = $14,
+                igst = $15
+            where serial_id = $1;",
+        )
+        .bind(current.serial_id)
+        .bind(name.unwrap_or(current.name))
+        .bind(remark.unwrap_or(current.remark))
+        .bind(
+            transaction_time
+                .map(|v| v.inner())
+                .unwrap_or(current.transaction_time.inner()),
+        )
+        .bind(
+            payment_method
+                .map(|v| v.to_string())
+                .unwrap_or(current.payment_method_name),
+        )
+        .bind(&seller_cost)
+        .bind(bill_id.unwrap_or(current.bill_id))
+        .bind(now)
+        .bind(
+            currency
+                .map(|v| v.code().to_string())
+                .unwrap_or(current.currency_code),
+        )
+        .bind(&tax.rates[0])
+        .bind(tax.inter_states[0])
+        .bind(&tax.taxable_values[0])
+        .bind(&tax.cgsts[0])
+        .bind(&tax.sgsts[0])
+        .bind(&tax.igsts[0]);
+
+        mm.dbx().execute(sqlx_query).await?;
+        // endregion: --- Update transaction
+
+        // region:    --- Rewrite transaction_tag
+        if tags.is_some() || allocations.is_some() {
+            let allocations = allocate(&seller_cost, tags, allocations);
+            let tags = allocations
+                .iter()
+                .map(|v| v.tag.clone())
+                .collect::<Vec<String>>();
+
+            Self::insert_tags(ctx, &mm, &tags, now).await?;
+
+            let sqlx_query = sqlx::query(
+                "delete from transaction_tag where transaction_serial_id = $1;",
+            )
+            .bind(current.serial_id);
+
+            mm.dbx().execute(sqlx_query).await?;
+
+            Self::insert_transaction_tags(
+                ctx,
+                &mm,
+                allocations
+                    .into_iter()
+                    .map(|v| (current.serial_id, v))
+                    .collect(),
+            )
+            .await?;
+        } else {
+            Self::rebalance_allocations(&mm, &[current.serial_id]).await?;
+        }
+        // endregion: --- Rewrite transaction_tag
+
+        // region:    --- Rewrite transaction_unit
+        if let Some(unit_cost) = unit_cost {
+            let sqlx_query = sqlx::query(
+                "delete from transaction_unit where transaction_serial_id = $1;",
+            )
+            .bind(current.serial_id);
+
+            mm.dbx().execute(sqlx_query).await?;
+
+            if let Some(unit) = unit_cost {
+                Self::insert_unit_costs(
+                    &mm,
+                    vec![(unit.unit_type.trim(), unit.cost_per_unit.clone())],
+                    now,
+                )
+                .await?;
+
+                Self::insert_transaction_units(
+                    &mm,
+                    vec![(current.serial_id, unit)],
+                )
+                .await?;
+            }
+        }
+        // endregion: --- Rewrite transaction_unit
+
+        // -- The credits of the purchase, or of its original, must still fit.
+        let mut linked_ids = Vec::from_iter(original_transaction_id);
+        let original_currencies =
+            Self::lock_originals(ctx, &mm, &linked_ids).await?;
+        if let Some(currency) = currency {
+            if let Some(original_transaction_id) = linked_ids.first()
+                && original_currencies.get(original_transaction_id)
+                    != Some(&currency)
+            {
+                return Err(Error::TransactionOriginalInvalid {
+                    transaction_id: original_transaction_id.to_string(),
+                    reason: "is in another currency",
+                });
+            }
+            if kind == TransactionKind::Purchase {
+                Self::ensure_credits_in_currency(
+                    &mm,
+                    current.serial_id,
+                    currency,
+                )
+                .await?;
+            }
+        }
+        linked_ids.push(transaction_id.to_string());
+        Self::ensure_within_originals(&mm, &linked_ids).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Self::fetch_by_transaction_ids(
+            ctx,
+            &mm,
+            vec![transaction_id.to_string()],
+        )
+        .await?
+        .pop()
+        .ok_or(Error::TransactionNotFound {
+            transaction_id: transaction_id.to_string(),
+        })
+    }
+
+    /// Delete the transaction, its `transaction_tag` and `transaction_unit`
+    /// rows go with it (on delete cascade). A purchase with linked refunds,
+    /// returns, etc. is kept.
+    pub async fn delete(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        transaction_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::TransactionDelete).await?;
+
+        // Start the transaction
+        let mm = mm.new_with_txn();
+        mm.dbx().begin_txn().await?;
+
+        let sqlx_query = sqlx::query_as::<_, (Option<String>, bool)>(
+            "select bl.bill_id, exists (
+                select 1 from transaction c
+                where c.original_transaction_serial_id = tr.serial_id)
+            from transaction tr
+            left join bill bl on bl.serial_id = tr.bill_serial_id
+            where tr.transaction_id = $1
+              and tr.org_serial_id = org_serial_id($2)
+            for update of tr;",
+        )
+        .bind(transaction_id)
+        .bind(ctx.org_id());
+
+        let (bill_id, has_linked) =
+            mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+                Error::TransactionNotFound {
+                    transaction_id: transaction_id.to_string(),
+                },
+            )?;
+
+        if has_linked {
+            return Err(Error::TransactionHasLinked {
+                transaction_id: transaction_id.to_string(),
+            });
+        }
+
+        if let Some(bill_id) = bill_id.as_deref() {
+            BillBmc::ensure_editable(ctx, &mm, bill_id).await?;
+        }
+
+        let sqlx_query =
+            sqlx::query("delete from transaction where transaction_id = $1;")
+                .bind(transaction_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        // Commit the transaction
+        mm.dbx().commit_txn().await?;
+
+        Ok(())
+    }
+
+    /// Lock the purchases linked to by new transactions, returning their
+    /// currency by `transaction_id`.
+    async fn lock_originals(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        transaction_ids: &[String],
+    ) -> Result<HashMap<String, Currency>> {
+        let sqlx_query = sqlx::query_as::<_, (String, String, String)>(
+            "select tr.transaction_id, tk.name, cu.code from transaction tr
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            inner join currency cu on cu.serial_id = tr.currency_serial_id
+            where tr.transaction_id = any($1)
+              and tr.org_serial_id = org_serial_id($2)
+            for update of tr;",
+        )
+        .bind(transaction_ids)
+        .bind(ctx.org_id());
+
+        let mut originals = HashMap::new();
+        for (transaction_id, kind, currency) in
+            mm.dbx().fetch_all(sqlx_query).await?
+        {
+            if kind != TransactionKind::Purchase.to_string() {
+                return Err(Error::TransactionOriginalInvalid {
+                    transaction_id,
+                    reason: "is not a purchase",
+                });
+            }
+            originals.insert(
+                transaction_id,
+                Currency::from_str(&currency).unwrap_or_default(),
+            );
+        }
+
+        if let Some(transaction_id) =
+            transaction_ids.iter().find(|v| !originals.contains_key(*v))
+        {
+            return Err(Error::TransactionOriginalNotFound {
+                transaction_id: transaction_id.to_string(),
+            });
+        }
+
+        Ok(originals)
+    }
+
+    /// Fail when a refund, return or discount of the purchase `serial_id` is
+    /// not in `currency`, e.g. once the currency of the purchase changed.
+    async fn ensure_credits_in_currency(
+        mm: &ModelManager,
+        serial_id: i64,
+        currency: Currency,
+    ) -> Result<()> {
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select o.transaction_id from transaction o
+                inner join transaction c
+                    on c.original_transaction_serial_id = o.serial_id
+                inner join currency cu on cu.serial_id = c.currency_serial_id
+                where o.serial_id = $1 and cu.code <> $2
+                limit 1;",
+        )
+        .bind(serial_id)
+        .bind(currency.code());
+
+        match mm.dbx().fetch_optional(sqlx_query).await? {
+            Some((transaction_id,)) => Err(Error::TransactionOriginalInvalid {
+                transaction_id,
+                reason: "has credits in another currency",
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail when the refunds, returns and discounts of one of the purchases
+    /// `transaction_ids` add up to more than its seller cost.
+    async fn ensure_within_originals(
+        mm: &ModelManager,
+        transaction_ids: &[String],
+    ) -> Result<()> {
+        let sqlx_query = sqlx::query_as::<_, (String, BigDecimal, BigDecimal)>(
+            "select o.transaction_id, o.seller_cost, sum(c.seller_cost)
+                from transaction o
+                inner join transaction c
+                    on c.original_transaction_serial_id = o.serial_id
+                inner join transaction_kind ck
+                    on ck.serial_id = c.transaction_kind_serial_id
+                where o.transaction_id = any($1) and ck.sign < 0
+                group by o.serial_id, o.transaction_id, o.seller_cost
+                having sum(c.seller_cost) > o.seller_cost
+                limit 1;",
+        )
+        .bind(transaction_ids);
+
+        match mm.dbx().fetch_optional(sqlx_query).await? {
+            Some((transaction_id, seller_cost, credited)) => {
+                Err(Error::TransactionExceedsOriginal {
+                    transaction_id,
+                    seller_cost,
+                    credited,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Insert the `(transaction_serial_id, allocation)` pairs, their tags
+    /// already normalized and inserted.
+    async fn insert_transaction_tags(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        allocations: Vec<(i64, TagAllocation)>,
+    ) -> Result<()> {
+        let mut transaction_serial_ids = Vec::with_capacity(allocations.len());
+        let mut tag_names = Vec::with_capacity(allocations.len());
+        let mut amounts = Vec::with_capacity(allocations.len());
+        for (transaction_serial_id, allocation) in allocations {
+            transaction_serial_ids.push(transaction_serial_id);
+            tag_names.push(allocation.tag);
+            amounts.push(allocation.amount);
+        }
+
+        let sqlx_query = sqlx::query(
+            "insert into transaction_tag (transaction_serial_id, tag_serial_id, amount)
+            select t.transaction_serial_id, tg.serial_id, t.amount from unnest(
+                $1::bigint[],
+                $2::text[],
+                $3::numeric[]
+            ) as t(transaction_serial_id, name, amount)
             inner join tag tg
-            on tr.tag_serial_id = tg.serial_id
-            where tr.transaction_serial_id in (select unnest($1::bigint[]));
-           ",
+                on tg.name = t.name and tg.org_serial_id = org_serial_id($4);",
+        )
+        .bind(transaction_serial_ids)
+        .bind(tag_names)
+        .bind(amounts)
+        .bind(ctx.org_id());
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Scale the allocations of the (locked) transactions `serial_ids` to
+    /// their seller cost, keeping the proportions between their tags. Used
+    /// once the seller cost changed or a tag was removed.
+    pub(crate) async fn rebalance_allocations(
+        mm: &ModelManager,
+        serial_ids: &[i64],
+    ) -> Result<()> {
+        let sqlx_query =
+            sqlx::query_as::<_, (i64, BigDecimal, i64, BigDecimal)>(
+                "select tr.serial_id, tr.seller_cost, tt.tag_serial_id, tt.amount
+                from transaction_tag tt
+                inner join transaction tr
+                    on tr.serial_id = tt.transaction_serial_id
+                where tr.serial_id = any($1)
+                order by tr.serial_id, tt.tag_serial_id;",
+            )
+            .bind(serial_ids);
+
+        let mut by_transaction =
+            Vec::<(i64, BigDecimal, Vec<i64>, Vec<BigDecimal>)>::new();
+        for (serial_id, seller_cost, tag_serial_id, amount) in
+            mm.dbx().fetch_all(sqlx_query).await?
+        {
+            match by_transaction.last_mut() {
+                Some(last) if last.0 == serial_id => {
+                    last.2.push(tag_serial_id);
+                    last.3.push(amount);
+                }
+                _ => by_transaction.push((
+                    serial_id,
+                    seller_cost,
+                    vec![tag_serial_id],
+                    vec![amount],
+                )),
+            }
+        }
+
+        let mut transaction_serial_ids = Vec::new();
+        let mut tag_serial_ids = Vec::new();
+        let mut amounts = Vec::new();
+        for (serial_id, seller_cost, tags, weights) in by_transaction {
+            for (tag_serial_id, amount) in
+                tags.into_iter().zip(split(&seller_cost, &weights))
+            {
+                transaction_serial_ids.push(serial_id);
+                tag_serial_ids.push(tag_serial_id);
+                amounts.push(amount);
+            }
+        }
+
+        let sqlx_query = sqlx::query(
+            "update transaction_tag tt set amount = t.amount
+            from unnest(
+                $1::bigint[],
+                $2::bigint[],
+                $3::numeric[]
+            ) as t(transaction_serial_id, tag_serial_id, amount)
+            where tt.transaction_serial_id = t.transaction_serial_id
+              and tt.tag_serial_id = t.tag_serial_id
+              and tt.amount <> t.amount;",
+        )
+        .bind(transaction_serial_ids)
+        .bind(tag_serial_ids)
+        .bind(amounts);
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Insert the (already normalized) tags that do not exist yet.
+    async fn insert_tags(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        tags: &[String],
+        now: OffsetDateTime,
+    ) -> Result<()> {
+        // region:    --- Insert tags
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select name from unnest($1::text[]) as t(name)
+            where t.name not in (
+                select name from tag where org_serial_id = org_serial_id($2));",
+        )
+        .bind(tags)
+        .bind(ctx.org_id());
+
+        let new_tags: Vec<String> = mm
+            .dbx()
+            .fetch_all(sqlx_query)
+            .await?
+            .into_iter()
+            .map(|v| v.0)
+            .collect();
+
+        let ctimes = vec![now; new_tags.len()];
+        let mtimes = vec![now; new_tags.len()];
+
+        let sqlx_query = sqlx::query(
+            "insert into tag (org_serial_id, name, ctime, mtime)
+            select org_serial_id($4), t.* from unnest(
+                $1::text[],
+                $2::timestamptz[],
+                $3::timestamptz[]
+            ) as t(name, ctime, mtime)
+            on conflict (org_serial_id, name) do nothing;",
+        )
+        .bind(new_tags)
+        .bind(ctimes)
+        .bind(mtimes)
+        .bind(ctx.org_id());
+
+        mm.dbx().execute(sqlx_query).await?;
+
+        // endregion: --- Insert tags
+        Ok(())
+    }
+
+    /// Insert the unit types and `(unit_type, cost_per_unit)` pairs that
+    /// do not exist yet.
+    async fn insert_unit_costs(
+        mm: &ModelManager,
+        per_unit_costs: Vec<(&str, BigDecimal)>,
+        now: OffsetDateTime,
+    ) -> Result<()> {
+        let unit_types = per_unit_costs
+            .iter()
+            .map(|v| v.0)
+            .collect::<HashSet<&str>>()
+            .into_iter()
+            .collect::<Vec<&str>>();
+
+        // region:    --- Insert unit_type
+
+        let sqlx_query = sqlx::query_as::<_, (String,)>(
+            "select name from unnest($1::text[]) as t(name)
+            where t.name not in (select name from unit_type);",
+        )
+        .bind(&unit_types);
+
+        let new_unit_types: Vec<String> = mm
+            .dbx()
+            .fetch_all(sqlx_query)
+            .await?
+            .into_iter()
+            .map(|v| v.0)
+            .collect();
+
+        let ctimes = vec![now; new_unit_types.len()];
+        let mtimes = vec![now; new_unit_types.len()];
+
+        let sqlx_query = sqlx::query(
+            "insert into unit_type (name, ctime, mtime)
+            select * from unnest(
+                $1::text[],
+                $2::timestamptz[],
+                $3::timestamptz[]
+            ) as t(name, ctime, mtime)
+            on conflict (name) do nothing;",
+        )
+        .bind(new_unit_types)
+        .bind(ctimes)
+        .bind(mtimes);
+
+        mm.dbx().execute(sqlx_query).await?;
+        // endregion: --- Insert unit_type
+
+        // region:    --- Insert unit_cost
+
+        let (unit_types, costs_per_unit): (Vec<&str>, Vec<BigDecimal>) =
+            per_unit_costs.into_iter().unzip();
+
+        // NOTE: The cast rounds the costs the same way the column does, so
+        //       the same pair sent twice (e.g. `40` and `40.001`) is one row.
+        let sqlx_query = sqlx::query(
+            "insert into unit_cost (unit_type_serial_id, cost_per_unit)
+            select distinct ut.serial_id, t.cost_per_unit from unnest(
+                $1::text[],
+                $2::numeric(12, 2)[]
+            ) as t(unit_type, cost_per_unit)
+            inner join unit_type ut on ut.name = t.unit_type
+            on conflict (unit_type_serial_id, cost_per_unit) do nothing;",
+        )
+        .bind(unit_types)
+        .bind(costs_per_unit);
+
+        mm.dbx().execute(sqlx_query).await?;
+        // endregion: --- Insert unit_cost
+
+        Ok(())
+    }
+
+    /// Link transactions to the exact `(unit_type, cost_per_unit)` pairs,
+    /// which must have been inserted with `insert_unit_costs`.
+    async fn insert_transaction_units(
+        mm: &ModelManager,
+        units: Vec<(i64, UnitCost)>,
+    ) -> Result<()> {
+        let mut transaction_serial_ids = Vec::with_capacity(units.len());
+        let mut unit_values = Vec::with_capacity(units.len());
+        let mut unit_types: Vec<String> = Vec::with_capacity(units.len());
+        let mut costs_per_unit = Vec::with_capacity(units.len());
+        for (transaction_serial_id, unit) in units {
+            transaction_serial_ids.push(transaction_serial_id);
+            unit_values.push(unit.unit);
+            unit_types.push(unit.unit_type.trim().to_string());
+            costs_per_unit.push(unit.cost_per_unit);
+        }
+
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "insert into transaction_unit (transaction_serial_id, unit, unit_cost_serial_id)
+            select t.transaction_serial_id, t.unit, uc.serial_id from unnest(
+                $1::bigint[],
+                $2::numeric[],
+                $3::text[],
+                $4::numeric(12, 2)[]
+            ) as t(transaction_serial_id, unit, unit_type, cost_per_unit)
+            inner join unit_type ut on ut.name = t.unit_type
+            inner join unit_cost uc
+                on uc.unit_type_serial_id = ut.serial_id
+                and uc.cost_per_unit = t.cost_per_unit
+            returning transaction_serial_id;",
+        )
+        .bind(&transaction_serial_ids)
+        .bind(unit_values)
+        .bind(&unit_types)
+        .bind(costs_per_unit);
+
+        let inserted = mm
+            .dbx()
+            .fetch_all(sqlx_query)
+            .await?
+            .into_iter()
+            .map(|v| v.0)
+            .collect::<HashSet<i64>>();
+
+        if let Some(idx) = transaction_serial_ids
+            .iter()
+            .position(|v| !inserted.contains(v))
+        {
+            return Err(Error::TransactionUnitCostNotResolved {
+                unit_type: unit_types.swap_remove(idx),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn get_unit_cost(
+        mm: &ModelManager,
+        transaction_serial_id: i64,
+    ) -> Result<Option<UnitCost>> {
+        let sqlx_query = sqlx::query_as::<_, (String, BigDecimal, BigDecimal)>(
+            "select ut.name, tu.unit, uc.cost_per_unit from transaction_unit tu
+            inner join unit_cost uc on uc.serial_id = tu.unit_cost_serial_id
+            inner join unit_type ut on ut.serial_id = uc.unit_type_serial_id
+            where tu.transaction_serial_id = $1;",
+        )
+        .bind(transaction_serial_id);
+
+        let unit_cost = mm.dbx().fetch_optional(sqlx_query).await?.map(
+            |(unit_type, unit, cost_per_unit)| UnitCost {
+                unit_type,
+                unit,
+                cost_per_unit,
+            },
+        );
+
+        Ok(unit_cost)
+    }
+
+    /// Transactions in the order of `transaction_ids`, unknown ids are
+    /// skipped.
+    pub async fn get_by_transaction_ids(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        transaction_ids: Vec<String>,
+    ) -> Result<Vec<Transaction>> {
+        acs::require(ctx, mm, Privilege::TransactionRead).await?;
+
+        Self::fetch_by_transaction_ids(ctx, mm, transaction_ids).await
+    }
+
+    /// Unchecked, to return the created or updated transactions.
+    async fn fetch_by_transaction_ids(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        transaction_ids: Vec<String>,
+    ) -> Result<Vec<Transaction>> {
+        let sqlx_query = sqlx::query_as::<_, TransactionRow>(
+            "select tr.serial_id, tr.transaction_id, tr.name,
+                tr.remark, tr.transaction_time,
+                pm.name as payment_method_name,
+                tr.seller_cost, cu.code as currency_code,
+                tr.tax_rate, tr.inter_state, tr.taxable_value,
+                tr.cgst, tr.sgst, tr.igst,
+                tk.name as transaction_kind_name,
+                otr.transaction_id as original_transaction_id,
+                bl.bill_id from transaction tr
+            inner join payment_method pm
+                on pm.serial_id = tr.payment_method_serial_id
+            inner join currency cu
+                on cu.serial_id = tr.currency_serial_id
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            left join transaction otr
+                on otr.serial_id = tr.original_transaction_serial_id
+            left join bill bl
+                on bl.serial_id = tr.bill_serial_id
+            inner join unnest($1::text[]) with ordinality as ids(transaction_id, idx)
+                on ids.transaction_id = tr.transaction_id
+            where tr.org_serial_id = org_serial_id($2)
+            order by ids.idx;",
+        )
+        .bind(&transaction_ids)
+        .bind(ctx.org_id());
+
+        let transactions = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Self::hydrate(mm, transactions).await
+    }
+
+    /// All the transactions of a bill, oldest first.
+    pub async fn get_by_bill_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<Vec<Transaction>> {
+        acs::require(ctx, mm, Privilege::TransactionRead).await?;
+
+        Self::fetch_by_bill_id(ctx, mm, bill_id).await
+    }
+
+    /// Unchecked, for `BillBmc::get_details`.
+    pub(crate) async fn fetch_by_bill_id(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        bill_id: &str,
+    ) -> Result<Vec<Transaction>> {
+        let sqlx_query = sqlx::query_as::<_, TransactionRow>(
+            "select tr.serial_id, tr.transaction_id, tr.name,
+                tr.remark, tr.transaction_time,
+                pm.name as payment_method_name,
+                tr.seller_cost, cu.code as currency_code,
+                tr.tax_rate, tr.inter_state, tr.taxable_value,
+                tr.cgst, tr.sgst, tr.igst,
+                tk.name as transaction_kind_name,
+                otr.transaction_id as original_transaction_id,
+                bl.bill_id from transaction tr
+            inner join payment_method pm
+                on pm.serial_id = tr.payment_method_serial_id
+            inner join currency cu
+                on cu.serial_id = tr.currency_serial_id
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            left join transaction otr
+                on otr.serial_id = tr.original_transaction_serial_id
+            inner join bill bl
+                on bl.serial_id = tr.bill_serial_id
+            where bl.bill_id = $1 and tr.org_serial_id = org_serial_id($2)
+            order by tr.transaction_time, tr.serial_id;",
+        )
+        .bind(bill_id)
+        .bind(ctx.org_id());
+
+        let transactions = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Self::hydrate(mm, transactions).await
+    }
+
+    /// List transactions matching `filter`, sorted by `list_options.sort_by`
+    /// and paginated with the opaque `list_options.cursor`.
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filter: TransactionFilter,
+        list_options: TransactionListOptions,
+    ) -> Result<Page<Transaction>> {
+        acs::require(ctx, mm, Privilege::TransactionRead).await?;
+
+        let TransactionFilter {
+            from,
+            to,
+            tag,
+            payment_method,
+            bill_id,
+            seller_id,
+            name,
+            min_cost,
+            max_cost,
+            kind,
+        } = filter;
+        let TransactionListOptions {
+            sort_by,
+            order,
+            cursor,
+            limit,
+        } = list_options;
+
+        let limit = normalize_limit(limit);
+        let cursor = cursor
+            .map(|c| Cursor::decode_for(&c, sort_by.as_ref(), order))
+            .transpose()?;
+        let (cursor_key, cursor_serial_id) = cursor
+            .map(|c| (Some(c.key), Some(c.serial_id)))
+            .unwrap_or_default();
+
+        // NOTE: Only the sort column and order are interpolated, both come
+        //       from closed enums. Every value is bound.
+        let (sort_expr, sort_typ) = sort_by.sql_expr_and_type();
+        let sql = format!(
+            "select tr.serial_id, tr.transaction_id, tr.name,
+                tr.remark, tr.transaction_time,
+                pm.name as payment_method_name,
+                tr.seller_cost, cu.code as currency_code,
+                tr.tax_rate, tr.inter_state, tr.taxable_value,
+                tr.cgst, tr.sgst, tr.igst,
+                tk.name as transaction_kind_name,
+                otr.transaction_id as original_transaction_id,
+                bl.bill_id,
+                {sort_expr}::text as sort_key
+            from transaction tr
+            inner join payment_method pm
+                on pm.serial_id = tr.payment_method_serial_id
+            inner join currency cu
+                on cu.serial_id = tr.currency_serial_id
+            inner join transaction_kind tk
+                on tk.serial_id = tr.transaction_kind_serial_id
+            left join transaction otr
+                on otr.serial_id = tr.original_transaction_serial_id
+            left join bill bl
+                on bl.serial_id = tr.bill_serial_id
+            left join seller sl
+                on sl.serial_id = bl.seller_serial_id
+            where tr.org_serial_id = org_serial_id($14)
+              and ($1::timestamptz is null or tr.transaction_time >= $1)
+              and ($2::timestamptz is null or tr.transaction_time < $2)
+              and ($3::text is null or exists (
+                select 1 from transaction_tag tt
+                inner join tag tg on tg.serial_id = tt.tag_serial_id
+                where tt.transaction_serial_id = tr.serial_id
+                  and tg.name = $3))
+              and ($4::text is null or pm.name = $4)
+              and ($5::text is null or bl.bill_id = $5)
+              and ($6::text is null or sl.seller_id = $6)
+              and ($7::text is null or tr.name ilike $7)
+              and ($8::numeric is null or tr.seller_cost >= $8)
+              and ($9::numeric is null or tr.seller_cost <= $9)
+              and ($13::text is null or tk.name = $13)
+              and ($10::text is null
+                or ({sort_expr}, tr.serial_id) {seek_op} ($10::text::{sort_typ}, $11::bigint))
+            order by {sort_expr} {order}, tr.serial_id {order}
+            limit $12;",
+            seek_op = order.seek_op(),
+            order = order.as_sql(),
+        );
+
+        let sqlx_query = sqlx::query_as::<_, TransactionListRow>(&sql)
+            .bind(from.map(|v| v.inner()))
+            .bind(to.map(|v| v.inner()))
+            .bind(tag.map(|v| v.trim().to_lowercase()))
+            .bind(payment_method.map(|v| v.to_string()))
+            .bind(bill_id)
+            .bind(seller_id)
+            .bind(name.map(|v| like_contains_pattern(&v)))
+            .bind(min_cost)
+            .bind(max_cost)
+            .bind(cursor_key)
+            .bind(cursor_serial_id)
+            .bind(limit + 1)
+            .bind(kind.map(|v| v.to_string()))
+            .bind(ctx.org_id());
+
+        let mut rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().map(|v| {
+                Cursor {
+                    sort: sort_by.as_ref().to_string(),
+                    order,
+                    key: v.sort_key.clone(),
+                    serial_id: v.row.serial_id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        let rows = rows.into_iter().map(|v| v.row).collect();
+        let items = Self::hydrate(mm, rows).await?;
+
+        Ok(Page { items, next_cursor })
+    }
+
+    /// Attach tags and unit cost to the transaction rows, keeping row order.
+    async fn hydrate(
+        mm: &ModelManager,
+        transactions: Vec<TransactionRow>,
+    ) -> Result<Vec<Transaction>> {
+        #[derive(sqlx::FromRow)]
+        struct TransactionTagsTable {
+            transaction_serial_id: i64,
+            tag_name: String,
+            amount: BigDecimal,
+        }
+
+        #[derive(Debug, sqlx::FromRow)]
+        struct TransactionUnitTable {
+            transaction_serial_id: i64,
+            unit: BigDecimal,
+            cost_per_unit: BigDecimal,
+            unit_type_name: String,
+        }
+
+        let transaction_serial_ids = transactions
+            .iter()
+            .map(|v| v.serial_id)
+            .collect::<Vec<i64>>();
+
+        let sqlx_query = sqlx::query_as::<_, TransactionTagsTable>(
+            "select tr.transaction_serial_id, tg.name as tag_name, tr.amount
+            from transaction_tag tr
+            inner join tag tg
+            on tr.tag_serial_id = tg.serial_id
+            where tr.transaction_serial_id in (select unnest($1::bigint[]))
+            order by tg.name;
+           ",
+        )
+        .bind(&transaction_serial_ids);
+
+        let mut allocations = HashMap::<i64, Vec<TagAllocation>>::new();
+        for tag in mm.dbx().fetch_all(sqlx_query).await? {
+            allocations
+                .entry(tag.transaction_serial_id)
+                .or_default()
+                .push(TagAllocation {
+                    tag: tag.tag_name,
+                    amount: tag.amount,
+                });
+        }
+
+        let sqlx_query = sqlx::query_as::<_, TransactionUnitTable>(
+            "select tu.transaction_serial_id, tu.unit,
+                uc.cost_per_unit, ut.name as unit_type_name
+            from transaction_unit tu
+            inner join unit_cost uc
+                on tu.unit_cost_serial_id = uc.serial_id
+            inner join unit_type ut
+                on uc.unit_type_serial_id = ut.serial_id
+            where tu.transaction_serial_id in (select unnest($1::bigint[]));
+           ",
+        )
+        .bind(&transaction_serial_ids);
+
+        let mut transactions_unit = mm
+            .dbx()
+            .fetch_all(sqlx_query)
+            .await?
+            .into_iter()
+            .map(|v| (v.transaction_serial_id, v))
+            .collect::<HashMap<i64, TransactionUnitTable>>();
+
+        Ok(transactions
+            .into_iter()
+            .map(|v| {
+                let tax = v.tax();
+                let allocations =
+                    allocations.remove(&v.serial_id).unwrap_or_default();
+                Transaction {
+                    transaction_id: v.transaction_id,
+                    name: v.name,
+                    transaction_time: v.transaction_time,
+                    remark: v.remark,
+                    tags: allocations.iter().map(|v| v.tag.clone()).collect(),
+                    allocations,
+                    payment_method: PaymentMethod::from_str(
+                        &v.payment_method_name,
+                    )
+                    .unwrap_or_default(),
+                    unit_cost: transactions_unit.remove(&v.serial_id).map(
+                        |t| UnitCost {
+                            unit_type: t.unit_type_name,
+                            unit: t.unit,
+                            cost_per_unit: t.cost_per_unit,
+                        },
+                    ),
+                    seller_cost: v.seller_cost,
+                    currency: Currency::from_str(&v.currency_code)
+                        .unwrap_or_default(),
+                    tax,
+                    kind: TransactionKind::from_str(&v.transaction_kind_name)
+                        .unwrap_or_default(),
+                    original_transaction_id: v.original_transaction_id,
+                    bill_id: v.bill_id,
+                }
+            })
+            .collect())
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::{
+        _dev_utils,
+        model::{bill::BillBmc, seller::SellerBmc, tag::TagBmc, unit::UnitBmc},
+    };
+    use serde_json::json;
+    use serial_test::serial;
+    use std::time::Instant;
+    use tracing::info;
+    use tracing_subscriber::EnvFilter;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+
+        let sellers = SellerBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "test_create_ok-seller_name-01" }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({
+                "remark": "test_create_ok-bill_remark-01",
+                "sellerId": sellers[0].seller_id
+            }))
+            .unwrap(),
+        )
+        .await?;
+
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([{
+              "name": "tr 1",
+              "transactionTime": null,
+              "remark": null,
+              "tags": ["a", "b "],
+              "paymentMethod": "Cash",
+              "unitCost": {
+                "unitType": "kg",
+                "unit": 1.4,
+                "costPerUnit": 58.0
+              },
+              "sellerCost": "81.2",
+              "billId": null
+            }, {
+              "name": "tr 2",
+              "transactionTime": "2020-09-08T13:10:08.511Z",
+              "remark": "Some remark",
+              "tags": ["d", "c"],
+              "paymentMethod": "Upi",
+              "unitCost": {
+                "unitType": "bag",
+                "unit": 5,
+                "costPerUnit": 340
+              },
+              "sellerCost": 1700,
+              "billId": bill_id
+            }]))
+            .unwrap(),
+        )
+        .await?;
+
+        println!("{:?}", transactions);
+
+        let transaction_ids = transactions
+            .iter()
+            .map(|v| v.transaction_id.clone())
+            .collect();
+
+        let transactions =
+            TransactionBmc::get_by_transaction_ids(&ctx, &mm, transaction_ids)
+                .await?;
+
+        println!("{}", serde_json::to_string_pretty(&transactions).unwrap());
+
+        // // -- Clean
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_by_id_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+
+        let sellers = SellerBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "test_create_ok-seller_name-01" }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({
+                "remark": "test_create_ok-bill_remark-01",
+                "sellerId": sellers[0].seller_id
+            }))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Exec
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([{
+                "name": "tr 1",
+                "transactionTime": null,
+                "remark": null,
+                "tags": ["a", "b "],
+                "paymentMethod": "Cash",
+                "unitCost": {
+                    "unitType": "kg",
+                    "unit": 1.4,
+                    "costPerUnit": 58.0
+                },
+                "sellerCost": "81.2",
+                "billId": null
+            }, {
+                "name": "tr 2",
+                "transactionTime": "2020-09-08T13:10:08.511Z",
+                "remark": "Some remark",
+                "tags": ["d", "c"],
+                "paymentMethod": "Upi",
+                "unitCost": {
+                    "unitType": "bag",
+                    "unit": 5,
+                    "costPerUnit": 340
+                },
+                "sellerCost": 1700,
+                "billId": bill_id
+            }, {
+                "name": "tr 3",
+                "transactionTime": "2020-09-08T13:10:08.511Z",
+                "remark": null,
+                "tags": ["a", "c"],
+                "paymentMethod": "Card",
+                "unitCost": null,
+                "sellerCost": 1700,
+                "billId": bill_id
+            }]))
+            .unwrap(),
+        )
+        .await?;
+
+        println!("{:?}", transactions);
+
+        let transaction_ids = transactions
+            .iter()
+            .map(|v| v.transaction_id.clone())
+            .collect();
+
+        let transactions =
+            TransactionBmc::get_by_transaction_ids(&ctx, &mm, transaction_ids)
+                .await?;
+
+        println!("{}", serde_json::to_string_pretty(&transactions).unwrap());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_filter_sort_paginate_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_tag = "test_list-tag-01";
+
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr list 1", "tags": [fx_tag], "paymentMethod": "Cash", "sellerCost": 30 },
+                { "name": "tr list 2", "tags": [fx_tag], "paymentMethod": "Upi", "sellerCost": 10 },
+                { "name": "tr list 3", "tags": [fx_tag], "paymentMethod": "Cash", "sellerCost": 20 },
+                { "name": "tr other", "tags": ["other"], "paymentMethod": "Cash", "sellerCost": 5 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        let fx_filter = || TransactionFilter {
+            tag: Some(fx_tag.to_string()),
+            ..Default::default()
+        };
+
+        // -- Exec
+        let page_1 = TransactionBmc::list(
+            &ctx,
+            &mm,
+            fx_filter(),
+            TransactionListOptions {
+                sort_by: TransactionSortBy::SellerCost,
+                order: SortOrder::Asc,
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let page_2 = TransactionBmc::list(
+            &ctx,
+            &mm,
+            fx_filter(),
+            TransactionListOptions {
+                sort_by: TransactionSortBy::SellerCost,
+                order: SortOrder::Asc,
+                limit: Some(2),
+                cursor: page_1.next_cursor.clone(),
+            },
+        )
+        .await?;
+        let cash_only = TransactionBmc::list(
+            &ctx,
+            &mm,
+            TransactionFilter {
+                payment_method: Some(PaymentMethod::Cash),
+                min_cost: Some(BigDecimal::from(25)),
+                ..fx_filter()
+            },
+            TransactionListOptions::default(),
+        )
+        .await?;
+
+        // -- Check
+        let names = |page: &Page<Transaction>| {
+            page.items
+                .iter()
+                .map(|v| v.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&page_1), ["tr list 2", "tr list 3"]);
+        assert_eq!(names(&page_2), ["tr list 1"]);
+        assert!(page_2.next_cursor.is_none());
+        assert_eq!(names(&cash_only), ["tr list 1"]);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_filter_name_literal_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_tag = "test_list_filter_name_literal-tag";
+
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr 10% off", "tags": [fx_tag], "paymentMethod": "Cash", "sellerCost": 10 },
+                { "name": "tr 100 off", "tags": [fx_tag], "paymentMethod": "Cash", "sellerCost": 10 },
+                { "name": "tr_a", "tags": [fx_tag], "paymentMethod": "Cash", "sellerCost": 10 },
+                { "name": "tr ab", "tags": [fx_tag], "paymentMethod": "Cash", "sellerCost": 10 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+
+        let fx_list = |name: &str| {
+            TransactionBmc::list(
+                &ctx,
+                &mm,
+                TransactionFilter {
+                    tag: Some(fx_tag.to_string()),
+                    name: Some(name.to_string()),
+                    ..Default::default()
+                },
+                TransactionListOptions::default(),
+            )
+        };
+
+        // -- Exec
+        let percent = fx_list("10%").await?;
+        let underscore = fx_list("r_a").await?;
+
+        // -- Check
+        let names = |page: Page<Transaction>| {
+            page.items.into_iter().map(|v| v.name).collect::<Vec<_>>()
+        };
+        assert_eq!(names(percent), ["tr 10% off"]);
+        assert_eq!(names(underscore), ["tr_a"]);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_delete_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([{
+                "name": "tr typo",
+                "remark": "some remark",
+                "tags": ["a", "b"],
+                "paymentMethod": "Cash",
+                "sellerCost": 100
+            }]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_transaction_id = transactions[0].transaction_id.clone();
+
+        // -- Exec
+        let transaction = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &fx_transaction_id,
+            serde_json::from_value(json!({
+                "name": "tr fixed",
+                "remark": null,
+                "tags": ["C ", "a"],
+                "paymentMethod": "Upi",
+                "unitCost": { "unitType": "kg", "unit": 3, "costPerUnit": 40 },
+                "sellerCost": 120
+            }))
+            .unwrap(),
+        )
+        .await?;
+
+        // -- Check
+        let mut tags = transaction.tags.clone();
+        tags.sort();
+        assert_eq!(transaction.name, "tr fixed");
+        assert_eq!(transaction.remark, None);
+        assert_eq!(tags, ["a", "c"]);
+        assert!(matches!(transaction.payment_method, PaymentMethod::Upi));
+        assert_eq!(transaction.seller_cost, BigDecimal::from(120));
+        let unit_cost = transaction.unit_cost.ok_or("Should have unit cost")?;
+        assert_eq!(unit_cost.unit, BigDecimal::from(3));
+        assert_eq!(unit_cost.cost_per_unit, BigDecimal::from(40));
+
+        // -- Exec & Check delete
+        TransactionBmc::delete(&ctx, &mm, &fx_transaction_id).await?;
+        let found = TransactionBmc::get_by_transaction_ids(
+            &ctx,
+            &mm,
+            vec![fx_transaction_id.clone()],
+        )
+        .await?;
+        assert!(found.is_empty());
+        assert!(matches!(
+            TransactionBmc::delete(&ctx, &mm, &fx_transaction_id).await,
+            Err(super::Error::TransactionNotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_allocations_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let amounts = |transaction: &Transaction| {
+            transaction
+                .allocations
+                .iter()
+                .map(|v| (v.tag.clone(), v.amount.clone()))
+                .collect::<Vec<_>>()
+        };
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                {
+                    "name": "tr split",
+                    "tags": ["groceries"],
+                    "allocations": [
+                        { "tag": "Office", "amount": 30 },
+                        { "tag": "home", "amount": 70 }
+                    ],
+                    "paymentMethod": "Card",
+                    "sellerCost": 100
+                },
+                { "name": "tr even", "tags": ["a", "b", "c"], "paymentMethod": "Card", "sellerCost": 10 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
+        let fx_transaction_id = transactions[0].transaction_id.clone();
+
+        // -- Check create
+        assert_eq!(
+            amounts(&transactions[0]),
+            [
+                ("groceries".into(), dec("0")),
+                ("home".into(), dec("70")),
+                ("office".into(), dec("30"))
+            ]
+        );
+        assert_eq!(
+            amounts(&transactions[1]),
+            [
+                ("a".into(), dec("3.34")),
+                ("b".into(), dec("3.33")),
+                ("c".into(), dec("3.33"))
+            ]
+        );
+
+        // -- Exec & Check the allocations follow the seller cost
+        let transaction = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &fx_transaction_id,
+            serde_json::from_value(json!({ "sellerCost": 50 })).unwrap(),
+        )
+        .await?;
+        assert_eq!(
+            amounts(&transaction),
+            [
+                ("groceries".into(), dec("0")),
+                ("home".into(), dec("35")),
+                ("office".into(), dec("15"))
+            ]
+        );
+
+        // -- Exec & Check allocations not summing up to the seller cost
+        let res = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &fx_transaction_id,
+            serde_json::from_value(json!({
+                "allocations": [{ "tag": "home", "amount": 40 }]
+            }))
+            .unwrap(),
+        )
+        .await;
+        let Err(super::Error::Validation(
+            crate::model::validation::Error::Validation { errors },
+        )) = res
+        else {
+            return Err("Should fail validation".into());
+        };
+        assert_eq!(errors[0].path, "allocations");
+
+        // -- Exec & Check a deleted tag leaves its share to the others
+        TagBmc::delete(&ctx, &mm, "home").await?;
+        let transaction = TransactionBmc::get_by_transaction_ids(
+            &ctx,
+            &mm,
+            vec![fx_transaction_id],
         )
-        .bind(&transaction_serial_ids);
+        .await?
+        .pop()
+        .ok_or("Should find the transaction")?;
+        assert_eq!(
+            amounts(&transaction),
+            [("groceries".into(), dec("0")), ("office".into(), dec("50"))]
+        );
 
-        let tags = mm.dbx().fetch_all(sqlx_query).await?;
+        Ok(())
+    }
 
-        let sqlx_query = sqlx::query_as::<_, TransactionUnitTable>(
-            "select tu.transaction_serial_id, tu.unit,
-                uc.cost_per_unit, ut.name as unit_type_name
-            from transaction_unit tu
-            inner join unit_cost uc
-                on tu.unit_cost_serial_id = uc.serial_id
-            inner join unit_type ut
-                on uc.unit_type_serial_id = ut.serial_id
-            where tu.transaction_serial_id in (select unnest($1::bigint[]));
-           ",
-        )
-        .bind(&transaction_serial_ids);
+    #[serial]
+    #[tokio::test]
+    async fn test_kinds_link_original_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let create = async |value: serde_json::Value| {
+            TransactionBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(value).unwrap(),
+            )
+            .await
+        };
+        let purchase = create(json!([
+            { "name": "tr purchase", "paymentMethod": "Cash", "sellerCost": 100 }
+        ]))
+        .await?
+        .remove(0);
+        let fx_purchase_id = purchase.transaction_id.as_str();
 
-        let transactions_unit = mm.dbx().fetch_all(sqlx_query).await?;
+        // -- Exec
+        let credits = create(json!([
+            { "name": "tr refund", "kind": "Refund", "originalTransactionId": fx_purchase_id, "paymentMethod": "Cash", "sellerCost": 60 },
+            { "name": "tr discount", "kind": "Discount", "originalTransactionId": fx_purchase_id, "paymentMethod": "Cash", "sellerCost": 40 }
+        ]))
+        .await?;
 
-        println!("{:#?}", transactions_unit);
+        // -- Check
+        assert_eq!(purchase.kind, TransactionKind::Purchase);
+        assert_eq!(credits[0].kind, TransactionKind::Refund);
+        assert_eq!(
+            credits[0].original_transaction_id.as_deref(),
+            Some(fx_purchase_id)
+        );
+        let page = TransactionBmc::list(
+            &ctx,
+            &mm,
+            TransactionFilter {
+                kind: Some(TransactionKind::Discount),
+                ..Default::default()
+            },
+            TransactionListOptions::default(),
+        )
+        .await?;
+        assert_eq!(page.items.len(), 1);
+
+        // -- Exec & Check rejected links
+        let res = create(json!([
+            { "name": "tr refund", "kind": "Refund", "originalTransactionId": fx_purchase_id, "paymentMethod": "Cash", "sellerCost": 0.01 }
+        ]))
+        .await;
+        assert!(
+            matches!(&res, Err(super::Error::TransactionExceedsOriginal { credited, .. }) if credited == &BigDecimal::from_str("100.01")?),
+            "{res:?}"
+        );
+        let res = create(json!([
+            { "name": "tr refund", "kind": "Refund", "originalTransactionId": credits[0].transaction_id, "paymentMethod": "Cash", "sellerCost": 1 }
+        ]))
+        .await;
+        assert!(matches!(
+            res,
+            Err(super::Error::TransactionOriginalInvalid { .. })
+        ));
+        let res = create(json!([
+            { "name": "tr return", "kind": "Return", "paymentMethod": "Cash", "sellerCost": 1 },
+            { "name": "tr purchase", "originalTransactionId": fx_purchase_id, "paymentMethod": "Cash", "sellerCost": 1 },
+            { "name": "tr negative", "kind": "Discount", "paymentMethod": "Cash", "sellerCost": -1 }
+        ]))
+        .await;
+        let Err(super::Error::Validation(
+            crate::model::validation::Error::Validation { errors },
+        )) = res
+        else {
+            return Err(format!("Should fail validation: {res:?}").into());
+        };
+        let paths = errors.iter().map(|v| v.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "[0].originalTransactionId",
+                "[1].originalTransactionId",
+                "[2].sellerCost"
+            ]
+        );
+
+        // -- Exec & Check the purchase is kept, its credits must still fit
+        assert!(matches!(
+            TransactionBmc::delete(&ctx, &mm, fx_purchase_id).await,
+            Err(super::Error::TransactionHasLinked { .. })
+        ));
+        let res = TransactionBmc::update(
+            &ctx,
+            &mm,
+            fx_purchase_id,
+            serde_json::from_value(json!({ "sellerCost": 90 })).unwrap(),
+        )
+        .await;
+        assert!(matches!(
+            res,
+            Err(super::Error::TransactionExceedsOriginal { .. })
+        ));
 
-        Ok(transactions
-            .into_iter()
-            .map(|v| Transaction {
-                transaction_id: v.transaction_id,
-                name: v.name,
-                transaction_time: v.transaction_time,
-                remark: v.remark,
-                tags: tags
-                    .iter()
-                    .filter(|t| t.transaction_serial_id == v.serial_id)
-                    .map(|t| t.tag_name.clone())
-                    .collect::<Vec<String>>(),
-                payment_method: PaymentMethod::from_str(&v.payment_method_name)
-                    .unwrap_or_default(),
-                unit_cost: transactions_unit
-                    .iter()
-                    .find(|t| t.transaction_serial_id == v.serial_id)
-                    .map(|t| UnitCost {
-                        unit_type: t.unit_type_name.clone(),
-                        unit: t.unit.clone(),
-                        cost_per_unit: t.cost_per_unit.clone(),
-                    }),
-                seller_cost: v.seller_cost,
-                bill_id: v.bill_id,
-            })
-            .collect())
+        Ok(())
     }
-}
-
-// region:    --- Tests
-
-#[cfg(test)]
-mod tests {
-    pub type Result<T> = std::result::Result<T, Error>;
-    pub type Error = Box<dyn std::error::Error>; // For tests.
-
-    use super::*;
-    use crate::{
-        _dev_utils,
-        model::{bill::BillBmc, seller::SellerBmc},
-    };
-    use serde_json::json;
-    use serial_test::serial;
 
     #[serial]
     #[tokio::test]
-    async fn test_create_ok() -> Result<()> {
+    async fn test_update_currency_linked_err() -> Result<()> {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
-
-        let sellers = SellerBmc::create(
+        let ctx = _dev_utils::demo_ctx();
+        let purchase = TransactionBmc::create(
+            &ctx,
             &mm,
             serde_json::from_value(json!([
-                { "name": "test_create_ok-seller_name-01" }
+                { "name": "tr purchase", "paymentMethod": "Cash", "sellerCost": 100 }
             ]))
             .unwrap(),
         )
-        .await?;
-
-        let bill_id = BillBmc::create(
+        .await?
+        .remove(0);
+        let fx_purchase_id = purchase.transaction_id.as_str();
+        let refund = TransactionBmc::create(
+            &ctx,
             &mm,
-            serde_json::from_value(json!({
-                "remark": "test_create_ok-bill_remark-01",
-                "sellerId": sellers[0].seller_id
-            }))
+            serde_json::from_value(json!([
+                { "name": "tr refund", "kind": "Refund", "originalTransactionId": fx_purchase_id, "paymentMethod": "Cash", "sellerCost": 60 }
+            ]))
             .unwrap(),
         )
+        .await?
+        .remove(0);
+        let update_currency = async |transaction_id: &str, currency: &str| {
+            TransactionBmc::update(
+                &ctx,
+                &mm,
+                transaction_id,
+                serde_json::from_value(json!({ "currency": currency }))
+                    .unwrap(),
+            )
+            .await
+        };
+
+        // -- Exec & Check
+        let res = update_currency(&refund.transaction_id, "USD").await;
+        assert!(
+            matches!(
+                &res,
+                Err(super::Error::TransactionOriginalInvalid {
+                    transaction_id,
+                    reason: "is in another currency",
+                }) if transaction_id == fx_purchase_id
+            ),
+            "{res:?}"
+        );
+        let res = update_currency(fx_purchase_id, "USD").await;
+        assert!(
+            matches!(
+                &res,
+                Err(super::Error::TransactionOriginalInvalid {
+                    reason: "has credits in another currency",
+                    ..
+                })
+            ),
+            "{res:?}"
+        );
+        update_currency(fx_purchase_id, "INR").await?;
+        update_currency(&refund.transaction_id, "INR").await?;
+        let transactions = TransactionBmc::get_by_transaction_ids(
+            &ctx,
+            &mm,
+            vec![fx_purchase_id.to_string(), refund.transaction_id.clone()],
+        )
         .await?;
+        assert!(transactions.iter().all(|v| v.currency == Currency::Inr));
 
-        let transactions = TransactionBmc::create(
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_update_tax_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        TransactionBmc::create(
+            &ctx,
             &mm,
             serde_json::from_value(json!([{
-              "name": "tr 1",
-              "transactionTime": null,
-              "remark": null,
-              "tags": ["a", "b "],
-              "paymentMethod": "Cash",
-              "unitCost": {
-                "unitType": "kg",
-                "unit": 1.4,
-                "costPerUnit": 58.0
-              },
-              "sellerCost": "10.4321",
-              "billId": null
-            }, {
-              "name": "tr 2",
-              "transactionTime": "2020-09-08T13:10:08.511Z",
-              "remark": "Some remark",
-              "tags": ["d", "c"],
-              "paymentMethod": "Upi",
-              "unitCost": {
-                "unitType": "bag",
-                "unit": 5,
-                "costPerUnit": 340
-              },
-              "sellerCost": 1700,
-              "billId": bill_id
+                "name": "tr seed",
+                "tags": ["stationery", "food"],
+                "paymentMethod": "Cash",
+                "unitCost": { "unitType": "ream", "unit": 1, "costPerUnit": 10 },
+                "sellerCost": 10
             }]))
             .unwrap(),
         )
         .await?;
+        TagBmc::update(
+            &ctx,
+            &mm,
+            "stationery",
+            serde_json::from_value(json!({ "taxRate": 12 })).unwrap(),
+        )
+        .await?;
+        TagBmc::update(
+            &ctx,
+            &mm,
+            "food",
+            serde_json::from_value(json!({ "taxRate": 5 })).unwrap(),
+        )
+        .await?;
+        UnitBmc::update(
+            &Ctx::root_ctx(),
+            &mm,
+            "ream",
+            serde_json::from_value(json!({ "taxRate": 18 })).unwrap(),
+        )
+        .await?;
 
-        println!("{:?}", transactions);
+        // -- Exec
+        let transactions = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr explicit", "paymentMethod": "Card", "sellerCost": 105.50, "taxRate": 5, "gstSupply": "interState", "tags": ["stationery"] },
+                { "name": "tr unit", "paymentMethod": "Card", "sellerCost": 118, "tags": ["stationery"], "unitCost": { "unitType": "ream", "unit": 2, "costPerUnit": 59 } },
+                { "name": "tr tag", "paymentMethod": "Card", "sellerCost": 112, "tags": ["Stationery "] },
+                { "name": "tr untaxed", "paymentMethod": "Card", "sellerCost": 50 }
+            ]))
+            .unwrap(),
+        )
+        .await?;
 
-        let transaction_ids = transactions
+        // -- Check
+        let taxes = transactions
             .iter()
-            .map(|v| v.transaction_id.clone())
-            .collect();
-
-        let transactions =
-            TransactionBmc::get_by_transaction_ids(&mm, transaction_ids)
-                .await?;
-
-        println!("{}", serde_json::to_string_pretty(&transactions).unwrap());
-
-        // // -- Clean
+            .map(|v| v.tax.as_ref().map(|t| t.rate.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            taxes,
+            [Some(dec("5")), Some(dec("18")), Some(dec("12")), None]
+        );
+        let tax = transactions[0].tax.as_ref().ok_or("Should have tax")?;
+        assert_eq!(tax.supply, GstSupply::InterState);
+        assert_eq!(tax.taxable_value, dec("100.48"));
+        assert_eq!((&tax.cgst, &tax.igst), (&dec("0"), &dec("5.02")));
+        let tax = transactions[1].tax.as_ref().ok_or("Should have tax")?;
+        assert_eq!(tax.taxable_value, dec("100"));
+        assert_eq!((&tax.cgst, &tax.sgst), (&dec("9"), &dec("9")));
+
+        // -- Exec & Check ambiguous tag rates
+        let res = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([
+                { "name": "tr mixed", "paymentMethod": "Card", "sellerCost": 10, "tags": ["stationery", "food"] }
+            ]))
+            .unwrap(),
+        )
+        .await;
+        assert!(matches!(
+            res,
+            Err(super::Error::TransactionTaxRateAmbiguous { tags, .. })
+                if tags == ["food", "stationery"]
+        ));
+
+        // -- Exec & Check update recomputes the tax
+        let transaction = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &transactions[2].transaction_id,
+            serde_json::from_value(json!({ "sellerCost": 224 })).unwrap(),
+        )
+        .await?;
+        let tax = transaction.tax.ok_or("Should have tax")?;
+        assert_eq!(tax.taxable_value, dec("200"));
+        assert_eq!((&tax.cgst, &tax.sgst), (&dec("12"), &dec("12")));
+        let transaction = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &transactions[2].transaction_id,
+            serde_json::from_value(json!({ "taxRate": null })).unwrap(),
+        )
+        .await?;
+        assert!(transaction.tax.is_none());
 
         Ok(())
     }
 
     #[serial]
     #[tokio::test]
-    async fn test_get_by_id_ok() -> Result<()> {
+    async fn test_create_unit_cost_read_back_ok() -> Result<()> {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_unit_costs = [
+            ("tr 1", "kg", "2", "12.35", "24.70"),
+            ("tr 2", "kg", "1.5", "40", "60"),
+            // Same cost as `tr 1`, another unit type.
+            ("tr 3", "bag", "3", "12.35", "37.05"),
+            // Same pair as `tr 1`, in the same batch.
+            ("tr 4", " kg", "1", "12.35", "12.35"),
+            ("tr 5", "box", "1", "1234567890.99", "1234567890.99"),
+        ];
+        let fx_transactions = fx_unit_costs
+            .iter()
+            .map(|(name, unit_type, unit, cost_per_unit, seller_cost)| {
+                json!({
+                    "name": name,
+                    "paymentMethod": "Cash",
+                    "unitCost": {
+                        "unitType": unit_type,
+                        "unit": unit,
+                        "costPerUnit": cost_per_unit
+                    },
+                    "sellerCost": seller_cost
+                })
+            })
+            .collect::<Vec<_>>();
 
-        let sellers = SellerBmc::create(
+        // -- Exec
+        let transactions = TransactionBmc::create(
+            &ctx,
             &mm,
-            serde_json::from_value(json!([
-                { "name": "test_create_ok-seller_name-01" }
-            ]))
+            serde_json::from_value(json!(fx_transactions)).unwrap(),
+        )
+        .await?;
+        // Reuses the existing `kg` at 12.35.
+        TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([{
+                "name": "tr 6",
+                "paymentMethod": "Cash",
+                "unitCost": { "unitType": "kg", "unit": 4, "costPerUnit": "12.35" },
+                "sellerCost": "49.40"
+            }]))
             .unwrap(),
         )
         .await?;
 
-        let bill_id = BillBmc::create(
+        // -- Check
+        let transactions = TransactionBmc::get_by_transaction_ids(
+            &ctx,
             &mm,
-            serde_json::from_value(json!({
-                "remark": "test_create_ok-bill_remark-01",
-                "sellerId": sellers[0].seller_id
-            }))
-            .unwrap(),
+            transactions
+                .iter()
+                .map(|v| v.transaction_id.clone())
+                .collect(),
         )
         .await?;
+        for (name, unit_type, unit, cost_per_unit, _) in fx_unit_costs {
+            let unit_cost = transactions
+                .iter()
+                .find(|v| v.name == name)
+                .and_then(|v| v.unit_cost.clone())
+                .ok_or("Should have unit cost")?;
+            assert_eq!(unit_cost.unit_type, unit_type.trim(), "{name}");
+            assert_eq!(unit_cost.unit, dec(unit), "{name}");
+            assert_eq!(unit_cost.cost_per_unit, dec(cost_per_unit), "{name}");
+        }
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "select count(*) from unit_cost uc
+            inner join unit_type ut on ut.serial_id = uc.unit_type_serial_id
+            where ut.name = 'kg' and uc.cost_per_unit = 12.35;",
+        );
+        let (count,) = mm.dbx().fetch_one(sqlx_query).await?;
+        assert_eq!(count, 1);
 
-        // -- Exec
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_unit_cost_seller_cost_mismatch_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
         let transactions = TransactionBmc::create(
+            &ctx,
             &mm,
             serde_json::from_value(json!([{
-                "name": "tr 1",
-                "transactionTime": null,
-                "remark": null,
-                "tags": ["a", "b "],
+                "name": "tr rounded",
                 "paymentMethod": "Cash",
-                "unitCost": {
-                    "unitType": "kg",
-                    "unit": 1.4,
-                    "costPerUnit": 58.0
-                },
-                "sellerCost": "10.4321",
-                "billId": null
-            }, {
-                "name": "tr 2",
-                "transactionTime": "2020-09-08T13:10:08.511Z",
-                "remark": "Some remark",
-                "tags": ["d", "c"],
-                "paymentMethod": "Upi",
-                "unitCost": {
-                    "unitType": "bag",
-                    "unit": 5,
-                    "costPerUnit": 340
-                },
-                "sellerCost": 1700,
-                "billId": bill_id
-            }, {
-                "name": "tr 3",
-                "transactionTime": "2020-09-08T13:10:08.511Z",
-                "remark": null,
-                "tags": ["a", "c"],
-                "paymentMethod": "Card",
-                "unitCost": null,
-                "sellerCost": 1700,
-                "billId": bill_id
+                "unitCost": { "unitType": "kg", "unit": "0.333", "costPerUnit": "45.50" },
+                "sellerCost": "15.16"
             }]))
             .unwrap(),
         )
         .await?;
+        let fx_transaction_id = transactions[0].transaction_id.clone();
 
-        println!("{:?}", transactions);
+        // -- Exec & Check create
+        let res = TransactionBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!([{
+                "name": "tr mismatch",
+                "paymentMethod": "Cash",
+                "unitCost": { "unitType": "kg", "unit": 2, "costPerUnit": 10 },
+                "sellerCost": 25
+            }]))
+            .unwrap(),
+        )
+        .await;
+        assert!(
+            matches!(
+                &res,
+                Err(super::Error::TransactionUnitCostMismatch {
+                    expected_seller_cost,
+                    ..
+                }) if *expected_seller_cost == BigDecimal::from(20)
+            ),
+            "{res:?}"
+        );
+
+        // -- Exec & Check update of the seller cost alone
+        assert!(matches!(
+            TransactionBmc::update(
+                &ctx,
+                &mm,
+                &fx_transaction_id,
+                serde_json::from_value(json!({ "sellerCost": 20 })).unwrap(),
+            )
+            .await,
+            Err(super::Error::TransactionUnitCostMismatch { .. })
+        ));
+        let transaction = TransactionBmc::update(
+            &ctx,
+            &mm,
+            &fx_transaction_id,
+            serde_json::from_value(
+                json!({ "unitCost": null, "sellerCost": 20 }),
+            )
+            .unwrap(),
+        )
+        .await?;
+        assert!(transaction.unit_cost.is_none());
 
-        let transaction_ids = transactions
-            .iter()
-            .map(|v| v.transaction_id.clone())
-            .collect();
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_validation_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_transactions = serde_json::from_value(json!([{
+            "name": "tr ok",
+            "paymentMethod": "Cash",
+            "sellerCost": 10
+        }, {
+            "name": " ",
+            "transactionTime": "2999-01-01T00:00:00Z",
+            "remark": "r".repeat(257),
+            "tags": ["ok", ""],
+            "paymentMethod": "Cash",
+            "unitCost": { "unitType": "kg", "unit": 0, "costPerUnit": 1 },
+            "sellerCost": -1
+        }]))
+        .unwrap();
+
+        // -- Exec
+        let res = TransactionBmc::create(&ctx, &mm, fx_transactions).await;
+
+        // -- Check
+        let Err(super::Error::Validation(
+            crate::model::validation::Error::Validation { errors },
+        )) = res
+        else {
+            return Err(format!("Should fail validation: {res:?}").into());
+        };
+        let paths = errors.iter().map(|v| v.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "[1].name",
+                "[1].remark",
+                "[1].transactionTime",
+                "[1].sellerCost",
+                "[1].tags[1]",
+                "[1].unitCost.unit",
+            ]
+        );
+        let page = TransactionBmc::list(
+            &ctx,
+            &mm,
+            TransactionFilter::default(),
+            TransactionListOptions::default(),
+        )
+        .await?;
+        assert!(page.items.iter().all(|v| v.name != "tr ok"));
+
+        Ok(())
+    }
+
+    /// Benchmark, the row by row insert took minutes for this import.
+    #[serial]
+    #[tokio::test]
+    async fn test_create_bulk_10k_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_count = 10_000;
+        let fx_transactions = fx_bulk_transactions(fx_count);
 
+        // -- Exec
         let transactions =
-            TransactionBmc::get_by_transaction_ids(&mm, transaction_ids)
-                .await?;
+            TransactionBmc::create(&ctx, &mm, fx_transactions).await?;
 
-        println!("{}", serde_json::to_string_pretty(&transactions).unwrap());
+        // -- Check
+        assert_eq!(transactions.len(), fx_count);
+        for (i, transaction) in transactions.iter().enumerate() {
+            assert_eq!(transaction.name, format!("tr bulk {i}"));
+        }
+        let last = &transactions[fx_count - 1];
+        let mut tags = last.tags.clone();
+        tags.sort();
+        assert_eq!(tags, ["bulk", "tag 49"]);
+        let unit_cost =
+            last.unit_cost.clone().ok_or("Should have unit cost")?;
+        assert_eq!(unit_cost.unit_type, "unit 0");
+        assert_eq!(unit_cost.cost_per_unit, BigDecimal::from(10 + 9_999 % 7));
 
         Ok(())
     }
+
+    /// Run with `cargo test -p lib-core bench_create_bulk_10k -- --ignored`.
+    #[serial]
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn bench_create_bulk_10k() -> Result<()> {
+        // -- Setup & Fixtures
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(EnvFilter::from_default_env())
+            .with_test_writer()
+            .try_init();
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let fx_count = 10_000;
+        let fx_transactions = fx_bulk_transactions(fx_count);
+
+        // -- Exec
+        let start = Instant::now();
+        let transactions =
+            TransactionBmc::create(&ctx, &mm, fx_transactions).await?;
+        let elapsed = start.elapsed();
+
+        // -- Report
+        info!(
+            "{:<12} - created {} transactions in {elapsed:?}",
+            "BENCH",
+            transactions.len()
+        );
+
+        Ok(())
+    }
+
+    /// `count` transactions over 50 tags and 3 unit types.
+    fn fx_bulk_transactions(count: usize) -> Vec<TransactionForCreate> {
+        let transactions = (0..count)
+            .map(|i| {
+                let cost_per_unit = 10 + i % 7;
+                json!({
+                    "name": format!("tr bulk {i}"),
+                    "tags": [format!("tag {}", i % 50), "bulk", "Bulk "],
+                    "paymentMethod": if i % 2 == 0 { "Cash" } else { "Upi" },
+                    "unitCost": {
+                        "unitType": format!("unit {}", i % 3),
+                        "unit": 2,
+                        "costPerUnit": cost_per_unit
+                    },
+                    "sellerCost": cost_per_unit * 2
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::from_value(json!(transactions)).unwrap()
+    }
 }
 
 // endregion: --- Tests
@@ -0,0 +1,27 @@
+use crate::model::acs;
+use crate::model::store::dbx;
+use crate::model::validation;
+use serde::Serialize;
+use serde_with::serde_as;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    UnitTypeNotFound {
+        name: String,
+    },
+
+    // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Dbx(#[from] dbx::Error),
+}
+
+// region:    --- Error Boilerplate
@@ -0,0 +1,433 @@
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::acs::{self, Privilege};
+use crate::model::validation::{Validate, Validator};
+use bigdecimal::BigDecimal;
+use lib_utils::money::Currency;
+use lib_utils::time::TimeRfc3339;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+mod error;
+
+pub use error::{Error, Result};
+
+/// Number of previous transactions the median cost is computed on.
+const RECENT_COST_WINDOW: i64 = 10;
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitType {
+    pub name: String,
+    pub transaction_count: i64,
+    pub latest_cost_per_unit: Option<BigDecimal>,
+    pub last_used: Option<TimeRfc3339>,
+    /// Default GST rate (in percent) of the new transactions with this unit
+    /// type, it prevails over the rate of their tags.
+    pub tax_rate: Option<BigDecimal>,
+}
+
+/// Fields that can be changed on an existing unit type.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitTypeForUpdate {
+    /// An explicit `null` removes the default tax rate.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    tax_rate: Option<Option<BigDecimal>>,
+}
+
+impl Validate for UnitTypeForUpdate {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        validator.percent(
+            path,
+            "taxRate",
+            self.tax_rate.as_ref().and_then(Option::as_ref),
+        );
+    }
+}
+
+/// A `cost_per_unit` paid in a transaction.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitPrice {
+    pub transaction_id: String,
+    pub transaction_time: TimeRfc3339,
+    pub unit: BigDecimal,
+    pub cost_per_unit: BigDecimal,
+    #[sqlx(try_from = "String")]
+    pub currency: Currency,
+    /// The seller of the transaction bill, if any.
+    pub seller_id: Option<String>,
+    pub seller_name: Option<String>,
+}
+
+/// How the unit cost of a transaction compares to the recent median cost of
+/// the same unit type.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitCostDeviation {
+    pub transaction_id: String,
+    pub unit_type: String,
+    pub cost_per_unit: BigDecimal,
+    pub median_cost_per_unit: BigDecimal,
+    /// Signed, a price hike is positive.
+    pub deviation_pct: BigDecimal,
+    pub flagged: bool,
+}
+
+/// The unit types are shared by all the organisations, so only updated by a
+/// `SysAdmin` (`unit.update`), their usage and costs are the ones of the
+/// transactions of the organisation of `ctx`.
+pub struct UnitBmc;
+
+impl UnitBmc {
+    pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<UnitType>> {
+        acs::require(ctx, mm, Privilege::UnitRead).await?;
+
+        let sqlx_query = sqlx::query_as::<_, UnitType>(
+            "select ut.name, u.transaction_count,
+                l.cost_per_unit as latest_cost_per_unit,
+                l.transaction_time as last_used, ut.tax_rate
+            from unit_type ut
+            left join lateral (
+                select count(*) as transaction_count from transaction_unit tu
+                inner join unit_cost uc on uc.serial_id = tu.unit_cost_serial_id
+                inner join transaction tr on tr.serial_id = tu.transaction_serial_id
+                where uc.unit_type_serial_id = ut.serial_id
+                  and tr.org_serial_id = org_serial_id($1)
+            ) u on true
+            left join lateral (
+                select uc.cost_per_unit, tr.transaction_time
+                from transaction_unit tu
+                inner join unit_cost uc on uc.serial_id = tu.unit_cost_serial_id
+                inner join transaction tr on tr.serial_id = tu.transaction_serial_id
+                where uc.unit_type_serial_id = ut.serial_id
+                  and tr.org_serial_id = org_serial_id($1)
+                order by tr.transaction_time desc, tr.serial_id desc
+                limit 1
+            ) l on true
+            order by ut.name;",
+        )
+        .bind(ctx.org_id());
+
+        let unit_types = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(unit_types)
+    }
+
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        name: &str,
+        unit_type_u: UnitTypeForUpdate,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::UnitUpdate).await?;
+
+        unit_type_u.validate()?;
+
+        let UnitTypeForUpdate { tax_rate } = unit_type_u;
+
+        let Some(tax_rate) = tax_rate else {
+            return Ok(());
+        };
+
+        let sqlx_query = sqlx::query(
+            "update unit_type set tax_rate = $2, mtime = $3 where name = $1;",
+        )
+        .bind(name)
+        .bind(tax_rate)
+        .bind(TimeRfc3339::now_utc().inner());
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+
+        if count == 0 {
+            return Err(Error::UnitTypeNotFound {
+                name: name.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Every cost paid for `unit_type`, oldest first.
+    pub async fn get_price_history(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        unit_type: &str,
+    ) -> Result<Vec<UnitPrice>> {
+        acs::require(ctx, mm, Privilege::UnitRead).await?;
+
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(
+            "select serial_id from unit_type where name = $1;",
+        )
+        .bind(unit_type);
+
+        let (unit_type_serial_id,) =
+            mm.dbx().fetch_optional(sqlx_query).await?.ok_or(
+                Error::UnitTypeNotFound {
+                    name: unit_type.to_string(),
+                },
+            )?;
+
+        let sqlx_query = sqlx::query_as::<_, UnitPrice>(
+            "select tr.transaction_id, tr.transaction_time, tu.unit,
+                uc.cost_per_unit, cu.code as currency,
+                s.seller_id, s.name as seller_name
+            from transaction_unit tu
+            inner join unit_cost uc on uc.serial_id = tu.unit_cost_serial_id
+            inner join transaction tr on tr.serial_id = tu.transaction_serial_id
+            inner join currency cu on cu.serial_id = tr.currency_serial_id
+            left join bill b on b.serial_id = tr.bill_serial_id
+            left join seller s on s.serial_id = b.seller_serial_id
+            where uc.unit_type_serial_id = $1
+              and tr.org_serial_id = org_serial_id($2)
+            order by tr.transaction_time, tr.serial_id;",
+        )
+        .bind(unit_type_serial_id)
+        .bind(ctx.org_id());
+
+        let prices = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(prices)
+    }
+
+    /// Compare the unit cost of each of `transaction_ids` to the median of
+    /// the previous `RECENT_COST_WINDOW` costs of its unit type, in the same
+    /// currency.
+    ///
+    /// The transactions without unit cost or without previous cost to
+    /// compare to are left out.
+    pub async fn check_cost_deviations(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        transaction_ids: &[String],
+        threshold_pct: u32,
+    ) -> Result<Vec<UnitCostDeviation>> {
+        acs::require(ctx, mm, Privilege::UnitRead).await?;
+
+        // NOTE: The median is the mean of the middle row(s) of the window.
+        let sqlx_query = sqlx::query_as::<
+            _,
+            (String, String, BigDecimal, BigDecimal),
+        >(
+            "select tr.transaction_id, ut.name, uc.cost_per_unit,
+                recent.median_cost_per_unit
+            from transaction tr
+            inner join transaction_unit tu on tu.transaction_serial_id = tr.serial_id
+            inner join unit_cost uc on uc.serial_id = tu.unit_cost_serial_id
+            inner join unit_type ut on ut.serial_id = uc.unit_type_serial_id
+            inner join lateral (
+                select avg(w.cost_per_unit) as median_cost_per_unit from (
+                    select r.cost_per_unit,
+                        row_number() over (order by r.cost_per_unit) as rn,
+                        count(*) over () as cnt
+                    from (
+                        select puc.cost_per_unit from transaction_unit ptu
+                        inner join unit_cost puc on puc.serial_id = ptu.unit_cost_serial_id
+                        inner join transaction ptr on ptr.serial_id = ptu.transaction_serial_id
+                        where puc.unit_type_serial_id = uc.unit_type_serial_id
+                          and ptr.serial_id <> tr.serial_id
+                          and ptr.transaction_time <= tr.transaction_time
+                          and ptr.currency_serial_id = tr.currency_serial_id
+                          and ptr.org_serial_id = tr.org_serial_id
+                        order by ptr.transaction_time desc, ptr.serial_id desc
+                        limit $3
+                    ) r
+                ) w
+                where w.rn in ((w.cnt + 1) / 2, (w.cnt + 2) / 2)
+            ) recent on true
+            where tr.transaction_id = any($1)
+              and tr.org_serial_id = org_serial_id($2)
+              and recent.median_cost_per_unit <> 0
+            order by tr.transaction_time, tr.serial_id;",
+        )
+        .bind(transaction_ids)
+        .bind(ctx.org_id())
+        .bind(RECENT_COST_WINDOW);
+
+        let deviations = mm
+            .dbx()
+            .fetch_all(sqlx_query)
+            .await?
+            .into_iter()
+            .map(|(transaction_id, unit_type, cost_per_unit, median_cost)| {
+                let median_cost_per_unit = median_cost.normalized();
+                let deviation_pct = ((&cost_per_unit - &median_cost_per_unit)
+                    * BigDecimal::from(100)
+                    / &median_cost_per_unit)
+                    .round(2);
+                let flagged =
+                    deviation_pct.abs() > BigDecimal::from(threshold_pct);
+
+                UnitCostDeviation {
+                    transaction_id,
+                    unit_type,
+                    cost_per_unit,
+                    median_cost_per_unit,
+                    deviation_pct,
+                    flagged,
+                }
+            })
+            .collect();
+
+        Ok(deviations)
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::bill::BillBmc;
+    use crate::model::transaction::TransactionBmc;
+    use crate::model::user::{UserBmc, UserForCreate};
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_price_history_and_deviation_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_threshold_pct = 20;
+        let bill_id = BillBmc::create(
+            &ctx,
+            &mm,
+            serde_json::from_value(json!({ "sellerId": "0002" })).unwrap(),
+        )
+        .await?;
+        let mut transaction_ids = Vec::new();
+        for (day, cost) in [(1, 40), (2, 42), (3, 41), (4, 55)] {
+            let transactions = TransactionBmc::create(
+                &ctx,
+                &mm,
+                serde_json::from_value(json!([{
+                    "name": "onion",
+                    "transactionTime": format!("2025-01-0{day}T10:00:00Z"),
+                    "paymentMethod": "Cash",
+                    "unitCost": { "unitType": "test_unit-kg", "unit": 2, "costPerUnit": cost },
+                    "sellerCost": cost * 2,
+                    "billId": bill_id
+                }]))
+                .unwrap(),
+            )
+            .await?;
+            transaction_ids.push(transactions[0].transaction_id.clone());
+        }
+
+        // -- Exec
+        let history =
+            UnitBmc::get_price_history(&ctx, &mm, "test_unit-kg").await?;
+        let unit_types = UnitBmc::list(&ctx, &mm).await?;
+        let deviations = UnitBmc::check_cost_deviations(
+            &ctx,
+            &mm,
+            &transaction_ids,
+            fx_threshold_pct,
+        )
+        .await?;
+
+        // -- Check
+        let costs = history
+            .iter()
+            .map(|v| v.cost_per_unit.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(costs, [dec("40"), dec("42"), dec("41"), dec("55")]);
+        assert_eq!(history[0].seller_id.as_deref(), Some("0002"));
+        let kg = unit_types
+            .iter()
+            .find(|v| v.name == "test_unit-kg")
+            .ok_or("Should list the unit type")?;
+        assert_eq!(kg.transaction_count, 4);
+        assert_eq!(kg.latest_cost_per_unit, Some(dec("55")));
+        let [second, usual, hike] = &deviations[..] else {
+            panic!("Should skip the first, without previous cost");
+        };
+        assert_eq!(second.transaction_id, transaction_ids[1]);
+        assert_eq!(second.median_cost_per_unit, dec("40"));
+        assert_eq!(usual.transaction_id, transaction_ids[2]);
+        assert_eq!(usual.median_cost_per_unit, dec("41"));
+        assert!(!usual.flagged);
+        assert_eq!(hike.transaction_id, transaction_ids[3]);
+        assert_eq!(hike.deviation_pct, dec("34.15"));
+        assert!(hike.flagged);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_other_org_tax_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = _dev_utils::demo_ctx();
+        let root_ctx = Ctx::root_ctx();
+        let fx_other_user_id = _dev_utils::seed_user(
+            &root_ctx,
+            &mm,
+            UserForCreate {
+                name: "other".to_string(),
+                email: "other@example.com".to_string(),
+                pwd_clear: "welcome".to_string(),
+            },
+        )
+        .await?;
+        let fx_other_org_id =
+            UserBmc::first_by_user_id(&root_ctx, &mm, &fx_other_user_id)
+                .await?
+                .and_then(|v| v.org_id)
+                .ok_or("Should have an organisation")?;
+        let fx_other_ctx =
+            Ctx::new_with_org(&fx_other_user_id, &fx_other_org_id)?;
+        let fx_transactions = json!([{
+            "name": "paper",
+            "paymentMethod": "Cash",
+            "unitCost": { "unitType": "test_unit-ream", "unit": 1, "costPerUnit": 118 },
+            "sellerCost": 118
+        }]);
+        TransactionBmc::create(
+            &fx_other_ctx,
+            &mm,
+            serde_json::from_value(fx_transactions.clone())?,
+        )
+        .await?;
+
+        // -- Exec
+        let res = UnitBmc::update(
+            &ctx,
+            &mm,
+            "test_unit-ream",
+            serde_json::from_value(json!({ "taxRate": 18 }))?,
+        )
+        .await;
+
+        // -- Check
+        assert!(
+            matches!(
+                res,
+                Err(super::Error::Acs(acs::Error::NoPermission {
+                    privilege: Privilege::UnitUpdate
+                }))
+            ),
+            "{res:?}"
+        );
+        let transactions = TransactionBmc::create(
+            &fx_other_ctx,
+            &mm,
+            serde_json::from_value(fx_transactions)?,
+        )
+        .await?;
+        assert!(transactions[0].tax.is_none(), "no default tax rate");
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -1,4 +1,7 @@
+use crate::model::acs;
+use crate::model::org;
 use crate::model::store::dbx::{self, UniqueViolation};
+use crate::model::validation;
 use lib_auth::pwd;
 use serde::Serialize;
 use serde_with::serde_as;
@@ -13,14 +16,29 @@ pub enum Error {
     },
     UserEmailNotFound,
 
+    /// The `token_salt` was rotated since read, e.g. by a concurrent reset
+    /// with the same token.
+    UserTokenSaltChanged {
+        user_id: String,
+    },
+
     // // -- DB
     // UniqueViolation { table: String, constraint: String },
     UserNotUnique,
 
     // -- Modules
+    #[error(transparent)]
+    Acs(#[from] acs::Error),
+
     #[error(transparent)]
     Pwd(#[from] pwd::Error),
 
+    #[error(transparent)]
+    Validation(#[from] validation::Error),
+
+    #[error(transparent)]
+    Org(#[from] org::Error),
+
     #[error(transparent)]
     Dbx(dbx::Error),
 }
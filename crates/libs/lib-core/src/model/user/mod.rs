@@ -1,7 +1,14 @@
-use crate::{ctx::Ctx, model::ModelManager};
+use crate::{
+    ctx::Ctx,
+    model::{
+        ModelManager,
+        acs::{self, Privilege},
+        org::OrgBmc,
+        validation::Validator,
+    },
+};
 use lib_auth::pwd::{self, ContentToHash};
-use lib_utils::{b58::b58_encode, time::TimeRfc3339};
-use rand::RngCore as _;
+use lib_utils::{id::IdSpec, time::TimeRfc3339};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -59,30 +66,32 @@ pub struct UserForAuth {
 
     // -- token info
     pub token_salt: Uuid,
+
+    /// The active organisation, falling back to the first one joined when the
+    /// user is no longer a member of it.
+    pub org_id: Option<String>,
 }
 
 // endregion: --- User Types
 
 // region:    --- UserBmc
 
+/// Public `user_id` of new users.
+const USER_ID: IdSpec = IdSpec::new("U", 10);
+
+/// Minimum length of a new password, on reset or change.
+const MIN_PWD_LEN: usize = 8;
+
 pub struct UserBmc;
 
 impl UserBmc {
-    fn generate_user_id() -> String {
-        let mut key = [0u8; 64]; // 512 bits = 64 bytes
-        rand::rng().fill_bytes(&mut key);
-        b58_encode(key)
-            .chars()
-            .take(10)
-            .collect::<String>()
-            .to_uppercase()
-    }
-
     pub async fn create(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         user_c: UserForCreate,
     ) -> Result<String> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let UserForCreate {
             name,
             pwd_clear,
@@ -94,7 +103,6 @@ impl UserBmc {
 
         mm.dbx().begin_txn().await?;
 
-        let user_id = UserBmc::generate_user_id();
         let pwd_salt = pwd::generate_random_uuid_v4().await?;
 
         let pwd = pwd::hash_pwd(ContentToHash {
@@ -105,18 +113,24 @@ impl UserBmc {
 
         let now = TimeRfc3339::now_utc().inner();
 
-        let sqlx_query = sqlx::query_as::<_, (i64,)>(
-            "insert into users (user_id, name, email, ctime, mtime) values ($1, $2, $3,
-            $4, $4 ) returning serial_id",
-        )
-        .bind(user_id.as_str())
-        .bind(name)
-        .bind(email)
-        .bind(now);
-
         // NOTE: For now, we will use the _txn for all create.
         //       We could have a with_txn as function argument if perf is an issue (it should not be)
-        let (serial_id,) = mm.dbx().fetch_one(sqlx_query).await?;
+        let (name, email) = (&name, &email);
+        let dbx = mm.dbx();
+        let (serial_id, user_id) = dbx
+            .retry_on_id_collision("users_user_id_key", || async move {
+                let sqlx_query = sqlx::query_as::<_, (i64, String)>(
+                    "insert into users (user_id, name, email, ctime, mtime) values ($1, $2, $3,
+                    $4, $4 ) returning serial_id, user_id",
+                )
+                .bind(USER_ID.generate())
+                .bind(name)
+                .bind(email)
+                .bind(now);
+
+                dbx.fetch_one(sqlx_query).await
+            })
+            .await?;
 
         let sqlx_query = sqlx::query(
             "insert into password_auth (user_serial_id, pwd, pwd_salt, ctime, mtime) values ($1, $2, $3, $4, $4)",
@@ -128,6 +142,18 @@ impl UserBmc {
 
         mm.dbx().execute(sqlx_query).await?;
 
+        // -- The own organisation of the user, active until switched.
+        let org_id = OrgBmc::insert_with_owner(&mm, &user_id, name).await?;
+
+        let sqlx_query = sqlx::query(
+            "update users set active_org_serial_id = org_serial_id($2)
+            where serial_id = $1;",
+        )
+        .bind(serial_id)
+        .bind(org_id);
+
+        mm.dbx().execute(sqlx_query).await?;
+
         // Commit the transaction
         mm.dbx().commit_txn().await?;
 
@@ -135,7 +161,17 @@ impl UserBmc {
     }
 
     pub async fn get_by_user_id(
-        _ctx: &Ctx,
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: &str,
+    ) -> Result<UserForLogin> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
+        Self::fetch_by_user_id(mm, user_id).await
+    }
+
+    /// Unchecked, for the password updates.
+    async fn fetch_by_user_id(
         mm: &ModelManager,
         user_id: &str,
     ) -> Result<UserForLogin> {
@@ -157,10 +193,12 @@ impl UserBmc {
     }
 
     pub async fn get_by_email(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         email: &str,
     ) -> Result<UserForLogin> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let sqlx_query = sqlx::query_as::<_, UserForLogin>(
             "select u.user_id, ut.typ, u.name, u.email, pa.pwd, pa.pwd_salt, pa.token_salt from users u
                 inner join user_type ut on u.user_type_serial_id = ut.serial_id
@@ -179,14 +217,23 @@ impl UserBmc {
     }
 
     pub async fn get_user_auth_by_email(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         email: &str,
     ) -> Result<Option<UserForAuth>> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let sqlx_query = sqlx::query_as::<_, UserForAuth>(
-            "select u.user_id, ut.typ, u.name, u.email, pa.token_salt from users u
+            "select u.user_id, ut.typ, u.name, u.email, pa.token_salt, ao.org_id from users u
                 inner join user_type ut on u.user_type_serial_id = ut.serial_id
                 inner join password_auth pa on u.serial_id = pa.user_serial_id
+                left join lateral (
+                  select o.org_id from org_member om
+                  inner join organisation o on o.serial_id = om.org_serial_id
+                  where om.user_serial_id = u.serial_id
+                  order by om.org_serial_id = u.active_org_serial_id desc nulls last, om.ctime
+                  limit 1
+                ) ao on true
                 where u.email = $1 limit 1;",
         )
         .bind(email);
@@ -197,14 +244,23 @@ impl UserBmc {
     }
 
     pub async fn first_by_user_id(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         user_id: &str,
     ) -> Result<Option<UserForAuth>> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let sqlx_query = sqlx::query_as::<_, UserForAuth>(
-            "select u.user_id, ut.typ, u.name, u.email, pa.token_salt from users u
+            "select u.user_id, ut.typ, u.name, u.email, pa.token_salt, ao.org_id from users u
                 inner join user_type ut on u.user_type_serial_id = ut.serial_id
                 inner join password_auth pa on u.serial_id = pa.user_serial_id
+                left join lateral (
+                  select o.org_id from org_member om
+                  inner join organisation o on o.serial_id = om.org_serial_id
+                  where om.user_serial_id = u.serial_id
+                  order by om.org_serial_id = u.active_org_serial_id desc nulls last, om.ctime
+                  limit 1
+                ) ao on true
                 where u.user_id = $1 limit 1;",
         )
         .bind(user_id);
@@ -215,14 +271,23 @@ impl UserBmc {
     }
 
     pub async fn first_by_email(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         email: &str,
     ) -> Result<Option<UserForAuth>> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let sqlx_query = sqlx::query_as::<_, UserForAuth>(
-            "select u.user_id, ut.typ, u.name, u.email, pa.token_salt from users u
+            "select u.user_id, ut.typ, u.name, u.email, pa.token_salt, ao.org_id from users u
                 inner join user_type ut on u.user_type_serial_id = ut.serial_id
                 inner join password_auth pa on u.serial_id = pa.user_serial_id
+                left join lateral (
+                  select o.org_id from org_member om
+                  inner join organisation o on o.serial_id = om.org_serial_id
+                  where om.user_serial_id = u.serial_id
+                  order by om.org_serial_id = u.active_org_serial_id desc nulls last, om.ctime
+                  limit 1
+                ) ao on true
                 where u.email = $1 limit 1;",
         )
         .bind(email);
@@ -233,11 +298,13 @@ impl UserBmc {
     }
 
     pub async fn list(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         // filter: Option<Vec<UserFilter>>,
         // list_options: Option<ListOptions>,
     ) -> Result<Vec<User>> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let sqlx_query = sqlx::query_as::<_, User>(
             "select u.user_id, u.name, u.email, ut.typ from users u
             inner join user_type ut on u.user_type_serial_id = ut.serial_id;",
@@ -254,8 +321,10 @@ impl UserBmc {
         user_id: &str,
         pwd_clear: &str,
     ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         // -- Prep password
-        let user: UserForLogin = Self::get_by_user_id(ctx, mm, user_id).await?;
+        let user: UserForLogin = Self::fetch_by_user_id(mm, user_id).await?;
 
         let pwd = pwd::hash_pwd(ContentToHash {
             content: pwd_clear.to_string(),
@@ -281,6 +350,127 @@ impl UserBmc {
         Ok(())
     }
 
+    /// Set the password and rotate the `token_salt`, so every token of the
+    /// user (web sessions, mailed links) is invalidated.
+    ///
+    /// `token_salt` is the one the caller validated the token with, it fails
+    /// with `UserTokenSaltChanged` when rotated since, so a reset token is
+    /// used once even by concurrent requests.
+    ///
+    /// Returns the new `token_salt`.
+    pub async fn reset_pwd(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: &str,
+        token_salt: Uuid,
+        pwd_clear: &str,
+    ) -> Result<Uuid> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
+        let mut validator = Validator::default();
+        validator.min_len("", "password", pwd_clear, MIN_PWD_LEN);
+        validator.finish()?;
+
+        // -- Prep password
+        let user: UserForLogin = Self::fetch_by_user_id(mm, user_id).await?;
+
+        let pwd = pwd::hash_pwd(ContentToHash {
+            content: pwd_clear.to_string(),
+            salt: user.pwd_salt,
+        })
+        .await?;
+
+        let new_token_salt = pwd::generate_random_uuid_v4().await?;
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query(
+            "update password_auth set
+              pwd = $2,
+              token_salt = $3,
+              mtime = $4
+            where user_serial_id = (select serial_id from users
+              where user_id = $1)
+              and token_salt = $5;",
+        )
+        .bind(user_id)
+        .bind(pwd)
+        .bind(new_token_salt)
+        .bind(now)
+        .bind(token_salt);
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+        if count == 0 {
+            return Err(Error::UserTokenSaltChanged {
+                user_id: user_id.into(),
+            });
+        }
+
+        Ok(new_token_salt)
+    }
+
+    /// Rotate the `token_salt`, so every token of the user (web sessions,
+    /// mailed links) is invalidated.
+    ///
+    /// Returns the new `token_salt`.
+    pub async fn rotate_token_salt(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: &str,
+    ) -> Result<Uuid> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
+        let token_salt = pwd::generate_random_uuid_v4().await?;
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query(
+            "update password_auth set
+              token_salt = $2,
+              mtime = $3
+            where user_serial_id = (select serial_id from users
+              where user_id = $1);",
+        )
+        .bind(user_id)
+        .bind(token_salt)
+        .bind(now);
+
+        let count = mm.dbx().execute(sqlx_query).await?;
+        if count == 0 {
+            return Err(Error::UserNotFound {
+                user_id: user_id.into(),
+            });
+        }
+
+        Ok(token_salt)
+    }
+
+    /// Promote the `UnVarifiedUser` to `User` once their email is verified,
+    /// which lifts the limits of `acs`. Other types stay as they are.
+    pub async fn verify_email(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: &str,
+    ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
+        let now = TimeRfc3339::now_utc().inner();
+
+        let sqlx_query = sqlx::query(
+            "update users set
+              user_type_serial_id = (select serial_id from user_type
+                where typ = 'User'),
+              mtime = $2
+            where user_id = $1
+              and user_type_serial_id = (select serial_id from user_type
+                where typ = 'UnVarifiedUser');",
+        )
+        .bind(user_id)
+        .bind(now);
+
+        let _count = mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
     /// TODO: For User, deletion will require a soft-delete approach:
     ///       - Set `deleted: true`.
     ///       - Change `username` to "DELETED-_user_id_".
@@ -289,10 +479,12 @@ impl UserBmc {
     ///       - It's likely necessary to record this action in a `um_change_log` (a user management change audit table).
     ///       - Remove or clean up any user-specific assets (messages, etc.).
     pub async fn delete(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         user_id: &str,
     ) -> Result<()> {
+        acs::require(ctx, mm, Privilege::UserAdmin).await?;
+
         let sqlx_query = sqlx::query(
             "delete from users 
                 where user_id = $1;",
@@ -351,6 +543,100 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_verify_email_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let user_id = UserBmc::create(
+            &ctx,
+            &mm,
+            UserForCreate {
+                name: "test_verify_email_ok-user-01".to_string(),
+                email: "test_verify_email_ok-user01@example.com".to_string(),
+                pwd_clear: "welcome".to_string(),
+            },
+        )
+        .await?;
+        let user = UserBmc::get_by_user_id(&ctx, &mm, &user_id).await?;
+        assert_eq!(user.typ, UserTyp::UnVarifiedUser.to_string());
+
+        // -- Exec
+        UserBmc::verify_email(&ctx, &mm, &user_id).await?;
+        UserBmc::verify_email(&ctx, &mm, "root").await?;
+
+        // -- Check
+        let user = UserBmc::get_by_user_id(&ctx, &mm, &user_id).await?;
+        assert_eq!(user.typ, UserTyp::User.to_string());
+        let root = UserBmc::get_by_user_id(&ctx, &mm, "root").await?;
+        assert_eq!(root.typ, UserTyp::Sys.to_string());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_reset_pwd_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_pwd_clear = "test_reset_pwd_ok new pwd";
+        let user = UserBmc::get_by_user_id(&ctx, &mm, "demo1").await?;
+
+        // -- Exec
+        let rotated_salt =
+            UserBmc::rotate_token_salt(&ctx, &mm, "demo1").await?;
+        let reset_salt =
+            UserBmc::reset_pwd(&ctx, &mm, "demo1", rotated_salt, fx_pwd_clear)
+                .await?;
+
+        // -- Check
+        let reset_user = UserBmc::get_by_user_id(&ctx, &mm, "demo1").await?;
+        assert_ne!(rotated_salt, user.token_salt);
+        assert_ne!(reset_salt, rotated_salt);
+        assert_eq!(reset_user.token_salt, reset_salt);
+        pwd::validate_pwd(
+            ContentToHash {
+                content: fx_pwd_clear.to_string(),
+                salt: reset_user.pwd_salt,
+            },
+            reset_user.pwd,
+        )
+        .await?;
+        assert!(matches!(
+            UserBmc::rotate_token_salt(&ctx, &mm, "nobody").await,
+            Err(super::Error::UserNotFound { .. })
+        ));
+
+        // -- Exec & Check single use
+        let res = UserBmc::reset_pwd(
+            &ctx,
+            &mm,
+            "demo1",
+            rotated_salt,
+            "test_reset_pwd_ok other pwd",
+        )
+        .await;
+        assert!(
+            matches!(res, Err(super::Error::UserTokenSaltChanged { .. })),
+            "{res:?}"
+        );
+
+        // -- Exec & Check too short
+        let res =
+            UserBmc::reset_pwd(&ctx, &mm, "demo1", reset_salt, "short").await;
+        let Err(super::Error::Validation(
+            crate::model::validation::Error::Validation { errors },
+        )) = res
+        else {
+            return Err("Should fail validation".into());
+        };
+        assert_eq!(errors[0].path, "password");
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_first_ok_demo1() -> Result<()> {
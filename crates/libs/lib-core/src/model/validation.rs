@@ -0,0 +1,316 @@
+//! Field level validation of the `*ForCreate` structs, run by the `*Bmc`
+//! functions before anything reaches the database.
+//!
+//! All the failing fields are reported, not only the first one. Paths follow
+//! the json sent by the client, e.g. `[2].unitCost.unit` for the unit of the
+//! third transaction of a batch.
+
+use bigdecimal::BigDecimal;
+use serde::Serialize;
+use time::{Duration, OffsetDateTime};
+
+// region:    --- Error
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug, Serialize, strum_macros::Display)]
+pub enum Error {
+    Validation { errors: Vec<FieldError> },
+}
+
+// endregion: --- Error
+
+/// Largest amount a `numeric(12, 2)` column can hold.
+const MAX_AMOUNT: i64 = 9_999_999_999;
+
+/// Client clocks may be a bit ahead of the server one.
+const MAX_CLOCK_SKEW: Duration = Duration::minutes(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldErrorCode {
+    Required,
+    TooShort,
+    TooLong,
+    Negative,
+    NotPositive,
+    TooLarge,
+    InFuture,
+    Invalid,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldError {
+    pub path: String,
+    pub code: FieldErrorCode,
+    pub message: String,
+}
+
+pub trait Validate {
+    /// Push the errors of `self` to `validator`, `path` being where `self`
+    /// sits in the request body (empty for the root).
+    fn validate_at(&self, path: &str, validator: &mut Validator);
+
+    fn validate(&self) -> Result<()> {
+        let mut validator = Validator::default();
+        self.validate_at("", &mut validator);
+        validator.finish()
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_at(&self, path: &str, validator: &mut Validator) {
+        for (idx, item) in self.iter().enumerate() {
+            item.validate_at(&format!("{path}[{idx}]"), validator);
+        }
+    }
+}
+
+/// Path of `field` within `path`.
+pub fn join_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{path}.{field}")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    fn check(
+        &mut self,
+        is_valid: bool,
+        path: &str,
+        field: &str,
+        code: FieldErrorCode,
+        message: impl FnOnce() -> String,
+    ) -> &mut Self {
+        if !is_valid {
+            self.errors.push(FieldError {
+                path: join_path(path, field),
+                code,
+                message: message(),
+            });
+        }
+        self
+    }
+
+    /// Not empty once trimmed.
+    pub fn required(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: &str,
+    ) -> &mut Self {
+        self.check(
+            !value.trim().is_empty(),
+            path,
+            field,
+            FieldErrorCode::Required,
+            || "must not be empty".to_string(),
+        )
+    }
+
+    /// At least `min` characters.
+    pub fn min_len(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: &str,
+        min: usize,
+    ) -> &mut Self {
+        let len = value.chars().count();
+        self.check(len >= min, path, field, FieldErrorCode::TooShort, || {
+            format!("must be at least {min} characters")
+        })
+    }
+
+    /// At most `max` characters, as counted by a `varchar(max)` column.
+    pub fn max_len(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: Option<&str>,
+        max: usize,
+    ) -> &mut Self {
+        let len = value.map(|v| v.chars().count()).unwrap_or_default();
+        self.check(len <= max, path, field, FieldErrorCode::TooLong, || {
+            format!("must be at most {max} characters")
+        })
+    }
+
+    pub fn positive(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: &BigDecimal,
+    ) -> &mut Self {
+        self.check(
+            value > &BigDecimal::from(0),
+            path,
+            field,
+            FieldErrorCode::NotPositive,
+            || "must be greater than 0".to_string(),
+        )
+    }
+
+    /// A non negative amount that fits a `numeric(12, 2)` column.
+    pub fn amount(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: &BigDecimal,
+    ) -> &mut Self {
+        self.check(
+            value >= &BigDecimal::from(0),
+            path,
+            field,
+            FieldErrorCode::Negative,
+            || "must not be negative".to_string(),
+        )
+        .check(
+            value <= &BigDecimal::from(MAX_AMOUNT),
+            path,
+            field,
+            FieldErrorCode::TooLarge,
+            || format!("must be at most {MAX_AMOUNT}"),
+        )
+    }
+
+    /// A percent (e.g., a tax rate) from 0 to 100, when set.
+    pub fn percent(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: Option<&BigDecimal>,
+    ) -> &mut Self {
+        let Some(value) = value else {
+            return self;
+        };
+        self.check(
+            value >= &BigDecimal::from(0),
+            path,
+            field,
+            FieldErrorCode::Negative,
+            || "must not be negative".to_string(),
+        )
+        .check(
+            value <= &BigDecimal::from(100),
+            path,
+            field,
+            FieldErrorCode::TooLarge,
+            || "must be at most 100".to_string(),
+        )
+    }
+
+    pub fn not_in_future(
+        &mut self,
+        path: &str,
+        field: &str,
+        value: Option<OffsetDateTime>,
+    ) -> &mut Self {
+        let max = OffsetDateTime::now_utc() + MAX_CLOCK_SKEW;
+        self.check(
+            value.is_none_or(|v| v <= max),
+            path,
+            field,
+            FieldErrorCode::InFuture,
+            || "must not be in the future".to_string(),
+        )
+    }
+
+    /// Any other rule, `message` telling what is expected.
+    pub fn expect(
+        &mut self,
+        path: &str,
+        field: &str,
+        is_valid: bool,
+        message: &str,
+    ) -> &mut Self {
+        self.check(is_valid, path, field, FieldErrorCode::Invalid, || {
+            message.to_string()
+        })
+    }
+
+    pub fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Validation {
+                errors: self.errors,
+            })
+        }
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    struct FxItem {
+        name: String,
+        cost: BigDecimal,
+    }
+
+    impl Validate for FxItem {
+        fn validate_at(&self, path: &str, validator: &mut Validator) {
+            validator
+                .required(path, "name", &self.name)
+                .max_len(path, "name", Some(&self.name), 4)
+                .amount(path, "cost", &self.cost);
+        }
+    }
+
+    #[test]
+    fn test_validate_vec_paths_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_items = vec![
+            FxItem {
+                name: "ok".to_string(),
+                cost: BigDecimal::from(1),
+            },
+            FxItem {
+                name: " ".to_string(),
+                cost: BigDecimal::from(-1),
+            },
+            FxItem {
+                name: "too long".to_string(),
+                cost: BigDecimal::from(MAX_AMOUNT) + BigDecimal::from(1),
+            },
+        ];
+
+        // -- Exec
+        let Err(super::Error::Validation { errors }) = fx_items.validate()
+        else {
+            return Err("Should fail validation".into());
+        };
+
+        // -- Check
+        let errors = errors
+            .iter()
+            .map(|v| (v.path.as_str(), v.code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("[1].name", FieldErrorCode::Required),
+                ("[1].cost", FieldErrorCode::Negative),
+                ("[2].name", FieldErrorCode::TooLong),
+                ("[2].cost", FieldErrorCode::TooLarge),
+            ]
+        );
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
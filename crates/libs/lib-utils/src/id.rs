@@ -0,0 +1,176 @@
+//! Public ids of the entities, e.g. `B7KX2M9QRT4W`.
+//!
+//! Ids use the Crockford base32 alphabet (no `I`, `L`, `O`, `U`), so they are
+//! easy to read out loud and type. The optional last character is a Luhn mod
+//! 32 checksum, catching single character typos and most adjacent swaps.
+
+use rand::Rng as _;
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[derive(Clone, Copy, Debug)]
+pub struct IdSpec {
+    prefix: &'static str,
+    /// Number of random characters, excluding the prefix and the checksum.
+    len: usize,
+    checksum: bool,
+}
+
+impl IdSpec {
+    pub const fn new(prefix: &'static str, len: usize) -> Self {
+        IdSpec {
+            prefix,
+            len,
+            checksum: false,
+        }
+    }
+
+    pub const fn with_checksum(self) -> Self {
+        IdSpec {
+            checksum: true,
+            ..self
+        }
+    }
+
+    pub fn generate(&self) -> String {
+        let mut rng = rand::rng();
+        let mut body = (0..self.len)
+            .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())])
+            .collect::<Vec<u8>>();
+
+        if self.checksum {
+            body.push(ALPHABET[luhn_check_value(&body)]);
+        }
+
+        // NOTE: The alphabet is ascii only.
+        format!("{}{}", self.prefix, String::from_utf8_lossy(&body))
+    }
+
+    /// True when `id` has the prefix, the length and the checksum of this
+    /// spec. It does not tell whether the id exists.
+    pub fn is_valid(&self, id: &str) -> bool {
+        let Some(body) = id.strip_prefix(self.prefix) else {
+            return false;
+        };
+        let body = body.as_bytes();
+        let expected_len = self.len + usize::from(self.checksum);
+
+        if body.len() != expected_len
+            || !body.iter().all(|v| ALPHABET.contains(v))
+        {
+            return false;
+        }
+
+        match body.split_last() {
+            Some((check, body)) if self.checksum => {
+                ALPHABET[luhn_check_value(body)] == *check
+            }
+            _ => true,
+        }
+    }
+
+    /// True when `id` has the prefix and the length of this spec but is not
+    /// valid, e.g. a generated id with a typo. Ids of another shape (e.g.,
+    /// created before this spec) are left to the lookup.
+    pub fn is_mistyped(&self, id: &str) -> bool {
+        let expected_len =
+            self.prefix.len() + self.len + usize::from(self.checksum);
+
+        id.starts_with(self.prefix)
+            && id.len() == expected_len
+            && !self.is_valid(id)
+    }
+}
+
+/// Luhn mod N check value of `body`, whose characters are in `ALPHABET`.
+fn luhn_check_value(body: &[u8]) -> usize {
+    let n = ALPHABET.len();
+    let sum = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, v)| {
+            let code_point =
+                ALPHABET.iter().position(|a| a == v).unwrap_or_default();
+            let addend = if idx % 2 == 0 {
+                code_point * 2
+            } else {
+                code_point
+            };
+            addend / n + addend % n
+        })
+        .sum::<usize>();
+
+    (n - sum % n) % n
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = core::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_id_generate_and_check_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_spec = IdSpec::new("B", 10).with_checksum();
+
+        // -- Exec
+        let id = fx_spec.generate();
+
+        // -- Check
+        assert_eq!(id.len(), 12);
+        assert!(id.starts_with('B'));
+        assert!(fx_spec.is_valid(&id));
+        assert!(!IdSpec::new("S", 10).with_checksum().is_valid(&id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_id_checksum_catches_typos_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_spec = IdSpec::new("", 10).with_checksum();
+        let fx_body = "7KX2M9QRT4";
+        let fx_id = format!(
+            "{fx_body}{}",
+            ALPHABET[luhn_check_value(fx_body.as_bytes())] as char
+        );
+
+        // -- Check
+        assert!(fx_spec.is_valid(&fx_id));
+        for typo in ["7KX2M9QRT5", "7KX2N9QRT4", "K7X2M9QRT4", "7KX2M9RQT4"] {
+            let check = &fx_id[fx_body.len()..];
+            assert!(!fx_spec.is_valid(&format!("{typo}{check}")), "{typo}");
+        }
+        assert!(!fx_spec.is_valid(&fx_id.to_lowercase()));
+        assert!(!fx_spec.is_valid(&fx_id[1..]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_id_is_mistyped_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_spec = IdSpec::new("S", 10).with_checksum();
+        let fx_id = fx_spec.generate();
+        let typo = if fx_id[1..].starts_with('A') {
+            "B"
+        } else {
+            "A"
+        };
+        let fx_typo = format!("S{typo}{}", &fx_id[2..]);
+
+        // -- Check
+        assert!(!fx_spec.is_mistyped(&fx_id));
+        assert!(fx_spec.is_mistyped(&fx_typo));
+        assert!(!fx_spec.is_mistyped("0001"), "older ids are looked up");
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
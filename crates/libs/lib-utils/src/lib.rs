@@ -3,4 +3,6 @@
 pub mod b58;
 pub mod b64;
 pub mod envs;
+pub mod id;
+pub mod money;
 pub mod time;
@@ -0,0 +1,148 @@
+//! Amounts with their ISO 4217 currency, e.g. `12.50 INR`.
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use std::fmt;
+
+// region:    --- Error
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug, strum_macros::Display)]
+pub enum Error {
+    CurrencyMismatch { left: Currency, right: Currency },
+}
+
+// endregion: --- Error
+
+/// The currencies we operate in.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    PartialEq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::IntoStaticStr,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
+#[strum(serialize_all = "UPPERCASE", ascii_case_insensitive)]
+pub enum Currency {
+    #[default]
+    Inr,
+    Usd,
+}
+
+impl Currency {
+    /// The ISO 4217 code, e.g. `INR`.
+    pub fn code(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Number of digits after the decimal point, e.g. 2 for paise.
+    pub fn minor_units(&self) -> i64 {
+        match self {
+            Self::Inr | Self::Usd => 2,
+        }
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Money {
+    pub amount: BigDecimal,
+    pub currency: Currency,
+}
+
+impl Money {
+    /// Rounded (half even) to the minor units of `currency`.
+    pub fn new(amount: BigDecimal, currency: Currency) -> Self {
+        let amount = amount
+            .with_scale_round(currency.minor_units(), RoundingMode::HalfEven);
+        Money { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(BigDecimal::from(0), currency)
+    }
+
+    /// Convert to `to`, `rate` being the price of one unit of this currency
+    /// in `to`.
+    pub fn convert(&self, rate: &BigDecimal, to: Currency) -> Money {
+        if self.currency == to {
+            return self.clone();
+        }
+        Self::new(&self.amount * rate, to)
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money> {
+        self.ensure_same_currency(other)?;
+        Ok(Self::new(&self.amount + &other.amount, self.currency))
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Money> {
+        self.ensure_same_currency(other)?;
+        Ok(Self::new(&self.amount - &other.amount, self.currency))
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<()> {
+        if self.currency != other.currency {
+            return Err(Error::CurrencyMismatch {
+                left: self.currency,
+                right: other.currency,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    pub type Result<T> = std::result::Result<T, Error>;
+    pub type Error = Box<dyn std::error::Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_money_convert_and_add_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let dec = |v: &str| v.parse::<BigDecimal>().unwrap();
+        let fx_usd = Money::new(dec("12.345"), Currency::Usd);
+        let fx_inr = Money::new(dec("100"), Currency::Inr);
+
+        // -- Exec
+        let converted = fx_usd.convert(&dec("83.1234"), Currency::Inr);
+        let total = converted.checked_add(&fx_inr)?;
+
+        // -- Check
+        assert_eq!(fx_usd.to_string(), "12.34 USD", "half even");
+        assert_eq!(converted.amount, dec("1025.74"));
+        assert_eq!(total.to_string(), "1125.74 INR");
+        assert!(fx_usd.checked_sub(&fx_inr).is_err());
+        assert_eq!("usd".parse::<Currency>()?, Currency::Usd);
+        assert_eq!(Currency::Inr.code(), "INR");
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -16,6 +16,8 @@ pub struct WebConfig {
     pub TEMPLATE_FOLDER: String,
     pub STATIC_FOLDER: String,
     pub HOST_PORT: SocketAddr,
+    /// Of the links in the mails, e.g. `http://localhost:8080`.
+    pub BASE_URL: String,
 }
 
 impl WebConfig {
@@ -24,6 +26,7 @@ impl WebConfig {
             TEMPLATE_FOLDER: get_env("SERVICE_TEMPLATE_FOLDER")?,
             STATIC_FOLDER: get_env("SERVICE_STATIC_FOLDER")?,
             HOST_PORT: get_env_parse("SERVICE_HOST_PORT")?,
+            BASE_URL: get_env("SERVICE_BASE_URL")?,
         })
     }
 }
@@ -4,7 +4,9 @@ use axum::extract::rejection::{FormRejection, JsonRejection};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use lib_auth::{pwd, token};
+use lib_core::mail;
 use lib_core::model;
+use lib_core::model::validation::{self, FieldError};
 use serde::Serialize;
 use serde_with::{DisplayFromStr, serde_as};
 use std::sync::Arc;
@@ -40,6 +42,17 @@ pub enum Error {
     #[error("LoginFailPwdNotMatching: user_id: {user_id}")]
     LoginFailPwdNotMatching { user_id: String },
 
+    // -- Email Verification
+    #[error("VerifyEmailFailTokenInvalid")]
+    VerifyEmailFailTokenInvalid,
+
+    // -- Password
+    #[error("PwdResetFailTokenInvalid")]
+    PwdResetFailTokenInvalid,
+
+    #[error("PwdChangeFailPwdNotMatching: user_id: {user_id}")]
+    PwdChangeFailPwdNotMatching { user_id: String },
+
     // -- CtxExtError
     #[error(transparent)]
     CtxExt(#[from] middleware::mw_auth::CtxExtError),
@@ -48,6 +61,13 @@ pub enum Error {
     #[error("ReqStampNotInReqExt")]
     ReqStampNotInReqExt,
 
+    // -- Idempotency
+    #[error("IdempotencyKeyInvalid")]
+    IdempotencyKeyInvalid,
+
+    #[error("IdempotencyResponseBody: {0}")]
+    IdempotencyResponseBody(String),
+
     // -- Modules
     #[error(transparent)]
     Model(#[from] model::Error),
@@ -58,6 +78,9 @@ pub enum Error {
     #[error(transparent)]
     Token(#[from] token::Error),
 
+    #[error(transparent)]
+    Mail(#[from] mail::Error),
+
     // -- External Modules
     #[error(transparent)]
     SerdeJson(
@@ -193,10 +216,136 @@ impl Error {
             // -- Auth
             CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
 
+            // -- Email Verification
+            VerifyEmailFailTokenInvalid => {
+                (StatusCode::BAD_REQUEST, ClientError::VERIFY_EMAIL_FAIL)
+            }
+
+            // -- Password
+            PwdResetFailTokenInvalid => {
+                (StatusCode::BAD_REQUEST, ClientError::PWD_RESET_FAIL)
+            }
+            PwdChangeFailPwdNotMatching { .. } => {
+                (StatusCode::FORBIDDEN, ClientError::PWD_NOT_MATCHING)
+            }
+
+            // -- Idempotency
+            IdempotencyKeyInvalid => (
+                StatusCode::BAD_REQUEST,
+                ClientError::IDEMPOTENCY_KEY_INVALID,
+            ),
+
+            Model(model::Error::Idempotency(
+                model::idempotency::Error::IdempotencyKeyReused { .. },
+            )) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::IDEMPOTENCY_KEY_REUSED,
+            ),
+
+            Model(model::Error::Idempotency(
+                model::idempotency::Error::IdempotencyKeyInProgress { .. },
+            )) => (StatusCode::CONFLICT, ClientError::IDEMPOTENCY_KEY_IN_USE),
+
+            // -- Access control
+            Model(model::Error::Seller(model::seller::Error::Acs(acs)))
+            | Model(model::Error::Bill(model::bill::Error::Acs(acs)))
+            | Model(model::Error::Payment(model::payment::Error::Acs(acs)))
+            | Model(model::Error::Transaction(
+                model::transaction::Error::Acs(acs),
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Acs(acs)))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::Acs(acs),
+            )))
+            | Model(model::Error::Unit(model::unit::Error::Acs(acs)))
+            | Model(model::Error::ExchangeRate(
+                model::exchange_rate::Error::Acs(acs),
+            ))
+            | Model(model::Error::User(model::user::Error::Acs(acs))) => {
+                match acs {
+                    model::acs::Error::NoPermission { privilege } => (
+                        StatusCode::FORBIDDEN,
+                        ClientError::NO_PERMISSION {
+                            privilege: privilege.to_string(),
+                        },
+                    ),
+                    model::acs::Error::EmailNotVerified { privilege } => (
+                        StatusCode::FORBIDDEN,
+                        ClientError::EMAIL_NOT_VERIFIED {
+                            privilege: privilege.to_string(),
+                        },
+                    ),
+                    model::acs::Error::Dbx(_) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        ClientError::SERVICE_ERROR,
+                    ),
+                }
+            }
+
+            // -- Organisation
+            Model(model::Error::Org(model::org::Error::OrgNotFound {
+                org_id,
+            }))
+            | Model(model::Error::User(model::user::Error::Org(
+                model::org::Error::OrgNotFound { org_id },
+            ))) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "organisation",
+                    id: org_id.to_string(),
+                },
+            ),
+
+            Model(model::Error::Org(
+                model::org::Error::OrgMemberNotFound { user_id_or_email },
+            ))
+            | Model(model::Error::User(model::user::Error::Org(
+                model::org::Error::OrgMemberNotFound { user_id_or_email },
+            ))) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "member",
+                    id: user_id_or_email.to_string(),
+                },
+            ),
+
+            Model(model::Error::Org(
+                model::org::Error::OrgMemberAlreadyExists,
+            ))
+            | Model(model::Error::User(model::user::Error::Org(
+                model::org::Error::OrgMemberAlreadyExists,
+            ))) => {
+                (StatusCode::CONFLICT, ClientError::ORG_MEMBER_ALREADY_EXISTS)
+            }
+
+            Model(model::Error::Org(model::org::Error::OrgRoleRequired {
+                role,
+            }))
+            | Model(model::Error::User(model::user::Error::Org(
+                model::org::Error::OrgRoleRequired { role },
+            ))) => (
+                StatusCode::FORBIDDEN,
+                ClientError::ORG_ROLE_REQUIRED {
+                    role: role.to_string(),
+                },
+            ),
+
+            Model(model::Error::Org(model::org::Error::OrgLastOwner {
+                ..
+            }))
+            | Model(model::Error::User(model::user::Error::Org(
+                model::org::Error::OrgLastOwner { .. },
+            ))) => (StatusCode::CONFLICT, ClientError::ORG_LAST_OWNER),
+
             // -- Model
             Model(model::Error::User(model::user::Error::UserNotUnique)) => {
                 (StatusCode::CONFLICT, ClientError::USER_ALREADY_EXISTS)
             }
+            // The session was invalidated meanwhile, e.g. by a concurrent
+            // password change.
+            Model(model::Error::User(
+                model::user::Error::UserTokenSaltChanged { .. },
+            )) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
             Model(model::Error::User(
                 model::user::Error::UserEmailNotFound,
             ))
@@ -204,6 +353,362 @@ impl Error {
                 ..
             })) => (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL),
 
+            Model(model::Error::Seller(
+                model::seller::Error::SellerNotFound { name_or_id },
+            ))
+            | Model(model::Error::Bill(model::bill::Error::Seller(
+                model::seller::Error::SellerNotFound { name_or_id },
+            ))) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "seller",
+                    id: name_or_id.to_string(),
+                },
+            ),
+
+            Model(model::Error::Seller(
+                model::seller::Error::SellerNotUnique,
+            )) => (StatusCode::CONFLICT, ClientError::SELLER_ALREADY_EXISTS),
+
+            Model(model::Error::Seller(
+                model::seller::Error::SellerMergeSame { .. },
+            )) => (StatusCode::BAD_REQUEST, ClientError::SELLER_MERGE_SAME),
+
+            Model(model::Error::Tag(model::tag::Error::TagNotFound {
+                name,
+            })) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "tag",
+                    id: name.to_string(),
+                },
+            ),
+
+            Model(model::Error::Tag(model::tag::Error::TagNotUnique)) => {
+                (StatusCode::CONFLICT, ClientError::TAG_ALREADY_EXISTS)
+            }
+
+            Model(model::Error::Tag(model::tag::Error::TagMergeSame {
+                ..
+            })) => (StatusCode::BAD_REQUEST, ClientError::TAG_MERGE_SAME),
+
+            Model(model::Error::Tag(model::tag::Error::TagParentCycle {
+                ..
+            })) => (StatusCode::BAD_REQUEST, ClientError::TAG_PARENT_CYCLE),
+
+            Model(model::Error::Unit(
+                model::unit::Error::UnitTypeNotFound { name },
+            )) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "unit_type",
+                    id: name.to_string(),
+                },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::TransactionNotFound {
+                    transaction_id,
+                },
+            ))
+            | Model(model::Error::Transaction(
+                model::transaction::Error::TransactionOriginalNotFound {
+                    transaction_id,
+                },
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::TransactionNotFound {
+                    transaction_id,
+                }
+                | model::transaction::Error::TransactionOriginalNotFound {
+                    transaction_id,
+                },
+            ))) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "transaction",
+                    id: transaction_id.to_string(),
+                },
+            ),
+
+            Model(model::Error::Bill(model::bill::Error::BillNotFound {
+                bill_id,
+            }))
+            | Model(model::Error::Transaction(
+                model::transaction::Error::Bill(
+                    model::bill::Error::BillNotFound { bill_id },
+                ),
+            ))
+            | Model(model::Error::Payment(model::payment::Error::Bill(
+                model::bill::Error::BillNotFound { bill_id },
+            )))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::Bill(
+                    model::bill::Error::BillNotFound { bill_id },
+                ),
+            ))) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND {
+                    entity: "bill",
+                    id: bill_id.to_string(),
+                },
+            ),
+
+            Model(model::Error::Bill(
+                model::bill::Error::BillNotEditable { bill_id, status },
+            ))
+            | Model(model::Error::Transaction(
+                model::transaction::Error::Bill(
+                    model::bill::Error::BillNotEditable { bill_id, status },
+                ),
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::Bill(
+                    model::bill::Error::BillNotEditable { bill_id, status },
+                ),
+            ))) => (
+                StatusCode::CONFLICT,
+                ClientError::BILL_NOT_EDITABLE {
+                    bill_id: bill_id.to_string(),
+                    status: status.to_string(),
+                },
+            ),
+
+            Model(model::Error::Bill(
+                model::bill::Error::BillStatusTransitionInvalid {
+                    from,
+                    to,
+                    ..
+                },
+            )) => (
+                StatusCode::CONFLICT,
+                ClientError::BILL_STATUS_TRANSITION_INVALID {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                },
+            ),
+
+            Model(model::Error::Bill(model::bill::Error::BillNotSettled {
+                outstanding,
+                ..
+            })) => (
+                StatusCode::CONFLICT,
+                ClientError::BILL_NOT_SETTLED {
+                    outstanding: outstanding.to_string(),
+                },
+            ),
+
+            Model(model::Error::Bill(
+                model::bill::Error::BillHasPayments { bill_id },
+            )) => (
+                StatusCode::CONFLICT,
+                ClientError::BILL_HAS_PAYMENTS {
+                    bill_id: bill_id.to_string(),
+                },
+            ),
+
+            Model(model::Error::Payment(
+                model::payment::Error::PaymentBillNotPayable {
+                    bill_id,
+                    status,
+                },
+            )) => (
+                StatusCode::CONFLICT,
+                ClientError::BILL_NOT_PAYABLE {
+                    bill_id: bill_id.to_string(),
+                    status: status.to_string(),
+                },
+            ),
+
+            Model(model::Error::Payment(
+                model::payment::Error::PaymentExceedsOutstanding {
+                    outstanding,
+                    ..
+                },
+            )) => (
+                StatusCode::CONFLICT,
+                ClientError::PAYMENT_EXCEEDS_OUTSTANDING {
+                    outstanding: outstanding.to_string(),
+                },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::TransactionUnitCostMismatch {
+                    expected_seller_cost,
+                    ..
+                },
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::TransactionUnitCostMismatch {
+                    expected_seller_cost,
+                    ..
+                },
+            ))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::UNIT_COST_MISMATCH {
+                    expected: expected_seller_cost.to_string(),
+                },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::TransactionOriginalInvalid {
+                    reason,
+                    ..
+                },
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::TransactionOriginalInvalid {
+                    reason,
+                    ..
+                },
+            ))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::ORIGINAL_TRANSACTION_INVALID { reason },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::TransactionExceedsOriginal {
+                    seller_cost,
+                    ..
+                },
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::TransactionExceedsOriginal {
+                    seller_cost,
+                    ..
+                },
+            ))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::ORIGINAL_TRANSACTION_EXCEEDED {
+                    seller_cost: seller_cost.to_string(),
+                },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::TransactionHasLinked { .. },
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::TransactionHasLinked { .. },
+            ))) => (StatusCode::CONFLICT, ClientError::TRANSACTION_HAS_LINKED),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::TransactionTaxRateAmbiguous {
+                    tags,
+                    ..
+                },
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::TransactionTaxRateAmbiguous {
+                    tags,
+                    ..
+                },
+            ))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::TAX_RATE_AMBIGUOUS { tags: tags.clone() },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::Validation(
+                    validation::Error::Validation { errors },
+                ),
+            ))
+            | Model(model::Error::Tag(model::tag::Error::Validation(
+                validation::Error::Validation { errors },
+            )))
+            | Model(model::Error::Unit(model::unit::Error::Validation(
+                validation::Error::Validation { errors },
+            )))
+            | Model(model::Error::Bill(model::bill::Error::Validation(
+                validation::Error::Validation { errors },
+            )))
+            | Model(model::Error::Seller(model::seller::Error::Validation(
+                validation::Error::Validation { errors },
+            )))
+            | Model(model::Error::Payment(
+                model::payment::Error::Validation(
+                    validation::Error::Validation { errors },
+                ),
+            ))
+            | Model(model::Error::ExchangeRate(
+                model::exchange_rate::Error::Validation(
+                    validation::Error::Validation { errors },
+                ),
+            ))
+            | Model(model::Error::Org(model::org::Error::Validation(
+                validation::Error::Validation { errors },
+            )))
+            | Model(model::Error::User(model::user::Error::Org(
+                model::org::Error::Validation(validation::Error::Validation {
+                    errors,
+                }),
+            )))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::Validation(
+                    validation::Error::Validation { errors },
+                ),
+            )))
+            | Model(model::Error::User(model::user::Error::Validation(
+                validation::Error::Validation { errors },
+            ))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::VALIDATION {
+                    errors: errors.clone(),
+                },
+            ),
+
+            Model(model::Error::Transaction(
+                model::transaction::Error::Pagination(..),
+            ))
+            | Model(model::Error::Bill(model::bill::Error::Pagination(..)))
+            | Model(model::Error::Tag(model::tag::Error::Transaction(
+                model::transaction::Error::Pagination(..),
+            ))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::QUERY_DESERIALIZE {
+                    message: "Invalid cursor for this sort",
+                },
+            ),
+
+            Model(model::Error::ExchangeRate(
+                model::exchange_rate::Error::ExchangeRateNotFound {
+                    from,
+                    to,
+                    date,
+                },
+            ))
+            | Model(model::Error::Bill(model::bill::Error::ExchangeRate(
+                model::exchange_rate::Error::ExchangeRateNotFound {
+                    from,
+                    to,
+                    date,
+                },
+            )))
+            | Model(model::Error::Seller(
+                model::seller::Error::ExchangeRate(
+                    model::exchange_rate::Error::ExchangeRateNotFound {
+                        from,
+                        to,
+                        date,
+                    },
+                ),
+            ))
+            | Model(model::Error::Payment(
+                model::payment::Error::ExchangeRate(
+                    model::exchange_rate::Error::ExchangeRateNotFound {
+                        from,
+                        to,
+                        date,
+                    },
+                ),
+            )) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::EXCHANGE_RATE_NOT_FOUND {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    date: date.to_string(),
+                },
+            ),
+
             // -- Tera.
             TeraRender(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -223,15 +728,92 @@ impl Error {
 #[serde(tag = "message", content = "detail")]
 #[allow(non_camel_case_types)]
 pub enum ClientError {
-    ROUTE_NOT_EXIST { uri: String },
-    JSON_DESERIALIZE { message: &'static str },
-    FORM_DESERIALIZE { message: &'static str },
-    QUERY_DESERIALIZE { message: &'static str },
-    DATASTAR_QUERY_DESERIALIZATION { message: &'static str },
+    ROUTE_NOT_EXIST {
+        uri: String,
+    },
+    JSON_DESERIALIZE {
+        message: &'static str,
+    },
+    FORM_DESERIALIZE {
+        message: &'static str,
+    },
+    QUERY_DESERIALIZE {
+        message: &'static str,
+    },
+    DATASTAR_QUERY_DESERIALIZATION {
+        message: &'static str,
+    },
     LOGIN_FAIL,
     NO_AUTH,
-    ENTITY_NOT_FOUND { entity: &'static str, id: String },
+    NO_PERMISSION {
+        privilege: String,
+    },
+    EMAIL_NOT_VERIFIED {
+        privilege: String,
+    },
+    VERIFY_EMAIL_FAIL,
+    PWD_RESET_FAIL,
+    PWD_NOT_MATCHING,
+    ENTITY_NOT_FOUND {
+        entity: &'static str,
+        id: String,
+    },
     USER_ALREADY_EXISTS,
+    SELLER_ALREADY_EXISTS,
+    SELLER_MERGE_SAME,
+    TAG_ALREADY_EXISTS,
+    TAG_MERGE_SAME,
+    TAG_PARENT_CYCLE,
+    BILL_NOT_EDITABLE {
+        bill_id: String,
+        status: String,
+    },
+    BILL_STATUS_TRANSITION_INVALID {
+        from: String,
+        to: String,
+    },
+    BILL_NOT_PAYABLE {
+        bill_id: String,
+        status: String,
+    },
+    BILL_NOT_SETTLED {
+        outstanding: String,
+    },
+    BILL_HAS_PAYMENTS {
+        bill_id: String,
+    },
+    PAYMENT_EXCEEDS_OUTSTANDING {
+        outstanding: String,
+    },
+    UNIT_COST_MISMATCH {
+        expected: String,
+    },
+    ORIGINAL_TRANSACTION_INVALID {
+        reason: &'static str,
+    },
+    ORIGINAL_TRANSACTION_EXCEEDED {
+        seller_cost: String,
+    },
+    TRANSACTION_HAS_LINKED,
+    TAX_RATE_AMBIGUOUS {
+        tags: Vec<String>,
+    },
+    VALIDATION {
+        errors: Vec<FieldError>,
+    },
+    EXCHANGE_RATE_NOT_FOUND {
+        from: String,
+        to: String,
+        date: String,
+    },
+    IDEMPOTENCY_KEY_INVALID,
+    IDEMPOTENCY_KEY_REUSED,
+    IDEMPOTENCY_KEY_IN_USE,
+    ORG_MEMBER_ALREADY_EXISTS,
+    ORG_ROLE_REQUIRED {
+        role: String,
+    },
+    ORG_LAST_OWNER,
     SERVICE_ERROR,
     UNSUPPORTED_MEDIA,
 }
@@ -1,21 +1,27 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
 
-use axum::extract::rejection::JsonRejection;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query};
 use axum::{Json, extract::State};
-use lib_core::model::bill::{BillBmc, BillForCreate};
+use lib_core::model::bill::{
+    BillBmc, BillFilter, BillForCreate, BillForUpdate, BillListOptions,
+};
+use lib_core::model::exchange_rate::ReportCurrency;
 use lib_core::model::{self, ModelManager};
 use serde_json::{Value, json};
 use tracing::debug;
 
 pub async fn create_handler(
     State(mm): State<ModelManager>,
+    ctx: CtxW,
     payload_or_error: std::result::Result<Json<BillForCreate>, JsonRejection>,
 ) -> Result<Json<Value>> {
     debug!("{:<12} - api_create_bill_handler", "HANDLER");
 
     let payload = payload_or_error?.0;
 
-    let bill_id = BillBmc::create(&mm, payload)
+    let bill_id = BillBmc::create(&ctx.0, &mm, payload)
         .await
         .map_err(model::Error::from)?;
 
@@ -29,4 +35,173 @@ pub async fn create_handler(
 
     Ok(body)
 }
-// endregion: --- Register
+
+pub async fn list_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    filter: std::result::Result<Query<BillFilter>, QueryRejection>,
+    list_options: std::result::Result<Query<BillListOptions>, QueryRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_bill_handler", "HANDLER");
+
+    let filter = filter
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+    let list_options = list_options
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let page = BillBmc::list(&ctx.0, &mm, filter, list_options)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "bills": page.items,
+            "nextCursor": page.next_cursor
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn get_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+    report: std::result::Result<Query<ReportCurrency>, QueryRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_get_bill_handler", "HANDLER");
+
+    let ReportCurrency { currency } = report
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let bill = BillBmc::get_details(&ctx.0, &mm, &bill_id, currency)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "bill": bill
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn update_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+    payload_or_error: std::result::Result<Json<BillForUpdate>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_update_bill_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    BillBmc::update(&ctx.0, &mm, &bill_id, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "billId": bill_id
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn delete_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_delete_bill_handler", "HANDLER");
+
+    BillBmc::delete(&ctx.0, &mm, &bill_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn finalize_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_finalize_bill_handler", "HANDLER");
+
+    BillBmc::finalize(&ctx.0, &mm, &bill_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "billId": bill_id
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn mark_paid_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_mark_paid_bill_handler", "HANDLER");
+
+    BillBmc::mark_paid(&ctx.0, &mm, &bill_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "billId": bill_id
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn void_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_void_bill_handler", "HANDLER");
+
+    BillBmc::void(&ctx.0, &mm, &bill_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "billId": bill_id
+        }
+    }));
+
+    Ok(body)
+}
@@ -0,0 +1,64 @@
+use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
+
+use axum::extract::Query;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::{Json, extract::State};
+use lib_core::model::exchange_rate::{
+    ExchangeRateBmc, ExchangeRateFilter, ExchangeRateForSet,
+};
+use lib_core::model::{self, ModelManager};
+use serde_json::{Value, json};
+use tracing::debug;
+
+pub async fn list_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    filter: std::result::Result<Query<ExchangeRateFilter>, QueryRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_exchange_rate_handler", "HANDLER");
+
+    let filter = filter
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let rates = ExchangeRateBmc::list(&ctx.0, &mm, filter)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "rates": rates
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn set_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    payload_or_error: std::result::Result<
+        Json<Vec<ExchangeRateForSet>>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_set_exchange_rate_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    ExchangeRateBmc::set(&ctx.0, &mm, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
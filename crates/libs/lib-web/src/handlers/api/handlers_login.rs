@@ -1,16 +1,22 @@
+use crate::config::web_config;
 use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
 use crate::utils::token;
 
-use axum::extract::rejection::JsonRejection;
+use axum::extract::Query;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
 use axum::{Json, extract::State};
 use lib_auth::pwd::{self, ContentToHash, SchemeStatus};
+use lib_auth::token::{Token, generate_email_token, validate_email_token};
 use lib_core::ctx::Ctx;
-use lib_core::model::user::{UserBmc, UserForCreate};
+use lib_core::mail::Mail;
+use lib_core::model::user::{UserBmc, UserForCreate, UserTyp};
 use lib_core::model::{self, ModelManager};
 use serde::Deserialize;
 use serde_json::{Value, json};
 use tower_cookies::Cookies;
-use tracing::debug;
+use tracing::{debug, warn};
+use uuid::Uuid;
 
 // region:    --- Login
 #[derive(Debug, Deserialize)]
@@ -70,7 +76,8 @@ pub async fn api_login_handler(
     // Create the success body.
     let body = Json(json!({
      "result": {
-      "success": true
+      "success": true,
+      "verified": user.typ != UserTyp::UnVarifiedUser.to_string()
      }
     }));
 
@@ -123,6 +130,18 @@ pub async fn api_register_handler(
         .map_err(model::Error::from)?;
     tracing::debug!("User id: {user_id}");
 
+    // -- Send the verification mail.
+    // NOTE: The user is created by now, so on failure they rather ask for
+    //       another one (`/api/verify-email/resend`) once logged in.
+    let user = UserBmc::get_by_user_id(&root_ctx, &mm, &user_id)
+        .await
+        .map_err(model::Error::from)?;
+    if let Err(ex) =
+        send_verify_email_mail(&mm, &user_id, &user.email, user.token_salt)
+    {
+        warn!("{:<12} - verification mail not sent - {ex:?}", "HANDLER");
+    }
+
     // Create the success body.
     let body = Json(json!({
      "result": {
@@ -133,3 +152,103 @@ pub async fn api_register_handler(
     Ok(body)
 }
 // endregion: --- Register
+
+// region:    --- Verify Email
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailParams {
+    token: String,
+}
+
+/// The link of the verification mail.
+pub async fn api_verify_email_handler(
+    State(mm): State<ModelManager>,
+    params: std::result::Result<Query<VerifyEmailParams>, QueryRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_verify_email_handler", "HANDLER");
+
+    let VerifyEmailParams { token } = params
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let token: Token = token
+        .parse()
+        .map_err(|_| Error::VerifyEmailFailTokenInvalid)?;
+
+    let root_ctx = Ctx::root_ctx();
+
+    // -- Get the user.
+    let user = UserBmc::first_by_user_id(&root_ctx, &mm, &token.ident)
+        .await
+        .map_err(model::Error::from)?
+        .ok_or(Error::VerifyEmailFailTokenInvalid)?;
+
+    // -- Validate the token.
+    validate_email_token(&token, user.token_salt)
+        .map_err(|_| Error::VerifyEmailFailTokenInvalid)?;
+
+    UserBmc::verify_email(&root_ctx, &mm, &user.user_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+     "result": {
+      "success": true
+     }
+    }));
+
+    Ok(body)
+}
+
+/// Send another verification mail to the user of the ctx, unless already
+/// verified.
+pub async fn api_verify_email_resend_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_verify_email_resend_handler", "HANDLER");
+
+    let user = UserBmc::get_by_user_id(&Ctx::root_ctx(), &mm, ctx.0.user_id())
+        .await
+        .map_err(model::Error::from)?;
+
+    let verified = user.typ != UserTyp::UnVarifiedUser.to_string();
+    if !verified {
+        send_verify_email_mail(
+            &mm,
+            &user.user_id,
+            &user.email,
+            user.token_salt,
+        )?;
+    }
+
+    // Create the success body.
+    let body = Json(json!({
+     "result": {
+      "success": true,
+      "verified": verified
+     }
+    }));
+
+    Ok(body)
+}
+
+fn send_verify_email_mail(
+    mm: &ModelManager,
+    user_id: &str,
+    email: &str,
+    token_salt: Uuid,
+) -> Result<()> {
+    let token = generate_email_token(user_id, token_salt)?;
+    let link =
+        format!("{}/api/verify-email?token={token}", web_config().BASE_URL);
+
+    mm.mailer().send(&Mail {
+        to: email.to_string(),
+        subject: "Verify your email".to_string(),
+        body: format!("Open the link below to verify your email:\r\n{link}"),
+    })?;
+
+    Ok(())
+}
+// endregion: --- Verify Email
@@ -0,0 +1,175 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+
+use axum::extract::Path;
+use axum::extract::rejection::JsonRejection;
+use axum::{Json, extract::State};
+use lib_core::model::org::{
+    OrgBmc, OrgForCreate, OrgMemberForAdd, OrgMemberForUpdate,
+};
+use lib_core::model::{self, ModelManager};
+use serde_json::{Value, json};
+use tracing::debug;
+
+pub async fn create_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    payload_or_error: std::result::Result<Json<OrgForCreate>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_create_org_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    let org_id = OrgBmc::create(&ctx.0, &mm, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "orgId": org_id
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn list_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_org_handler", "HANDLER");
+
+    let orgs = OrgBmc::list(&ctx.0, &mm)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "orgs": orgs
+        }
+    }));
+
+    Ok(body)
+}
+
+/// The switch applies from the next request on.
+pub async fn switch_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(org_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_switch_org_handler", "HANDLER");
+
+    OrgBmc::switch(&ctx.0, &mm, &org_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "orgId": org_id
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn list_members_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(org_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_org_members_handler", "HANDLER");
+
+    let members = OrgBmc::list_members(&ctx.0, &mm, &org_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "members": members
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn add_member_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(org_id): Path<String>,
+    payload_or_error: std::result::Result<Json<OrgMemberForAdd>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_add_org_member_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    let member = OrgBmc::add_member(&ctx.0, &mm, &org_id, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "member": member
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn update_member_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path((org_id, user_id)): Path<(String, String)>,
+    payload_or_error: std::result::Result<
+        Json<OrgMemberForUpdate>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_update_org_member_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    OrgBmc::update_member(&ctx.0, &mm, &org_id, &user_id, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn remove_member_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path((org_id, user_id)): Path<(String, String)>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_remove_org_member_handler", "HANDLER");
+
+    OrgBmc::remove_member(&ctx.0, &mm, &org_id, &user_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
@@ -0,0 +1,216 @@
+use crate::config::web_config;
+use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
+use crate::utils::token;
+
+use axum::extract::rejection::JsonRejection;
+use axum::{Json, extract::State};
+use lib_auth::pwd::{self, ContentToHash};
+use lib_auth::token::{
+    Token, generate_pwd_reset_token, validate_pwd_reset_token,
+};
+use lib_core::ctx::Ctx;
+use lib_core::mail::Mail;
+use lib_core::model::user::{self, UserBmc};
+use lib_core::model::{self, ModelManager};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tower_cookies::Cookies;
+use tracing::debug;
+use uuid::Uuid;
+
+// region:    --- Forgot
+#[derive(Debug, Deserialize)]
+pub struct PwdForgotPayload {
+    email: String,
+}
+
+/// Mail a reset token to the user of `email`. Succeeds for an unknown email
+/// too, so that it does not tell which ones are registered.
+pub async fn api_pwd_forgot_handler(
+    State(mm): State<ModelManager>,
+    payload_or_error: std::result::Result<
+        Json<PwdForgotPayload>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_pwd_forgot_handler", "HANDLER");
+
+    let PwdForgotPayload { email } = payload_or_error?.0;
+
+    let root_ctx = Ctx::root_ctx();
+
+    let user = UserBmc::first_by_email(&root_ctx, &mm, &email)
+        .await
+        .map_err(model::Error::from)?;
+
+    if let Some(user) = user {
+        // -- Rotate the token salt, so only the last reset token is valid.
+        let token_salt =
+            UserBmc::rotate_token_salt(&root_ctx, &mm, &user.user_id)
+                .await
+                .map_err(model::Error::from)?;
+
+        send_pwd_reset_mail(&mm, &user.user_id, &user.email, token_salt)?;
+    }
+
+    // Create the success body.
+    let body = Json(json!({
+     "result": {
+      "success": true
+     }
+    }));
+
+    Ok(body)
+}
+
+fn send_pwd_reset_mail(
+    mm: &ModelManager,
+    user_id: &str,
+    email: &str,
+    token_salt: Uuid,
+) -> Result<()> {
+    let token = generate_pwd_reset_token(user_id, token_salt)?;
+
+    mm.mailer().send(&Mail {
+        to: email.to_string(),
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Reset your password at {}/api/password/reset with the token:\r\n{token}\r\n\r\nIgnore this mail if you did not ask for it.",
+            web_config().BASE_URL
+        ),
+    })?;
+
+    Ok(())
+}
+// endregion: --- Forgot
+
+// region:    --- Reset
+#[derive(Debug, Deserialize)]
+pub struct PwdResetPayload {
+    token: String,
+    password: String,
+}
+
+pub async fn api_pwd_reset_handler(
+    State(mm): State<ModelManager>,
+    payload_or_error: std::result::Result<Json<PwdResetPayload>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_pwd_reset_handler", "HANDLER");
+
+    let PwdResetPayload {
+        token,
+        password: pwd_clear,
+    } = payload_or_error?.0;
+
+    let token: Token =
+        token.parse().map_err(|_| Error::PwdResetFailTokenInvalid)?;
+
+    let root_ctx = Ctx::root_ctx();
+
+    // -- Get the user.
+    let user = UserBmc::first_by_user_id(&root_ctx, &mm, &token.ident)
+        .await
+        .map_err(model::Error::from)?
+        .ok_or(Error::PwdResetFailTokenInvalid)?;
+
+    // -- Validate the token.
+    validate_pwd_reset_token(&token, user.token_salt)
+        .map_err(|_| Error::PwdResetFailTokenInvalid)?;
+
+    // -- Reset, which also uses up the token.
+    UserBmc::reset_pwd(
+        &root_ctx,
+        &mm,
+        &user.user_id,
+        user.token_salt,
+        &pwd_clear,
+    )
+    .await
+    .map_err(|ex| match ex {
+        user::Error::UserTokenSaltChanged { .. } => {
+            Error::PwdResetFailTokenInvalid
+        }
+        ex => model::Error::from(ex).into(),
+    })?;
+
+    // Create the success body.
+    let body = Json(json!({
+     "result": {
+      "success": true
+     }
+    }));
+
+    Ok(body)
+}
+// endregion: --- Reset
+
+// region:    --- Change
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PwdChangePayload {
+    current_password: String,
+    password: String,
+}
+
+/// Every other session of the user is logged off, this one gets a new token.
+pub async fn api_pwd_change_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    cookies: Cookies,
+    payload_or_error: std::result::Result<
+        Json<PwdChangePayload>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_pwd_change_handler", "HANDLER");
+
+    let PwdChangePayload {
+        current_password,
+        password: pwd_clear,
+    } = payload_or_error?.0;
+
+    let root_ctx = Ctx::root_ctx();
+
+    // -- Get the user.
+    let user = UserBmc::get_by_user_id(&root_ctx, &mm, ctx.0.user_id())
+        .await
+        .map_err(model::Error::from)?;
+    let user_id = user.user_id;
+
+    // -- Validate the current password.
+    pwd::validate_pwd(
+        ContentToHash {
+            salt: user.pwd_salt,
+            content: current_password,
+        },
+        user.pwd,
+    )
+    .await
+    .map_err(|_| Error::PwdChangeFailPwdNotMatching {
+        user_id: user_id.clone(),
+    })?;
+
+    let token_salt = UserBmc::reset_pwd(
+        &root_ctx,
+        &mm,
+        &user_id,
+        user.token_salt,
+        &pwd_clear,
+    )
+    .await
+    .map_err(model::Error::from)?;
+
+    // -- Set web token.
+    token::set_token_cookie(&cookies, &user_id, token_salt)?;
+
+    // Create the success body.
+    let body = Json(json!({
+     "result": {
+      "success": true
+     }
+    }));
+
+    Ok(body)
+}
+// endregion: --- Change
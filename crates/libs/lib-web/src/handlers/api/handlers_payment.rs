@@ -0,0 +1,60 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+
+use axum::extract::Path;
+use axum::extract::rejection::JsonRejection;
+use axum::{Json, extract::State};
+use lib_core::model::payment::{PaymentBmc, PaymentForCreate};
+use lib_core::model::{self, ModelManager};
+use serde_json::{Value, json};
+use tracing::debug;
+
+pub async fn create_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+    payload_or_error: std::result::Result<
+        Json<PaymentForCreate>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_create_payment_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    let payment_id = PaymentBmc::create(&ctx.0, &mm, &bill_id, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "paymentId": payment_id
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn list_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(bill_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_payment_handler", "HANDLER");
+
+    let payments = PaymentBmc::get_by_bill_id(&ctx.0, &mm, &bill_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "payments": payments
+        }
+    }));
+
+    Ok(body)
+}
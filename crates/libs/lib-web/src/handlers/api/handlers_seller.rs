@@ -1,14 +1,22 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
 
-use axum::extract::rejection::JsonRejection;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query};
 use axum::{Json, extract::State};
-use lib_core::model::seller::{SellerBmc, SellerForCreate};
+use lib_core::model::bill::BillBmc;
+use lib_core::model::exchange_rate::ReportCurrency;
+use lib_core::model::seller::{
+    SellerBmc, SellerForCreate, SellerForUpdate, SellerLedgerFilter,
+};
 use lib_core::model::{self, ModelManager};
+use serde::Deserialize;
 use serde_json::{Value, json};
 use tracing::debug;
 
 pub async fn create_handler(
     State(mm): State<ModelManager>,
+    ctx: CtxW,
     payload_or_error: std::result::Result<
         Json<Vec<SellerForCreate>>,
         JsonRejection,
@@ -18,7 +26,7 @@ pub async fn create_handler(
 
     let payload = payload_or_error?.0;
 
-    let sellers = SellerBmc::create(&mm, payload)
+    let sellers = SellerBmc::create(&ctx.0, &mm, payload)
         .await
         .map_err(model::Error::from)?;
 
@@ -32,4 +40,147 @@ pub async fn create_handler(
 
     Ok(body)
 }
+
+pub async fn unpaid_bills_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(seller_id): Path<String>,
+    report: std::result::Result<Query<ReportCurrency>, QueryRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_seller_unpaid_bills_handler", "HANDLER");
+
+    let ReportCurrency { currency } = report
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let bills =
+        BillBmc::list_unpaid_by_seller_id(&ctx.0, &mm, &seller_id, currency)
+            .await
+            .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "bills": bills
+        }
+    }));
+
+    Ok(body)
+}
 // endregion: --- Register
+
+pub async fn ledger_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(seller_id): Path<String>,
+    filter: std::result::Result<Query<SellerLedgerFilter>, QueryRejection>,
+    report: std::result::Result<Query<ReportCurrency>, QueryRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_seller_ledger_handler", "HANDLER");
+
+    let filter = filter
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+    let ReportCurrency { currency } = report
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let ledger =
+        SellerBmc::get_ledger(&ctx.0, &mm, &seller_id, filter, currency)
+            .await
+            .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "ledger": ledger
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn update_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(seller_id): Path<String>,
+    payload_or_error: std::result::Result<Json<SellerForUpdate>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_update_seller_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    SellerBmc::update(&ctx.0, &mm, &seller_id, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    let seller = SellerBmc::get_by_seller_id(&ctx.0, &mm, &seller_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "seller": seller
+        }
+    }));
+
+    Ok(body)
+}
+
+/// Archives the seller, see `SellerBmc::archive`.
+pub async fn delete_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(seller_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_archive_seller_handler", "HANDLER");
+
+    SellerBmc::archive(&ctx.0, &mm, &seller_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSeller {
+    /// The seller kept after the merge.
+    into_seller_id: String,
+}
+
+pub async fn merge_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(seller_id): Path<String>,
+    payload_or_error: std::result::Result<Json<MergeSeller>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_merge_seller_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    SellerBmc::merge(&ctx.0, &mm, &seller_id, &payload.into_seller_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "sellerId": payload.into_seller_id
+        }
+    }));
+
+    Ok(body)
+}
@@ -0,0 +1,108 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+
+use axum::extract::Path;
+use axum::extract::rejection::JsonRejection;
+use axum::{Json, extract::State};
+use lib_core::model::tag::{TagBmc, TagForUpdate};
+use lib_core::model::{self, ModelManager};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::debug;
+
+pub async fn list_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_tag_handler", "HANDLER");
+
+    let tags = TagBmc::list(&ctx.0, &mm)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "tags": tags
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn update_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(name): Path<String>,
+    payload_or_error: std::result::Result<Json<TagForUpdate>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_update_tag_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    TagBmc::update(&ctx.0, &mm, &name, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn delete_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(name): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_delete_tag_handler", "HANDLER");
+
+    TagBmc::delete(&ctx.0, &mm, &name)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTag {
+    /// The tag kept after the merge.
+    into_tag: String,
+}
+
+pub async fn merge_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(name): Path<String>,
+    payload_or_error: std::result::Result<Json<MergeTag>, JsonRejection>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_merge_tag_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    TagBmc::merge(&ctx.0, &mm, &name, &payload.into_tag)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
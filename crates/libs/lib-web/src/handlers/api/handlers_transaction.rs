@@ -1,16 +1,31 @@
 use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
 
-use axum::extract::Query;
 use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query};
 use axum::{Json, extract::State};
-use lib_core::model::transaction::{TransactionBmc, TransactionForCreate};
+use lib_core::config::core_config;
+use lib_core::model::transaction::{
+    TransactionBmc, TransactionFilter, TransactionForCreate,
+    TransactionForUpdate, TransactionListOptions,
+};
+use lib_core::model::unit::UnitBmc;
 use lib_core::model::{self, ModelManager};
 use serde::Deserialize;
 use serde_json::{Value, json};
-use tracing::debug;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceAlertOptions {
+    /// Defaults to the `UNIT_COST_DEVIATION_PCT` config.
+    deviation_threshold_pct: Option<u32>,
+}
 
 pub async fn create_handler(
     State(mm): State<ModelManager>,
+    ctx: CtxW,
+    options: std::result::Result<Query<PriceAlertOptions>, QueryRejection>,
     payload_or_error: std::result::Result<
         Json<Vec<TransactionForCreate>>,
         JsonRejection,
@@ -18,17 +33,48 @@ pub async fn create_handler(
 ) -> Result<Json<Value>> {
     debug!("{:<12} - api_create_transaction_handler", "HANDLER");
 
+    let threshold_pct = options
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0
+        .deviation_threshold_pct
+        .unwrap_or(core_config().UNIT_COST_DEVIATION_PCT);
     let payload = payload_or_error?.0;
 
-    let transactions = TransactionBmc::create(&mm, payload)
+    let transactions = TransactionBmc::create(&ctx.0, &mm, payload)
         .await
         .map_err(model::Error::from)?;
 
+    // -- Flag the unit costs far from the recent median (price hikes).
+    // NOTE: Best effort, the transactions are committed by now, so failing
+    //       the response would only make the client create them again.
+    let transaction_ids = transactions
+        .iter()
+        .filter(|v| v.unit_cost.is_some())
+        .map(|v| v.transaction_id.clone())
+        .collect::<Vec<_>>();
+    let price_alerts = match UnitBmc::check_cost_deviations(
+        &ctx.0,
+        &mm,
+        &transaction_ids,
+        threshold_pct,
+    )
+    .await
+    {
+        Ok(deviations) => {
+            deviations.into_iter().filter(|v| v.flagged).collect()
+        }
+        Err(ex) => {
+            warn!("{:<12} - price alerts not checked - {ex:?}", "HANDLER");
+            Vec::new()
+        }
+    };
+
     // Create the success body.
     let body = Json(json!({
         "result": {
             "success": true,
-            "transactions": transactions
+            "transactions": transactions,
+            "priceAlerts": price_alerts
         }
     }));
 
@@ -40,24 +86,87 @@ pub struct GetTransactions {
     ids: Option<String>,
 }
 
+/// `GET /api/transaction?ids=a,b` fetches the given transactions, otherwise
+/// lists them with the `TransactionFilter` and `TransactionListOptions`
+/// query params.
 #[axum::debug_handler]
 pub async fn get_handler(
     State(mm): State<ModelManager>,
+    ctx: CtxW,
     query: std::result::Result<Query<GetTransactions>, QueryRejection>,
+    filter: std::result::Result<Query<TransactionFilter>, QueryRejection>,
+    list_options: std::result::Result<
+        Query<TransactionListOptions>,
+        QueryRejection,
+    >,
 ) -> Result<Json<Value>> {
     debug!("{:<12} - api_get_transaction_handler", "HANDLER");
 
-    let transaction_ids = query
+    let ids = query
         .map_err(|e| Error::QueryDeserialization(e.to_string()))?
         .0
-        .ids
-        .ok_or(Error::UnsupportedMedia)?
-        .split(",")
-        .map(ToOwned::to_owned)
-        .collect();
-
-    let transactions =
-        TransactionBmc::get_by_transaction_ids(&mm, transaction_ids)
+        .ids;
+
+    if let Some(ids) = ids {
+        let transaction_ids = ids.split(",").map(ToOwned::to_owned).collect();
+
+        let transactions = TransactionBmc::get_by_transaction_ids(
+            &ctx.0,
+            &mm,
+            transaction_ids,
+        )
+        .await
+        .map_err(model::Error::from)?;
+
+        // Create the success body.
+        let body = Json(json!({
+            "result": {
+                "success": true,
+                "transactions": transactions
+            }
+        }));
+
+        return Ok(body);
+    }
+
+    let filter = filter
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+    let list_options = list_options
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let page = TransactionBmc::list(&ctx.0, &mm, filter, list_options)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "transactions": page.items,
+            "nextCursor": page.next_cursor
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn update_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(transaction_id): Path<String>,
+    payload_or_error: std::result::Result<
+        Json<TransactionForUpdate>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_update_transaction_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    let transaction =
+        TransactionBmc::update(&ctx.0, &mm, &transaction_id, payload)
             .await
             .map_err(model::Error::from)?;
 
@@ -65,7 +174,28 @@ pub async fn get_handler(
     let body = Json(json!({
         "result": {
             "success": true,
-            "transactions": transactions
+            "transaction": transaction
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn delete_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(transaction_id): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_delete_transaction_handler", "HANDLER");
+
+    TransactionBmc::delete(&ctx.0, &mm, &transaction_id)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
         }
     }));
 
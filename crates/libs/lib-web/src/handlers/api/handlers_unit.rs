@@ -0,0 +1,80 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+
+use axum::extract::Path;
+use axum::extract::rejection::JsonRejection;
+use axum::{Json, extract::State};
+use lib_core::model::unit::{UnitBmc, UnitTypeForUpdate};
+use lib_core::model::{self, ModelManager};
+use serde_json::{Value, json};
+use tracing::debug;
+
+pub async fn list_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_list_unit_handler", "HANDLER");
+
+    let unit_types = UnitBmc::list(&ctx.0, &mm)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "unitTypes": unit_types
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn update_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(name): Path<String>,
+    payload_or_error: std::result::Result<
+        Json<UnitTypeForUpdate>,
+        JsonRejection,
+    >,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_update_unit_handler", "HANDLER");
+
+    let payload = payload_or_error?.0;
+
+    UnitBmc::update(&ctx.0, &mm, &name, payload)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+pub async fn price_history_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(unit_type): Path<String>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_unit_price_history_handler", "HANDLER");
+
+    let prices = UnitBmc::get_price_history(&ctx.0, &mm, &unit_type)
+        .await
+        .map_err(model::Error::from)?;
+
+    // Create the success body.
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "prices": prices
+        }
+    }));
+
+    Ok(body)
+}
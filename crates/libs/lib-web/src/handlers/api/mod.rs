@@ -2,9 +2,15 @@ use crate::error::{Error, Result};
 use axum::http::Uri;
 
 pub mod handlers_bill;
+pub mod handlers_exchange_rate;
 pub mod handlers_login;
+pub mod handlers_org;
+pub mod handlers_password;
+pub mod handlers_payment;
 pub mod handlers_seller;
+pub mod handlers_tag;
 pub mod handlers_transaction;
+pub mod handlers_unit;
 
 pub async fn fallback(uri: Uri) -> Result<()> {
     Err(Error::RouteNotExist(uri.to_string()))
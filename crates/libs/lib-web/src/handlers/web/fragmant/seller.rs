@@ -1,6 +1,7 @@
 use crate::{
     error::Result,
     extractors::{DatastarQuery, DatastarQueryError},
+    middleware::mw_auth::CtxW,
     tera::render_fragmant,
 };
 use axum::{extract::State, response::IntoResponse};
@@ -15,11 +16,12 @@ pub struct Search {
 
 pub async fn search(
     State(mm): State<ModelManager>,
+    ctx: CtxW,
     query: std::result::Result<DatastarQuery<Search>, DatastarQueryError>,
 ) -> Result<impl IntoResponse> {
     let name = query?.0.search;
 
-    let sellers = SellerBmc::search_by_name(&mm, &name, None)
+    let sellers = SellerBmc::search_by_name(&ctx.0, &mm, &name, None)
         .await
         .map_err(model::Error::from)?;
 
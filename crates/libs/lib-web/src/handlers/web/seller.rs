@@ -1,15 +1,24 @@
-use crate::{error::Result, tera::render};
+use crate::{
+    error::{Error, Result},
+    middleware::mw_auth::CtxW,
+    tera::render,
+};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query};
 use axum::{extract::State, response::IntoResponse};
-use lib_core::model::{self, ModelManager, seller::SellerBmc};
+use lib_core::model::exchange_rate::ReportCurrency;
+use lib_core::model::seller::{SellerBmc, SellerLedgerFilter};
+use lib_core::model::{self, ModelManager};
 use tera::Context;
 use tracing::debug;
 
 pub async fn render_seller(
     State(mm): State<ModelManager>,
+    ctx: CtxW,
 ) -> Result<impl IntoResponse> {
     debug!("{:<12} - web_seller_handler", "HANDLER");
 
-    let sellers = SellerBmc::get_all(&mm, None)
+    let sellers = SellerBmc::get_all(&ctx.0, &mm, None)
         .await
         .map_err(model::Error::from)?;
 
@@ -18,3 +27,34 @@ pub async fn render_seller(
     context.insert("sellers", &sellers);
     render("routes/seller.html", &context).map(IntoResponse::into_response)
 }
+
+pub async fn render_seller_statement(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    Path(seller_id): Path<String>,
+    filter: std::result::Result<Query<SellerLedgerFilter>, QueryRejection>,
+    report: std::result::Result<Query<ReportCurrency>, QueryRejection>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - web_seller_statement_handler", "HANDLER");
+
+    let filter = filter
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+    let (from, to) = (filter.from.clone(), filter.to.clone());
+    let ReportCurrency { currency } = report
+        .map_err(|e| Error::QueryDeserialization(e.to_string()))?
+        .0;
+
+    let ledger =
+        SellerBmc::get_ledger(&ctx.0, &mm, &seller_id, filter, currency)
+            .await
+            .map_err(model::Error::from)?;
+
+    let mut context = Context::new();
+
+    context.insert("ledger", &ledger);
+    context.insert("from", &from);
+    context.insert("to", &to);
+    render("routes/sellerStatement.html", &context)
+        .map(IntoResponse::into_response)
+}
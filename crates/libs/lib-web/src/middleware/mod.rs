@@ -1,3 +1,4 @@
 pub mod mw_auth;
+pub mod mw_idempotency;
 pub mod mw_req_stamp;
 pub mod mw_res_map;
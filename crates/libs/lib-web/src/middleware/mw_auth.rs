@@ -87,9 +87,12 @@ async fn ctx_resolve(mm: ModelManager, cookies: &Cookies) -> CtxExtResult {
         .map_err(|_| CtxExtError::CannotSetTokenCookie)?;
 
     // -- Create CtxExtResult
-    Ctx::new(&user.user_id)
-        .map(CtxW)
-        .map_err(|ex| CtxExtError::CtxCreateFail(ex.to_string()))
+    match user.org_id.as_deref() {
+        Some(org_id) => Ctx::new_with_org(&user.user_id, org_id),
+        None => Ctx::new(&user.user_id),
+    }
+    .map(CtxW)
+    .map_err(|ex| CtxExtError::CtxCreateFail(ex.to_string()))
 }
 
 // region:    --- Ctx Extractor
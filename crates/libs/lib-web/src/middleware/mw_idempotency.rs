@@ -0,0 +1,123 @@
+use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
+use axum::body::{self, Body};
+use axum::extract::{OriginalUri, State};
+use axum::http::{HeaderValue, Request, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use lib_core::model::idempotency::{
+    IdempotencyBegin, IdempotencyBmc, StoredResponse,
+};
+use lib_core::model::{self, ModelManager};
+use tracing::debug;
+
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+pub const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Same as the default limit of the axum `Json` extractor.
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Replay the first response of a create request sent again with the same
+/// `Idempotency-Key` header. Requests without the header go through, the
+/// ones with the header require a ctx since the keys are scoped per user.
+///
+/// Only the successful responses are stored, a failed request releases its
+/// key so it can be retried.
+pub async fn mw_idempotency(
+    State(mm): State<ModelManager>,
+    ctx: Result<CtxW>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    debug!("{:<12} - mw_idempotency", "MIDDLEWARE");
+
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY) else {
+        return Ok(next.run(req).await);
+    };
+    let CtxW(ctx) = ctx?;
+    let key = key
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && v.len() <= MAX_KEY_LEN)
+        .ok_or(Error::IdempotencyKeyInvalid)?
+        .to_string();
+
+    let (parts, req_body) = req.into_parts();
+    let req_body = body::to_bytes(req_body, MAX_BODY_SIZE)
+        .await
+        .map_err(|e| Error::JsonDeserialization(e.to_string()))?;
+
+    // NOTE: The uri of a nested router is stripped of the nest prefix.
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map_or(parts.uri.path(), |v| v.path())
+        .to_string();
+
+    match IdempotencyBmc::begin(
+        &ctx,
+        &mm,
+        &key,
+        parts.method.as_str(),
+        &path,
+        &req_body,
+    )
+    .await
+    .map_err(model::Error::from)?
+    {
+        IdempotencyBegin::Replay(StoredResponse { status, body }) => {
+            let status =
+                StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK);
+            let mut res = (status, body).into_response();
+            let headers = res.headers_mut();
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            headers
+                .insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+
+            return Ok(res);
+        }
+        IdempotencyBegin::New => (),
+    }
+
+    let res = next
+        .run(Request::from_parts(parts, Body::from(req_body)))
+        .await;
+
+    if !res.status().is_success() {
+        IdempotencyBmc::abort(&ctx, &mm, &key)
+            .await
+            .map_err(model::Error::from)?;
+        return Ok(res);
+    }
+
+    let (parts, res_body) = res.into_parts();
+    let res_body = match body::to_bytes(res_body, usize::MAX).await {
+        Ok(res_body) => res_body,
+        Err(ex) => {
+            IdempotencyBmc::abort(&ctx, &mm, &key)
+                .await
+                .map_err(model::Error::from)?;
+            return Err(Error::IdempotencyResponseBody(ex.to_string()));
+        }
+    };
+
+    IdempotencyBmc::complete(
+        &ctx,
+        &mm,
+        &key,
+        StoredResponse {
+            status: parts.status.as_u16() as i16,
+            body: res_body.to_vec(),
+        },
+    )
+    .await
+    .map_err(model::Error::from)?;
+
+    Ok(Response::from_parts(parts, Body::from(res_body)))
+}
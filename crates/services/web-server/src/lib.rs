@@ -34,10 +34,7 @@ pub async fn routes() -> Result<Router> {
                     mm.clone(),
                     mw_ctx_resolver,
                 ))
-                .layer(middleware::map_response(mw_reponse_map)), //
-                                                                  // .layer(middleware::from_fn(
-                                                                  //     lib_web::middleware::mw_auth::mw_ctx_require,
-                                                                  // )),
+                .layer(middleware::map_response(mw_reponse_map)),
         )
         .nest("/static", routes_static::server_assets())
         .route_service("/favicon.ico", routes_static::favicon())
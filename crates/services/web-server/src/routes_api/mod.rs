@@ -4,6 +4,8 @@ use lib_web::handlers::api::fallback;
 
 // region:    --- Modules
 mod routes_login;
+mod routes_org;
+mod routes_password;
 mod routes_transaction;
 
 // endregion: --- Modules
@@ -11,6 +13,8 @@ mod routes_transaction;
 pub fn routes(mm: ModelManager) -> Router {
     Router::new()
         .merge(routes_transaction::routes(mm.clone()))
+        .merge(routes_org::routes(mm.clone()))
+        .merge(routes_password::routes(mm.clone()))
         .merge(routes_login::routes(mm))
         .fallback(fallback)
 }
@@ -1,5 +1,5 @@
 use axum::Router;
-use axum::routing::post;
+use axum::routing::{get, post};
 use lib_core::model::ModelManager;
 use lib_web::handlers::api::handlers_login;
 
@@ -8,5 +8,14 @@ pub fn routes(mm: ModelManager) -> Router {
         .route("/login", post(handlers_login::api_login_handler))
         .route("/logoff", post(handlers_login::api_logoff_handler))
         .route("/register", post(handlers_login::api_register_handler))
+        .route(
+            "/verify-email",
+            get(handlers_login::api_verify_email_handler),
+        )
+        // NOTE: Requires the ctx, through the `CtxW` extractor.
+        .route(
+            "/verify-email/resend",
+            post(handlers_login::api_verify_email_resend_handler),
+        )
         .with_state(mm)
 }
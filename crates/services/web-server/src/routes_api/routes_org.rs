@@ -0,0 +1,27 @@
+use axum::Router;
+use axum::middleware;
+use axum::routing::{get, patch, post};
+use lib_core::model::ModelManager;
+use lib_web::handlers::api::handlers_org;
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new()
+        .route(
+            "/org",
+            get(handlers_org::list_handler).post(handlers_org::create_handler),
+        )
+        .route("/org/{id}/switch", post(handlers_org::switch_handler))
+        .route(
+            "/org/{id}/member",
+            get(handlers_org::list_members_handler)
+                .post(handlers_org::add_member_handler),
+        )
+        .route(
+            "/org/{id}/member/{user_id}",
+            patch(handlers_org::update_member_handler)
+                .delete(handlers_org::remove_member_handler),
+        )
+        .route_layer(middleware::from_fn(mw_ctx_require))
+        .with_state(mm)
+}
@@ -0,0 +1,22 @@
+use axum::Router;
+use axum::routing::post;
+use lib_core::model::ModelManager;
+use lib_web::handlers::api::handlers_password;
+
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new()
+        .route(
+            "/password/forgot",
+            post(handlers_password::api_pwd_forgot_handler),
+        )
+        .route(
+            "/password/reset",
+            post(handlers_password::api_pwd_reset_handler),
+        )
+        // NOTE: Requires the ctx, through the `CtxW` extractor.
+        .route(
+            "/password/change",
+            post(handlers_password::api_pwd_change_handler),
+        )
+        .with_state(mm)
+}
@@ -1,18 +1,83 @@
 use axum::Router;
-use axum::routing::post;
+use axum::middleware;
+use axum::routing::{get, patch, post};
 use lib_core::model::ModelManager;
 use lib_web::handlers::api::{
-    handlers_bill, handlers_seller, handlers_transaction,
+    handlers_bill, handlers_exchange_rate, handlers_payment, handlers_seller,
+    handlers_tag, handlers_transaction, handlers_unit,
 };
+use lib_web::middleware::mw_auth::mw_ctx_require;
+use lib_web::middleware::mw_idempotency::mw_idempotency;
 
 pub fn routes(mm: ModelManager) -> Router {
     Router::new()
         .route(
             "/transaction",
             post(handlers_transaction::create_handler)
+                .layer(middleware::from_fn_with_state(
+                    mm.clone(),
+                    mw_idempotency,
+                ))
                 .get(handlers_transaction::get_handler),
         )
-        .route("/bill", post(handlers_bill::create_handler))
+        .route(
+            "/transaction/{id}",
+            patch(handlers_transaction::update_handler)
+                .delete(handlers_transaction::delete_handler),
+        )
+        .route(
+            "/bill",
+            post(handlers_bill::create_handler)
+                .layer(middleware::from_fn_with_state(
+                    mm.clone(),
+                    mw_idempotency,
+                ))
+                .get(handlers_bill::list_handler),
+        )
+        .route(
+            "/bill/{id}",
+            get(handlers_bill::get_handler)
+                .patch(handlers_bill::update_handler)
+                .delete(handlers_bill::delete_handler),
+        )
+        .route("/bill/{id}/finalize", post(handlers_bill::finalize_handler))
+        .route("/bill/{id}/paid", post(handlers_bill::mark_paid_handler))
+        .route("/bill/{id}/void", post(handlers_bill::void_handler))
+        .route(
+            "/bill/{id}/payment",
+            post(handlers_payment::create_handler)
+                .get(handlers_payment::list_handler),
+        )
         .route("/seller", post(handlers_seller::create_handler))
+        .route(
+            "/seller/{id}",
+            patch(handlers_seller::update_handler)
+                .delete(handlers_seller::delete_handler),
+        )
+        .route("/seller/{id}/merge", post(handlers_seller::merge_handler))
+        .route(
+            "/seller/{id}/unpaid-bills",
+            get(handlers_seller::unpaid_bills_handler),
+        )
+        .route("/seller/{id}/ledger", get(handlers_seller::ledger_handler))
+        .route("/tag", get(handlers_tag::list_handler))
+        .route(
+            "/tag/{name}",
+            patch(handlers_tag::update_handler)
+                .delete(handlers_tag::delete_handler),
+        )
+        .route("/tag/{name}/merge", post(handlers_tag::merge_handler))
+        .route(
+            "/exchange-rate",
+            get(handlers_exchange_rate::list_handler)
+                .put(handlers_exchange_rate::set_handler),
+        )
+        .route("/unit", get(handlers_unit::list_handler))
+        .route("/unit/{name}", patch(handlers_unit::update_handler))
+        .route(
+            "/unit/{name}/history",
+            get(handlers_unit::price_history_handler),
+        )
+        .route_layer(middleware::from_fn(mw_ctx_require))
         .with_state(mm.clone())
 }
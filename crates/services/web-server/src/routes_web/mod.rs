@@ -1,6 +1,7 @@
-use axum::{Router, routing::get};
+use axum::{Router, middleware, routing::get};
 use lib_core::model::ModelManager;
 use lib_web::handlers::web::{auth, seller};
+use lib_web::middleware::mw_auth::mw_ctx_require;
 
 // region:    --- Modules
 mod routes_fragmant;
@@ -9,10 +10,16 @@ pub mod routes_static;
 // endregion: --- Modules
 
 pub fn routes(mm: ModelManager) -> Router {
+    // NOTE: The `route_layer` only applies to the routes above it.
     Router::new()
+        .route("/seller", get(seller::render_seller))
+        .route(
+            "/seller/{id}/statement",
+            get(seller::render_seller_statement),
+        )
+        .route_layer(middleware::from_fn(mw_ctx_require))
         .route("/login", get(auth::render_login))
         .route("/register", get(auth::render_register))
-        .route("/seller", get(seller::render_seller))
         .nest_service("/fragmant", routes_fragmant::routes(mm.clone()))
         .with_state(mm)
 }
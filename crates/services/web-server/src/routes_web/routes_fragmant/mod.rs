@@ -1,9 +1,11 @@
-use axum::{Router, routing::get};
+use axum::{Router, middleware, routing::get};
 use lib_core::model::ModelManager;
 use lib_web::handlers::web::fragmant::seller;
+use lib_web::middleware::mw_auth::mw_ctx_require;
 
 pub fn routes(mm: ModelManager) -> Router {
     Router::new()
         .route("/seller/search", get(seller::search))
+        .route_layer(middleware::from_fn(mw_ctx_require))
         .with_state(mm)
 }
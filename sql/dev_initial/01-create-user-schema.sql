@@ -51,3 +51,202 @@ create table "password_auth" (
     on update cascade
     on delete cascade
 );
+
+-- Serial id of the user `user_id` (e.g., of the request ctx), null when
+-- there is no such user.
+create function user_serial_id(user_id varchar) returns bigint
+language sql stable as $$
+  select u.serial_id from users u where u.user_id = user_serial_id.user_id
+$$;
+
+-- Access control (PBAC)
+-- Privileges (e.g., `bill.finalize`) are grouped into roles. A role is
+-- assigned to a user for all the organisations (`user_role`), or to a member
+-- of an organisation (`org_member.role`).
+
+create table "privilege" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  name varchar(64) not null unique
+);
+
+create table "role" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  name varchar(32) not null unique
+);
+
+create table "role_privilege" (
+  role_serial_id bigint not null,
+  privilege_serial_id bigint not null,
+
+  primary key (role_serial_id, privilege_serial_id),
+  foreign key(role_serial_id)
+    references role (serial_id)
+    on update cascade
+    on delete cascade,
+  foreign key(privilege_serial_id)
+    references privilege (serial_id)
+    on update cascade
+    on delete cascade
+);
+
+create table "user_role" (
+  user_serial_id bigint not null,
+  role_serial_id bigint not null,
+
+  primary key (user_serial_id, role_serial_id),
+  foreign key(user_serial_id)
+    references users (serial_id)
+    on update cascade
+    on delete cascade,
+  foreign key(role_serial_id)
+    references role (serial_id)
+    on update cascade
+    on delete cascade
+);
+
+-- NOTE: Must match `acs::Privilege`.
+insert into "privilege" (name) values
+    ('seller.read'),
+    ('seller.create'),
+    ('seller.update'),
+    ('seller.merge'),
+    ('bill.read'),
+    ('bill.create'),
+    ('bill.update'),
+    ('bill.delete'),
+    ('bill.finalize'),
+    ('bill.void'),
+    ('bill.pay'),
+    ('payment.create'),
+    ('transaction.read'),
+    ('transaction.create'),
+    ('transaction.update'),
+    ('transaction.delete'),
+    ('tag.read'),
+    ('tag.update'),
+    ('tag.merge'),
+    ('tag.delete'),
+    ('unit.read'),
+    ('unit.update'),
+    ('exchange_rate.read'),
+    ('exchange_rate.set'),
+    ('user.admin');
+
+insert into "role" (name) values
+    ('SysAdmin'),
+    ('Owner'),
+    ('Admin'),
+    ('Member'),
+    ('Viewer');
+
+-- SysAdmin: everything, including the users and the data shared by all the
+-- organisations (unit types and exchange rates), Owner and Admin: the books,
+-- Member: the day to day bookkeeping, Viewer: reading.
+insert into "role_privilege" (role_serial_id, privilege_serial_id)
+select r.serial_id, p.serial_id from role r, privilege p
+where r.name = 'SysAdmin'
+   or (r.name in ('Owner', 'Admin')
+       and p.name not in ('user.admin', 'unit.update', 'exchange_rate.set'))
+   or (r.name = 'Member' and (p.name like '%.read' or p.name in (
+        'seller.create', 'seller.update',
+        'bill.create', 'bill.update', 'bill.delete',
+        'transaction.create', 'transaction.update', 'transaction.delete',
+        'tag.update')))
+   or (r.name = 'Viewer' and p.name like '%.read');
+
+-- Organisation
+-- A workspace with its own books (sellers, bills, transactions and tags),
+-- shared by its members.
+
+create table "organisation" (
+  serial_id bigint generated by default as identity (start with 1000) primary key,
+
+  org_id varchar(30) not null unique,
+  name varchar(128) not null,
+
+  -- timestamps
+  ctime timestamp with time zone not null default current_timestamp,
+  mtime timestamp with time zone not null default current_timestamp
+);
+
+create table "org_member" (
+  org_serial_id bigint not null,
+  user_serial_id bigint not null,
+  role varchar(32) not null,
+
+  -- timestamps
+  ctime timestamp with time zone not null default current_timestamp,
+  mtime timestamp with time zone not null default current_timestamp,
+
+  primary key (org_serial_id, user_serial_id),
+  foreign key(role)
+    references role (name)
+    on update cascade
+    on delete restrict,
+  foreign key(org_serial_id)
+    references organisation (serial_id)
+    on update cascade
+    on delete cascade,
+  foreign key(user_serial_id)
+    references users (serial_id)
+    on update cascade
+    on delete cascade
+);
+
+-- The organisation the user works in, only while a member of it.
+alter table "users" add column active_org_serial_id bigint default null
+  references organisation (serial_id)
+    on update cascade
+    on delete set null;
+
+-- Serial id of the organisation `org_id` (e.g., of the request ctx), null
+-- when there is no such organisation.
+create function org_serial_id(org_id varchar) returns bigint
+language sql stable as $$
+  select o.serial_id from organisation o where o.org_id = org_serial_id.org_id
+$$;
+
+-- Whether the user `user_id` is past the email verification, i.e. not a new
+-- `UnVarifiedUser` registration.
+create function user_verified(user_id varchar) returns boolean
+language sql stable as $$
+  select not exists (
+    select 1 from users u
+    inner join user_type ut on ut.serial_id = u.user_type_serial_id
+    where u.user_id = user_verified.user_id and ut.typ = 'UnVarifiedUser'
+  )
+$$;
+
+-- Whether a role of the user `user_id`, in the organisation `org_id` (may be
+-- null), grants `privilege`, whether or not the user is verified.
+create function role_has_privilege(user_id varchar, org_id varchar, privilege varchar) returns boolean
+language sql stable as $$
+  select exists (
+    select 1 from role_privilege rp
+    inner join privilege p on p.serial_id = rp.privilege_serial_id
+    where p.name = role_has_privilege.privilege
+      and rp.role_serial_id in (
+        select ur.role_serial_id from user_role ur
+        inner join users u on u.serial_id = ur.user_serial_id
+        where u.user_id = role_has_privilege.user_id
+        union all
+        select r.serial_id from org_member om
+        inner join role r on r.name = om.role
+        inner join users u on u.serial_id = om.user_serial_id
+        where u.user_id = role_has_privilege.user_id
+          and om.org_serial_id = org_serial_id(role_has_privilege.org_id)
+      )
+  )
+$$;
+
+-- Whether the user `user_id`, in the organisation `org_id` (may be null),
+-- holds `privilege` through their roles. Until verified, only the `*.read`
+-- ones.
+create function has_privilege(user_id varchar, org_id varchar, privilege varchar) returns boolean
+language sql stable as $$
+  select (has_privilege.privilege like '%.read'
+      or user_verified(has_privilege.user_id))
+    and role_has_privilege(
+      has_privilege.user_id, has_privilege.org_id, has_privilege.privilege
+    )
+$$;
@@ -14,11 +14,16 @@ insert into "password_auth"
         now()
     );
 
--- User demo1
+-- root is the system admin
+insert into "user_role"
+    (user_serial_id, role_serial_id) values
+    (0, (select serial_id from role where name = 'SysAdmin'));
+
+-- User demo1 (verified)
 -- password: welcome
 insert into "users" 
-    (user_id, name, email, ctime, mtime) values 
-    ('demo1', 'demo1', 'demo1@example.com', now(), now());
+    (user_id, user_type_serial_id, name, email, ctime, mtime) values 
+    ('demo1', (select ut.serial_id from user_type ut where ut.typ = 'User'), 'demo1', 'demo1@example.com', now(), now());
 insert into "password_auth" 
     (user_serial_id, pwd, pwd_salt, ctime, mtime) values 
     (
@@ -28,3 +33,20 @@ insert into "password_auth"
         now(),
         now()
     );
+
+-- Organisation of demo1
+insert into "organisation"
+    (org_id, name, ctime, mtime) values
+    ('demo1', 'demo1', now(), now());
+insert into "org_member"
+    (org_serial_id, user_serial_id, role, ctime, mtime) values
+    (
+        (select serial_id from organisation where org_id = 'demo1'),
+        (select serial_id from users where user_id = 'demo1'),
+        'Owner',
+        now(),
+        now()
+    );
+update "users"
+    set active_org_serial_id = (select serial_id from organisation where org_id = 'demo1')
+    where user_id = 'demo1';
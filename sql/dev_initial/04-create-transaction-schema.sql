@@ -1,27 +1,73 @@
+-- Trigram matching for the fuzzy seller search.
+create extension if not exists pg_trgm;
+
+-- The sellers, bills, transactions and tags belong to an organisation
+-- (`org_serial_id`), the other tables are shared.
 create table "seller" (
   serial_id bigint generated by default as identity (start with 1) primary key,
-  name varchar(64) not null unique,
+  org_serial_id bigint not null,
+  name varchar(64) not null,
   seller_id varchar(30) not null unique,
+  phone varchar(32) default null,
+  address varchar(512) default null,
+  tax_id varchar(32) default null,
+  notes varchar(1024) default null,
+  archived_at timestamp with time zone default null,
 
   -- timestamps
   ctime timestamp with time zone not null default current_timestamp,
-  mtime timestamp with time zone not null default current_timestamp
+  mtime timestamp with time zone not null default current_timestamp,
+
+  unique (org_serial_id, name),
+  foreign key(org_serial_id)
+    references organisation (serial_id)
+    on update cascade
+    on delete cascade
+);
+
+create index seller_name_trgm_idx on seller using gin (name gin_trgm_ops);
+
+create table "bill_status" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  name varchar(32) not null unique,
+
+  -- timestamps
+  ctime timestamp with time zone not null default now(),
+  mtime timestamp with time zone not null default now()
 );
 
+insert into "bill_status"
+    (serial_id, name)
+values
+    (1, 'Draft'),
+    (2, 'Finalized'),
+    (3, 'Paid'),
+    (4, 'Void');
+
 create table "bill" (
   serial_id bigint generated by default as identity (start with 1) primary key,
+  org_serial_id bigint not null,
   bill_id varchar(30) not null unique,
   remark varchar(256) default null,
   seller_serial_id bigint default null,
+  bill_status_serial_id bigint not null default 1, -- 1: Draft
 
   -- timestamps
   ctime timestamp with time zone not null default now(),
   mtime timestamp with time zone not null default now(),
 
+  foreign key(org_serial_id)
+    references organisation (serial_id)
+    on update cascade
+    on delete cascade,
   foreign key(seller_serial_id) 
     references seller (serial_id)
     on update cascade
-    on delete cascade
+    on delete cascade,
+  foreign key(bill_status_serial_id)
+    references bill_status (serial_id)
+    on update cascade
+    on delete restrict
 );
 
 create table "payment_method" (
@@ -33,42 +79,184 @@ create table "payment_method" (
   mtime timestamp with time zone not null default now()
 );
 
+-- What a transaction is, `sign` tells whether it adds to (1) or takes from
+-- (-1) what is owed to the seller. The `seller_cost` of an adjustment holds
+-- its own sign, the one of the other kinds is never negative.
+create table "transaction_kind" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  name varchar(32) not null unique,
+  sign smallint not null check (sign in (-1, 1)),
+
+  -- timestamps
+  ctime timestamp with time zone not null default now(),
+  mtime timestamp with time zone not null default now()
+);
+
+insert into "transaction_kind"
+    (serial_id, name, sign)
+values
+    (1, 'Purchase', 1),
+    (2, 'Refund', -1),
+    (3, 'Return', -1),
+    (4, 'Discount', -1),
+    (5, 'Adjustment', 1);
+
+-- ISO 4217 currencies, amounts are in the currency of their row.
+create table "currency" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  code varchar(3) not null unique,
+
+  -- timestamps
+  ctime timestamp with time zone not null default now(),
+  mtime timestamp with time zone not null default now()
+);
+
+insert into "currency"
+    (serial_id, code)
+values
+    (1, 'INR'),
+    (2, 'USD');
+
+-- Price of one `from` unit in `to`, for the day and the following days
+-- without a rate.
+create table "exchange_rate" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  rate_date date not null,
+  from_currency_serial_id bigint not null,
+  to_currency_serial_id bigint not null,
+  rate numeric(18, 8) not null check (rate > 0),
+
+  -- timestamps
+  ctime timestamp with time zone not null default now(),
+  mtime timestamp with time zone not null default now(),
+
+  unique (rate_date, from_currency_serial_id, to_currency_serial_id),
+  foreign key(from_currency_serial_id)
+    references currency (serial_id)
+    on update cascade
+    on delete cascade,
+  foreign key(to_currency_serial_id)
+    references currency (serial_id)
+    on update cascade
+    on delete cascade
+);
+
+-- Latest rate on or before `at_date`, the inverse pair is used when only
+-- it is known. Null when there is no rate.
+create function exchange_rate_at(
+  from_currency bigint, to_currency bigint, at_date date
+) returns numeric language sql stable as $$
+  select case when from_currency = to_currency then 1::numeric else (
+    select r.rate from (
+      select er.rate, er.rate_date, 0 as inverse from exchange_rate er
+      where er.from_currency_serial_id = from_currency
+        and er.to_currency_serial_id = to_currency
+        and er.rate_date <= at_date
+      union all
+      select 1 / er.rate, er.rate_date, 1 from exchange_rate er
+      where er.from_currency_serial_id = to_currency
+        and er.to_currency_serial_id = from_currency
+        and er.rate_date <= at_date
+    ) r
+    order by r.rate_date desc, r.inverse
+    limit 1
+  ) end
+$$;
+
+-- `amount` in `to_currency` at the rate of the (utc) day of `at_time`,
+-- rounded to the cent. Null when there is no rate.
+create function convert_amount(
+  amount numeric, from_currency bigint, to_currency bigint,
+  at_time timestamp with time zone
+) returns numeric language sql stable as $$
+  select round(amount * exchange_rate_at(
+    from_currency, to_currency, (at_time at time zone 'UTC')::date
+  ), 2)
+$$;
+
 create table "transaction" (
   serial_id bigint generated by default as identity (start with 1) primary key,
+  org_serial_id bigint not null,
   transaction_id varchar(30) not null unique,
   name varchar(128) not null,
   remark varchar(256) default null,
   transaction_time timestamp with time zone not null,
   payment_method_serial_id bigint not null,
   seller_cost numeric(12, 2) not null,
+  currency_serial_id bigint not null default 1, -- 1: INR
   bill_serial_id bigint default null,
+  transaction_kind_serial_id bigint not null default 1, -- 1: Purchase
+  -- The purchase refunded, returned, discounted or adjusted.
+  original_transaction_serial_id bigint default null,
+  -- GST included in `seller_cost`, none when `tax_rate` is null.
+  tax_rate numeric(5, 2) default null check (tax_rate between 0 and 100),
+  inter_state boolean not null default false,
+  taxable_value numeric(12, 2) default null,
+  cgst numeric(12, 2) default null,
+  sgst numeric(12, 2) default null,
+  igst numeric(12, 2) default null,
 
   -- timestamps
   ctime timestamp with time zone not null default now(),
   mtime timestamp with time zone not null default now(),
   
+  foreign key(org_serial_id)
+    references organisation (serial_id)
+    on update cascade
+    on delete cascade,
   foreign key(payment_method_serial_id) 
     references payment_method (serial_id)
     on update cascade
     on delete cascade,
+  foreign key(currency_serial_id)
+    references currency (serial_id)
+    on update cascade
+    on delete restrict,
   foreign key(bill_serial_id) 
     references bill (serial_id)
     on update cascade
-    on delete cascade
+    on delete cascade,
+  foreign key(transaction_kind_serial_id)
+    references transaction_kind (serial_id)
+    on update cascade
+    on delete restrict,
+  -- NOTE: Checked at the end of the statement, so a bill can still be
+  --       deleted with both a purchase and its refund.
+  foreign key(original_transaction_serial_id)
+    references transaction (serial_id)
+    on update cascade
+    on delete no action
 );
 
 create table "tag" (
   serial_id bigint generated by default as identity (start with 1) primary key,
-  name varchar(64) not null unique,
+  org_serial_id bigint not null,
+  name varchar(64) not null,
+  parent_serial_id bigint default null,
+  -- Default GST rate of the transactions tagged with it.
+  tax_rate numeric(5, 2) default null check (tax_rate between 0 and 100),
 
   -- timestamps
   ctime timestamp with time zone not null default now(),
-  mtime timestamp with time zone not null default now()
+  mtime timestamp with time zone not null default now(),
+
+  unique (org_serial_id, name),
+  foreign key(org_serial_id)
+    references organisation (serial_id)
+    on update cascade
+    on delete cascade,
+  foreign key(parent_serial_id)
+    references tag (serial_id)
+    on update cascade
+    on delete set null
 );
 
 create table "transaction_tag" (
   transaction_serial_id bigint not null,
   tag_serial_id bigint not null,
+  -- Portion of the `seller_cost` allocated to the tag, the portions of a
+  -- transaction sum up to its `seller_cost`.
+  amount numeric(12, 2) not null,
 
   primary key (transaction_serial_id, tag_serial_id),
   foreign key(transaction_serial_id)
@@ -84,6 +272,8 @@ create table "transaction_tag" (
 create table "unit_type" (
   serial_id bigint generated by default as identity (start with 1) primary key,
   name varchar(64) not null unique,
+  -- Default GST rate of the transactions with this unit type.
+  tax_rate numeric(5, 2) default null check (tax_rate between 0 and 100),
 
   -- timestamps
   ctime timestamp with time zone not null default now(),
@@ -107,6 +297,10 @@ create table "transaction_unit" (
   unit numeric(10, 3) not null,
   unit_cost_serial_id bigint not null,
 
+  foreign key(transaction_serial_id)
+    references transaction (serial_id)
+    on update cascade
+    on delete cascade,
   foreign key(unit_cost_serial_id)
     references unit_cost (serial_id)
     on update cascade
@@ -121,9 +315,64 @@ insert into "payment_method"
     ('Unknown', now(), now());
 
 insert into "seller" 
-    (name, seller_id) values 
+    (org_serial_id, name, seller_id)
+select o.serial_id, s.name, s.seller_id from organisation o, (values
     ('Abc', '0001'),
     ('pqr', '0002'),
     ('hello', '0003'),
-    ('bc', '0004');
+    ('bc', '0004')) as s(name, seller_id)
+where o.org_id = 'demo1';
+
+
+create table "payment" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  payment_id varchar(30) not null unique,
+  bill_serial_id bigint not null,
+  amount numeric(12, 2) not null check (amount > 0),
+  currency_serial_id bigint not null default 1, -- 1: INR
+  payment_time timestamp with time zone not null,
+  payment_method_serial_id bigint not null,
+  reference varchar(128) default null,
+
+  -- timestamps
+  ctime timestamp with time zone not null default now(),
+  mtime timestamp with time zone not null default now(),
+
+  foreign key(bill_serial_id)
+    references bill (serial_id)
+    on update cascade
+    on delete restrict,
+  foreign key(payment_method_serial_id)
+    references payment_method (serial_id)
+    on update cascade
+    on delete cascade,
+  foreign key(currency_serial_id)
+    references currency (serial_id)
+    on update cascade
+    on delete restrict
+);
+
+-- First response of the create requests sent with an `Idempotency-Key`.
+create table "idempotency_key" (
+  serial_id bigint generated by default as identity (start with 1) primary key,
+  user_id varchar(30) not null,
+  -- The active organisation of the request, null without one.
+  org_serial_id bigint default null,
+  key varchar(255) not null,
+  request_hash bytea not null,
+  -- Null while the first request is in progress.
+  response_status smallint default null,
+  response_body bytea default null,
+
+  -- timestamps
+  ctime timestamp with time zone not null default now(),
+
+  unique nulls not distinct (user_id, org_serial_id, key),
+  foreign key(org_serial_id)
+    references organisation (serial_id)
+    on update cascade
+    on delete cascade
+);
 
+-- For the purge of the expired keys.
+create index idempotency_key_ctime_idx on idempotency_key (ctime);